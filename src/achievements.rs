@@ -0,0 +1,133 @@
+// Achievements: goals the player unlocks once and keeps for good
+//
+// Each achievement measures its progress as (current, target) and unlocks
+// when current reaches target. Yes/no conditions use a target of 1. Saves
+// record unlocked achievements by key, so the list can grow or be reordered
+// without changing what old saves have unlocked. Some achievements also
+// multiply all minion production once unlocked.
+
+use crate::GameState;
+
+#[derive(Clone, Debug)]
+pub struct Achievement {
+    pub key: &'static str, // Stable id used in saves
+    pub name: &'static str,
+    pub description: &'static str,
+    pub progress: fn(&GameState) -> (u64, u64),
+    pub reward_multiplier: Option<f64>, // Applied to all production once unlocked
+    pub unlocked: bool,
+}
+
+impl Achievement {
+    fn new(key: &'static str, name: &'static str, description: &'static str, progress: fn(&GameState) -> (u64, u64)) -> Self {
+        Achievement { key, name, description, progress, reward_multiplier: None, unlocked: false }
+    }
+
+    fn rewarding(self, multiplier: f64) -> Self {
+        Achievement { reward_multiplier: Some(multiplier), ..self }
+    }
+
+    pub fn is_met(&self, state: &GameState) -> bool {
+        let (current, target) = (self.progress)(state);
+        current >= target
+    }
+}
+
+fn building_count(state: &GameState, key: &str) -> u64 {
+    state.building(key).map_or(0, |building| building.count)
+}
+
+pub fn definitions() -> Vec<Achievement> {
+    vec![
+        Achievement::new("first_click", "The First Whisper", "Spread your influence for the first time",
+            |state| (state.stats.total_clicks.min(1), 1)),
+        Achievement::new("clicks_1000", "Tireless Preacher", "Spread your influence 1,000 times",
+            |state| (state.stats.total_clicks.min(1_000), 1_000))
+            .rewarding(1.02),
+        Achievement::new("cultists_100", "A Hundred Whisperers", "Own 100 Cultists",
+            |state| (building_count(state, "cursor").min(100), 100))
+            .rewarding(1.02),
+        Achievement::new("elder_ones_100", "Council of Elders", "Own 100 Elder Ones",
+            |state| (building_count(state, "grandma").min(100), 100))
+            .rewarding(1.02),
+        Achievement::new("every_building", "Full Pantheon", "Own one of every minion",
+            |state| {
+                let owned = state.buildings.iter().filter(|building| building.count > 0).count();
+                (owned as u64, state.buildings.len() as u64)
+            })
+            .rewarding(1.05),
+        Achievement::new("every_upgrade", "Curator of the Forbidden", "Acquire every artifact",
+            |state| {
+                let owned = state.upgrades.iter().filter(|upgrade| upgrade.purchased).count();
+                (owned as u64, state.upgrades.len() as u64)
+            })
+            .rewarding(1.05),
+        Achievement::new("lifetime_1m", "Global Reach", "Gather 1,000,000 total converts",
+            |state| (state.lifetime_points.to_u64().min(1_000_000), 1_000_000))
+            .rewarding(1.02),
+        Achievement::new("lifetime_1b", "Cthulhu Rises", "Gather 1,000,000,000 total converts",
+            |state| (state.lifetime_points.to_u64().min(1_000_000_000), 1_000_000_000))
+            .rewarding(1.1),
+        Achievement::new("first_shard", "Beyond the Veil", "Hold an eldritch shard",
+            |state| (state.shards.min(1), 1)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, BigNum};
+
+    fn unlocked(state: &GameState, key: &str) -> bool {
+        state.achievements.iter().any(|achievement| achievement.key == key && achievement.unlocked)
+    }
+
+    #[test]
+    fn reaching_a_threshold_unlocks_the_achievement() {
+        let mut state = GameState::new();
+        state.buildings[0].count = 99;
+        state.points = BigNum::from(1_000_000_000_000u64);
+        state.check_achievements();
+        assert!(!unlocked(&state, "cultists_100"));
+
+        state.apply_action(&Action::BuyBuilding("cursor".to_string()));
+        assert!(unlocked(&state, "cultists_100"));
+        assert_eq!(state.notifications.back().unwrap().text, "Achievement unlocked: A Hundred Whisperers");
+    }
+
+    #[test]
+    fn clicks_and_converts_unlock_their_achievements() {
+        let mut state = GameState::new();
+        state.apply_action(&Action::Click);
+        assert!(unlocked(&state, "first_click"));
+
+        state.lifetime_points = BigNum::from(999_999u64);
+        state.apply_action(&Action::Click);
+        assert!(unlocked(&state, "lifetime_1m"));
+        assert!(!unlocked(&state, "lifetime_1b"));
+    }
+
+    #[test]
+    fn saves_keep_unlocked_achievements_by_key() {
+        let mut state = GameState::new();
+        state.apply_action(&Action::Click);
+        let mut value: serde_json::Value = serde_json::from_str(crate::save::unseal(&state.save_contents().unwrap()).unwrap()).unwrap();
+        assert_eq!(value["achievements"], serde_json::json!(["first_click"]));
+
+        // Keys no longer defined are dropped rather than unlocking something else
+        value["achievements"] = serde_json::json!(["first_click", "retired_achievement"]);
+        let loaded: GameState = serde_json::from_value(value).unwrap();
+        let keys: Vec<&str> = loaded.achievements.iter().filter(|achievement| achievement.unlocked).map(|achievement| achievement.key).collect();
+        assert_eq!(keys, vec!["first_click"]);
+    }
+
+    #[test]
+    fn rewards_multiply_production() {
+        let mut state = GameState::new();
+        state.buildings[1].count = 100;
+        state.check_achievements();
+        // Council of Elders
+        assert_eq!(state.achievement_multiplier(), 1.02);
+        assert!((state.calculate_production_per_second() - 102.0).abs() < 1e-9);
+    }
+}
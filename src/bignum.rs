@@ -0,0 +1,385 @@
+// Follower counts that can grow past u64::MAX
+//
+// A BigNum is mantissa * 10^exponent. Below EXACT_LIMIT the exponent is 0 and
+// the mantissa holds the whole value, so everyday counts and prices stay
+// exact integers. Past it, the mantissa is kept in [EXACT_LIMIT / 10,
+// EXACT_LIMIT) and the exponent grows instead, trading the last digits for
+// range. Every value has exactly one such form, which is what makes ordering
+// a comparison of (exponent, mantissa). Values too large even for that
+// saturate at BigNum::MAX rather than wrapping around to zero.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+
+// Integers below this are exact in an f64
+const EXACT_LIMIT: f64 = 1e15;
+const EXACT_DIGITS: i32 = 15;
+
+// Largest exponent kept, far past any reachable price but with room for the
+// sum of two exponents in a product to stay inside an i32
+const MAX_EXPONENT: i32 = 1_000_000_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Parts")]
+pub struct BigNum {
+    mantissa: f64,
+    exponent: i32,
+}
+
+// A BigNum as saved, which may not be in canonical form if it was edited by hand
+#[derive(Deserialize)]
+struct Parts {
+    mantissa: f64,
+    exponent: i32,
+}
+
+impl From<Parts> for BigNum {
+    fn from(Parts { mantissa, exponent }: Parts) -> Self {
+        BigNum { mantissa: mantissa.floor(), exponent: exponent.max(0) }.normalized()
+    }
+}
+
+impl BigNum {
+    pub const ZERO: BigNum = BigNum { mantissa: 0.0, exponent: 0 };
+    pub const MAX: BigNum = BigNum { mantissa: EXACT_LIMIT - 1.0, exponent: MAX_EXPONENT };
+
+    // Negative and NaN values become zero, positive infinity BigNum::MAX
+    pub fn from_f64(value: f64) -> Self {
+        if value == f64::INFINITY {
+            BigNum::MAX
+        } else if value.is_finite() && value > 0.0 {
+            BigNum { mantissa: value, exponent: 0 }.normalized()
+        } else {
+            BigNum::ZERO
+        }
+    }
+
+    // base^power, computed through logarithms once it leaves the f64 range
+    pub fn pow(base: f64, power: f64) -> Self {
+        let direct = base.powf(power);
+        if direct.is_finite() {
+            return BigNum::from_f64(direct);
+        }
+        BigNum::from_log10(power * base.log10())
+    }
+
+    fn from_log10(log: f64) -> Self {
+        if log >= (MAX_EXPONENT + EXACT_DIGITS) as f64 {
+            return BigNum::MAX;
+        }
+        if !log.is_finite() {
+            return BigNum::ZERO;
+        }
+        let exponent = (log.floor() as i32 - (EXACT_DIGITS - 1)).max(0);
+        BigNum { mantissa: 10f64.powf(log - exponent as f64), exponent }.normalized()
+    }
+
+    pub fn log10(self) -> f64 {
+        self.mantissa.log10() + self.exponent as f64
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.mantissa == 0.0
+    }
+
+    // Rounds down to a whole number; values past EXACT_LIMIT already are
+    pub fn floor(self) -> Self {
+        BigNum { mantissa: self.mantissa.floor(), exponent: self.exponent }
+    }
+
+    // May be infinite for the very largest values
+    pub fn to_f64(self) -> f64 {
+        self.mantissa * 10f64.powi(self.exponent)
+    }
+
+    // Clamped to u64::MAX, for counts that only matter while they are small
+    pub fn to_u64(self) -> u64 {
+        self.to_f64() as u64
+    }
+
+    // The whole mantissa and the exponent, for printing digit by digit
+    pub fn parts(self) -> (u64, i32) {
+        (self.mantissa as u64, self.exponent)
+    }
+
+    // Brings the value back to its one canonical form
+    fn normalized(self) -> Self {
+        let BigNum { mut mantissa, mut exponent } = self;
+        if mantissa == f64::INFINITY || (mantissa > 0.0 && exponent > MAX_EXPONENT) {
+            return BigNum::MAX;
+        }
+        if !mantissa.is_finite() || mantissa <= 0.0 {
+            return BigNum::ZERO;
+        }
+        if mantissa >= EXACT_LIMIT {
+            let shift = mantissa.log10().floor() as i32 - (EXACT_DIGITS - 1);
+            mantissa /= 10f64.powi(shift);
+            exponent += shift;
+            // log10 can land one digit off near powers of ten
+            if mantissa >= EXACT_LIMIT {
+                mantissa /= 10.0;
+                exponent += 1;
+            }
+        } else if exponent > 0 {
+            let room = EXACT_DIGITS - 1 - mantissa.log10().floor() as i32;
+            let shift = room.min(exponent);
+            mantissa *= 10f64.powi(shift);
+            exponent -= shift;
+            if mantissa >= EXACT_LIMIT {
+                mantissa /= 10.0;
+                exponent += 1;
+            }
+        }
+        // Or a digit short, when dividing by a power of ten rounds down
+        if exponent > 0 && mantissa < EXACT_LIMIT / 10.0 {
+            mantissa *= 10.0;
+            exponent -= 1;
+        }
+        if exponent > MAX_EXPONENT {
+            return BigNum::MAX;
+        }
+        // Digits below the mantissa's last place are dropped
+        if exponent > 0 {
+            mantissa = mantissa.floor();
+        }
+        BigNum { mantissa, exponent }
+    }
+
+    // The mantissa of `self` rescaled to `exponent`, which is at least its own
+    fn mantissa_at(self, exponent: i32) -> f64 {
+        self.mantissa / 10f64.powi(exponent - self.exponent)
+    }
+}
+
+impl From<u64> for BigNum {
+    fn from(value: u64) -> Self {
+        BigNum::from_f64(value as f64)
+    }
+}
+
+// Plain digits while exact, "<mantissa>e<exponent>" past that
+impl fmt::Display for BigNum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.exponent == 0 {
+            write!(f, "{:.0}", self.mantissa)
+        } else {
+            write!(f, "{}e{}", self.mantissa as u64, self.exponent)
+        }
+    }
+}
+
+// Reads back what Display writes
+impl std::str::FromStr for BigNum {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once('e') {
+            Some((mantissa, exponent)) => {
+                let mantissa: u64 = mantissa.parse()?;
+                let exponent: i32 = exponent.parse()?;
+                Ok(BigNum { mantissa: mantissa as f64, exponent: exponent.max(0) }.normalized())
+            },
+            None => text.parse::<u64>().map(BigNum::from),
+        }
+    }
+}
+
+// The mantissa is never NaN, so the ordering is total
+impl Eq for BigNum {}
+
+impl Ord for BigNum {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.exponent.cmp(&other.exponent) {
+            Ordering::Equal => self.mantissa.total_cmp(&other.mantissa),
+            // Zero is the only value whose exponent says nothing about its size
+            _ if self.is_zero() || other.is_zero() => self.mantissa.total_cmp(&other.mantissa),
+            ordering => ordering,
+        }
+    }
+}
+
+impl PartialOrd for BigNum {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for BigNum {
+    type Output = BigNum;
+
+    fn add(self, other: BigNum) -> BigNum {
+        let exponent = self.exponent.max(other.exponent);
+        BigNum { mantissa: self.mantissa_at(exponent) + other.mantissa_at(exponent), exponent }.normalized()
+    }
+}
+
+// Saturates at zero, since followers can't go negative
+impl Sub for BigNum {
+    type Output = BigNum;
+
+    fn sub(self, other: BigNum) -> BigNum {
+        if other >= self {
+            return BigNum::ZERO;
+        }
+        let exponent = self.exponent.max(other.exponent);
+        BigNum { mantissa: self.mantissa_at(exponent) - other.mantissa_at(exponent), exponent }.normalized()
+    }
+}
+
+impl Mul for BigNum {
+    type Output = BigNum;
+
+    fn mul(self, other: BigNum) -> BigNum {
+        if self.is_zero() || other.is_zero() {
+            return BigNum::ZERO;
+        }
+        let product = self.mantissa * other.mantissa;
+        if product.is_finite() {
+            return BigNum { mantissa: product, exponent: self.exponent + other.exponent }.normalized();
+        }
+        BigNum::from_log10(self.log10() + other.log10())
+    }
+}
+
+impl Mul<f64> for BigNum {
+    type Output = BigNum;
+
+    fn mul(self, factor: f64) -> BigNum {
+        self * BigNum::from_f64(factor)
+    }
+}
+
+impl AddAssign for BigNum {
+    fn add_assign(&mut self, other: BigNum) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for BigNum {
+    fn sub_assign(&mut self, other: BigNum) {
+        *self = *self - other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_stay_exact() {
+        let a = BigNum::from(123_456_789_012u64);
+        let b = BigNum::from(987_654_321u64);
+        assert_eq!((a + b).to_u64(), 124_444_443_333);
+        assert_eq!((a - b).to_u64(), 122_469_134_691);
+        assert_eq!((b * BigNum::from(3u64)).to_u64(), 2_962_962_963);
+    }
+
+    #[test]
+    fn subtraction_saturates_at_zero() {
+        let small = BigNum::from(5u64);
+        let large = BigNum::from(7u64);
+        assert_eq!(small - large, BigNum::ZERO);
+        assert_eq!(small - small, BigNum::ZERO);
+
+        let mut points = BigNum::from(3u64);
+        points -= BigNum::pow(10.0, 30.0);
+        assert!(points.is_zero());
+    }
+
+    #[test]
+    fn large_values_grow_the_exponent() {
+        let n = BigNum::pow(10.0, 20.0);
+        assert_eq!(n.parts(), (100_000_000_000_000, 6));
+        assert_eq!(n.to_string(), "100000000000000e6");
+        assert_eq!((n * n).to_string(), "100000000000000e26");
+        assert_eq!((n + BigNum::from(1u64)).to_string(), n.to_string());
+    }
+
+    #[test]
+    fn ordering_compares_exponents_then_mantissas() {
+        let mut values = vec![
+            BigNum::pow(10.0, 40.0),
+            BigNum::ZERO,
+            BigNum::from(999u64),
+            BigNum::MAX,
+            BigNum::pow(10.0, 16.0),
+            BigNum::from(1_000u64),
+        ];
+        values.sort();
+        assert_eq!(values, vec![
+            BigNum::ZERO,
+            BigNum::from(999u64),
+            BigNum::from(1_000u64),
+            BigNum::pow(10.0, 16.0),
+            BigNum::pow(10.0, 40.0),
+            BigNum::MAX,
+        ]);
+        assert!(BigNum::ZERO < BigNum::pow(10.0, 40.0));
+    }
+
+    #[test]
+    fn display_and_from_str_round_trip() {
+        for n in [BigNum::ZERO, BigNum::from(42u64), BigNum::pow(2.0, 100.0), BigNum::MAX] {
+            assert_eq!(n.to_string().parse::<BigNum>(), Ok(n));
+        }
+        assert!("12x".parse::<BigNum>().is_err());
+    }
+
+    #[test]
+    fn adding_wildly_different_magnitudes() {
+        let huge = BigNum::pow(10.0, 100.0);
+        let tiny = BigNum::from(1u64);
+        assert!(huge.parts().0 >= 100_000_000_000_000, "{:?} is not canonical", huge);
+        assert_eq!(huge + tiny, huge);
+        assert_eq!(tiny + huge, huge);
+        assert_eq!(huge - tiny, huge);
+
+        // Both still count when they overlap in the mantissa's digits
+        let a = BigNum::pow(10.0, 20.0);
+        let b = BigNum::from(5_000_000u64);
+        assert_eq!((a + b).to_string(), "100000000000005e6");
+
+        // Past u64::MAX instead of wrapping
+        let max = BigNum::from(u64::MAX);
+        assert!(max + max > max);
+        assert_eq!((max + max).log10().floor(), 19.0);
+    }
+
+    #[test]
+    fn multiplying_adds_exponents() {
+        let a = BigNum::pow(10.0, 200.0) * 3.0;
+        let b = BigNum::pow(10.0, 150.0) * 2.0;
+        assert!(((a * b).log10() - (350.0 + 6f64.log10())).abs() < 1e-9);
+        assert_eq!(a * BigNum::ZERO, BigNum::ZERO);
+        assert_eq!(BigNum::from(12u64) * 0.5, BigNum::from(6u64));
+    }
+
+    #[test]
+    fn saved_parts_are_normalized() {
+        let parse = |json: &str| serde_json::from_str::<BigNum>(json).unwrap();
+        // A small value written with an exponent moves back into the mantissa
+        assert_eq!(parse(r#"{"mantissa": 15.0, "exponent": 3}"#), BigNum::from(15_000u64));
+        // An oversized mantissa moves into the exponent
+        assert_eq!(parse(r#"{"mantissa": 1e20, "exponent": 0}"#), BigNum::pow(10.0, 20.0));
+        assert_eq!(parse(r#"{"mantissa": 2.9, "exponent": 0}"#), BigNum::from(2u64));
+        assert_eq!(parse(r#"{"mantissa": 7.0, "exponent": -4}"#), BigNum::from(7u64));
+        assert_eq!(parse(r#"{"mantissa": -3.0, "exponent": 2}"#), BigNum::ZERO);
+        assert_eq!(parse(r#"{"mantissa": 5.0, "exponent": 2000000000}"#), BigNum::MAX);
+    }
+
+    #[test]
+    fn overflow_saturates_at_max() {
+        assert_eq!(BigNum::MAX + BigNum::MAX, BigNum::MAX);
+        assert_eq!(BigNum::MAX + BigNum::from(1u64), BigNum::MAX);
+        assert_eq!(BigNum::MAX * BigNum::MAX, BigNum::MAX);
+        assert_eq!(BigNum::MAX * 1e300, BigNum::MAX);
+        assert_eq!(BigNum::pow(10.0, 1e12), BigNum::MAX);
+        assert_eq!(BigNum::from_f64(f64::INFINITY), BigNum::MAX);
+        assert_eq!(BigNum::from_f64(f64::NAN), BigNum::ZERO);
+        assert_eq!(BigNum::from_f64(-5.0), BigNum::ZERO);
+        assert_eq!(BigNum::MAX.to_u64(), u64::MAX);
+        assert!(BigNum::MAX - BigNum::from(1u64) <= BigNum::MAX);
+    }
+}
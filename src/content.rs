@@ -0,0 +1,495 @@
+// Minion, artifact and relic definitions, built in or read from
+// `content.json` in the save directory
+//
+// The file can replace any of the three lists and leave the others built in:
+//
+//     {
+//       "buildings": [
+//         {"key": "cursor", "name": "Cultist", "description": "Whispers eldritch secrets",
+//          "base_cost": 15, "base_production": 0.1, "cost_multiplier": 1.15}
+//       ],
+//       "upgrades": [
+//         {"name": "Necronomicon Pages", "description": "Cultists are twice as efficient",
+//          "cost": 100, "building_multiplier": ["cursor", 2.0]}
+//       ],
+//       "relics": [
+//         {"name": "Mark of the Deep", "description": "+1 influence power", "cost": 2, "click_bonus": 1}
+//       ]
+//     }
+//
+// A minion with `clicks_per_second` instead clicks on its own, each click
+// bringing what one of yours would. Artifacts have an optional `building_multiplier` (a minion key, or "all")
+// and `click_multiplier`; relics an optional `production_bonus` and
+// `click_bonus`. Saves match minions by key but artifacts and relics by
+// position, so new ones belong at the end of their list.
+//
+// Packs in `content/*.pack.json` have the same three lists, all optional,
+// and add to the content instead of replacing it.
+
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::{Building, PrestigeUpgrade, Upgrade, MAX_BASE_PRODUCTION};
+
+// Most clicks a second one auto-clicking minion may make, far beyond any hand
+const MAX_CLICKS_PER_SECOND: f64 = 100.0;
+
+pub const FILE_NAME: &str = "content.json";
+
+// Packs are the files in this directory, next to content.json, ending in PACK_SUFFIX
+pub const PACK_DIR: &str = "content";
+const PACK_SUFFIX: &str = ".pack.json";
+
+// Set once at startup; GameState::new builds every run from it
+static CONTENT: OnceLock<Content> = OnceLock::new();
+
+#[derive(Clone, Debug)]
+pub struct Content {
+    pub buildings: Vec<Building>, // Cheapest first
+    pub upgrades: Vec<Upgrade>,
+    pub relics: Vec<PrestigeUpgrade>,
+}
+
+// The file as written, where a missing list means the built-in one
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContentFile {
+    buildings: Option<Vec<Building>>,
+    upgrades: Option<Vec<Upgrade>>,
+    relics: Option<Vec<PrestigeUpgrade>>,
+}
+
+// A pack, whose lists are added to the others instead of replacing them
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Pack {
+    #[serde(default)]
+    buildings: Vec<Building>,
+    #[serde(default)]
+    upgrades: Vec<Upgrade>,
+    #[serde(default)]
+    relics: Vec<PrestigeUpgrade>,
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        // Add Cthulhu-themed buildings, cheapest first
+        let buildings = vec![
+            Building::new("cursor", "Cultist", "Whispers eldritch secrets", 15, 0.1, 1.15),
+            Building::new("grandma", "Elder One", "Ancient being from beyond", 100, 1.0, 1.15),
+            Building::auto_clicker("idol", "Animated Idol", "Spreads your influence on its own", 500, 0.2, 1.15),
+            Building::new("farm", "Ritual Site", "Conducts forbidden ceremonies", 1100, 8.0, 1.15),
+            Building::new("mine", "Deep One Colony", "Underwater servants of Cthulhu", 12000, 47.0, 1.15),
+            Building::new("temple", "Temple of Dagon", "Ancient place of worship", 130000, 260.0, 1.15),
+            Building::new("portal", "Dimensional Portal", "Gateway to R'lyeh", 1400000, 1400.0, 1.15),
+        ];
+
+        // Create Cthulhu-themed upgrades
+        let upgrades = vec![
+            Upgrade::new(
+                "Necronomicon Pages",
+                "Cultists are twice as efficient",
+                100,
+                Some(("cursor".to_string(), 2.0)),
+                None,
+            ),
+            Upgrade::new(
+                "Eldritch Incantation",
+                "Your influence is twice as powerful",
+                500,
+                None,
+                Some(2.0),
+            ),
+            Upgrade::new(
+                "Ancient Artifacts",
+                "Elder Ones are twice as efficient",
+                1000,
+                Some(("grandma".to_string(), 2.0)),
+                None,
+            ),
+            Upgrade::new(
+                "Blood Sacrifice",
+                "Ritual Sites are twice as efficient",
+                11000,
+                Some(("farm".to_string(), 2.0)),
+                None,
+            ),
+            Upgrade::new(
+                "Esoteric Geometry",
+                "Deep One Colonies are twice as efficient",
+                120000,
+                Some(("mine".to_string(), 2.0)),
+                None,
+            ),
+            Upgrade::new(
+                "Non-Euclidean Architecture",
+                "Temples of Dagon are twice as efficient",
+                1300000,
+                Some(("temple".to_string(), 2.0)),
+                None,
+            ),
+            Upgrade::new(
+                "The Stars Are Right",
+                "All minions are twice as efficient",
+                10000000,
+                Some(("all".to_string(), 2.0)),
+                Some(5.0),
+            ),
+        ];
+
+        // Relics bought with eldritch shards
+        let relics = vec![
+            PrestigeUpgrade::new("Echo of R'lyeh", "Minions are 5% more efficient", 1, Some(0.05), None),
+            PrestigeUpgrade::new("Mark of the Deep", "+1 influence power", 2, None, Some(1)),
+            PrestigeUpgrade::new("Chorus of the Drowned", "Minions are 15% more efficient", 5, Some(0.15), None),
+            PrestigeUpgrade::new("Eye of Azathoth", "+5 influence power", 10, None, Some(5)),
+            PrestigeUpgrade::new("Dreams of the Sleeper", "Minions are 50% more efficient", 25, Some(0.5), None),
+        ];
+        
+        Content { buildings, upgrades, relics }
+    }
+}
+
+impl Content {
+    // The built-in content with the file's lists in place of its own. A
+    // missing file is the same as an empty one; an unreadable, malformed or
+    // invalid one is an error, and the caller falls back to the built-in
+    // content.
+    pub fn load(dir: &Path) -> Result<Self, Vec<String>> {
+        match fs::read_to_string(dir.join(FILE_NAME)) {
+            Ok(text) => Self::parse(&text),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(vec![err.to_string()]),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Vec<String>> {
+        let file: ContentFile = serde_json::from_str(text).map_err(|err| vec![err.to_string()])?;
+        let defaults = Self::default();
+        let content = Content {
+            buildings: file.buildings.unwrap_or(defaults.buildings),
+            upgrades: file.upgrades.unwrap_or(defaults.upgrades),
+            relics: file.relics.unwrap_or(defaults.relics),
+        };
+        validate_content(&content)?;
+        Ok(content)
+    }
+
+    // Merges every `*.pack.json` in `dir` in file name order. A minion whose
+    // key is already taken is left out, so the first definition of a key
+    // wins. A pack that can't be read or isn't valid is left out whole,
+    // including one whose artifacts need a minion from a pack that was left
+    // out. Returns a warning for everything left out.
+    pub fn add_packs(&mut self, dir: &Path) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut packs = Vec::new();
+        for (name, pack) in read_packs(dir) {
+            match pack {
+                Ok(pack) => packs.push((name, pack)),
+                Err(errors) => warnings.push(format!("Skipped the pack {}: {}", name, errors.join("; "))),
+            }
+        }
+
+        // Minions come first so that artifacts can name a minion from any
+        // pack. Dropping a pack can leave another one's artifacts without
+        // their minion, so check again until nothing more is dropped.
+        loop {
+            let buildings = self.buildings_with(&packs);
+            let before = packs.len();
+            packs.retain(|(name, pack)| {
+                let mut errors = Vec::new();
+                check_upgrades(&pack.upgrades, &buildings, &mut errors);
+                if !errors.is_empty() {
+                    warnings.push(format!("Skipped the pack {}: {}", name, errors.join("; ")));
+                }
+                errors.is_empty()
+            });
+            if packs.len() == before {
+                break;
+            }
+        }
+
+        for (name, pack) in &packs {
+            for building in &pack.buildings {
+                if self.buildings.iter().any(|other| other.key == building.key) {
+                    warnings.push(format!("Left out the minion '{}' from the pack {}: the key is already used", building.key, name));
+                } else {
+                    self.buildings.push(building.clone());
+                }
+            }
+            self.upgrades.extend(pack.upgrades.iter().cloned());
+            self.relics.extend(pack.relics.iter().cloned());
+        }
+        // Kept cheapest first, with ties in the order they were added
+        self.buildings.sort_by_key(|building| building.base_cost);
+        warnings
+    }
+
+    // The minions these packs would add to, first definition of a key winning
+    fn buildings_with(&self, packs: &[(String, Pack)]) -> Vec<Building> {
+        let mut buildings = self.buildings.clone();
+        for building in packs.iter().flat_map(|(_, pack)| &pack.buildings) {
+            if !buildings.iter().any(|other| other.key == building.key) {
+                buildings.push(building.clone());
+            }
+        }
+        buildings
+    }
+}
+
+// Every pack in `dir` by file name, sorted, with its minions and relics
+// already checked. A missing directory has no packs.
+fn read_packs(dir: &Path) -> Vec<(String, Result<Pack, Vec<String>>)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(PACK_SUFFIX))
+        .collect();
+    names.sort();
+
+    names.into_iter()
+        .map(|name| {
+            let pack = fs::read_to_string(dir.join(&name))
+                .map_err(|err| err.to_string())
+                .and_then(|text| serde_json::from_str::<Pack>(&text).map_err(|err| err.to_string()))
+                .map_err(|err| vec![err])
+                .and_then(|pack| {
+                    let mut errors = Vec::new();
+                    check_buildings(&pack.buildings, &mut errors);
+                    check_relics(&pack.relics, &mut errors);
+                    if errors.is_empty() { Ok(pack) } else { Err(errors) }
+                });
+            (name, pack)
+        })
+        .collect()
+}
+
+// Everything wrong with `content` that the economy can't work with, each
+// naming the entry at fault
+pub fn validate_content(content: &Content) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if content.buildings.is_empty() {
+        errors.push("there must be at least one minion".to_string());
+    }
+    check_buildings(&content.buildings, &mut errors);
+    check_upgrades(&content.upgrades, &content.buildings, &mut errors);
+    check_relics(&content.relics, &mut errors);
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+fn check_buildings(buildings: &[Building], errors: &mut Vec<String>) {
+    for (index, building) in buildings.iter().enumerate() {
+        let mut fault = |problem: &str| errors.push(format!("minion {} ('{}'): {}", index + 1, building.key, problem));
+        if building.key.is_empty() || building.key == "all" {
+            fault("the key must not be empty or \"all\"");
+        }
+        if buildings[..index].iter().any(|other| other.key == building.key) {
+            fault("the key is already used");
+        }
+        if building.base_cost == 0 {
+            fault("base_cost must be positive");
+        }
+        if !(0.0..=MAX_BASE_PRODUCTION).contains(&building.base_production) {
+            fault(&format!("base_production must be between 0 and {}", MAX_BASE_PRODUCTION));
+        }
+        if !(0.0..=MAX_CLICKS_PER_SECOND).contains(&building.clicks_per_second) {
+            fault(&format!("clicks_per_second must be between 0 and {}", MAX_CLICKS_PER_SECOND));
+        }
+        // At 1 or below every minion would cost the same or less than the last
+        if !(building.cost_multiplier.is_finite() && building.cost_multiplier > 1.0) {
+            fault("cost_multiplier must be greater than 1");
+        }
+    }
+}
+
+fn check_upgrades(upgrades: &[Upgrade], buildings: &[Building], errors: &mut Vec<String>) {
+    for (index, upgrade) in upgrades.iter().enumerate() {
+        let mut fault = |problem: &str| errors.push(format!("artifact {} ('{}'): {}", index + 1, upgrade.name, problem));
+        if upgrade.cost == 0 {
+            fault("cost must be positive");
+        }
+        if let Some((key, multiplier)) = &upgrade.building_multiplier {
+            if key != "all" && !buildings.iter().any(|building| &building.key == key) {
+                fault(&format!("there is no minion with the key '{}'", key));
+            }
+            if !(multiplier.is_finite() && *multiplier > 0.0) {
+                fault("the minion multiplier must be positive");
+            }
+        }
+        if upgrade.click_multiplier.is_some_and(|multiplier| !(multiplier.is_finite() && multiplier > 0.0)) {
+            fault("click_multiplier must be positive");
+        }
+    }
+}
+
+fn check_relics(relics: &[PrestigeUpgrade], errors: &mut Vec<String>) {
+    for (index, relic) in relics.iter().enumerate() {
+        let mut fault = |problem: &str| errors.push(format!("relic {} ('{}'): {}", index + 1, relic.name, problem));
+        if relic.cost == 0 {
+            fault("cost must be positive");
+        }
+        if relic.production_bonus.is_some_and(|bonus| !(bonus.is_finite() && bonus >= 0.0)) {
+            fault("production_bonus must not be negative");
+        }
+    }
+}
+
+// Makes `content` the one every new GameState starts from. Only the first
+// call counts, and it has to come before the first GameState is made.
+pub fn install(content: Content) {
+    let _ = CONTENT.set(content);
+}
+
+pub fn current() -> &'static Content {
+    CONTENT.get_or_init(Content::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const SMALL: &str = r#"{
+        "buildings": [
+            {"key": "acolyte", "name": "Acolyte", "base_cost": 10, "base_production": 0.5, "cost_multiplier": 1.1},
+            {"key": "shrine", "name": "Shrine", "description": "Hums at night", "base_cost": 250, "base_production": 4.0, "cost_multiplier": 1.2},
+            {"key": "bell", "name": "Bell", "base_cost": 90, "base_production": 0.0, "clicks_per_second": 1.5, "cost_multiplier": 1.3}
+        ],
+        "upgrades": [
+            {"name": "Candles", "description": "Acolytes work twice as hard", "cost": 50, "building_multiplier": ["acolyte", 2.0], "click_multiplier": null}
+        ]
+    }"#;
+
+    #[test]
+    fn a_content_file_replaces_the_lists_it_has() {
+        let content = Content::parse(SMALL).unwrap();
+        let keys: Vec<&str> = content.buildings.iter().map(|building| building.key.as_str()).collect();
+        assert_eq!(keys, ["acolyte", "shrine", "bell"]);
+        let shrine = &content.buildings[1];
+        assert_eq!((shrine.base_cost, shrine.base_production, shrine.cost_multiplier), (250, 4.0, 1.2));
+        assert!(content.buildings[2].is_auto_clicker());
+        assert_eq!(content.buildings[1].current_cost(), 250.into());
+
+        assert_eq!(content.upgrades.len(), 1);
+        assert_eq!(content.upgrades[0].building_multiplier, Some(("acolyte".to_string(), 2.0)));
+        // Relics weren't in the file, so they stay built in
+        assert_eq!(content.relics.len(), Content::default().relics.len());
+    }
+
+    #[test]
+    fn missing_content_files_give_the_built_in_content() {
+        let dir = TempDir::new("content-missing");
+        let content = Content::load(&dir).unwrap();
+        assert_eq!(content.buildings.len(), 7);
+        assert_eq!(content.upgrades.len(), 7);
+        assert_eq!(content.relics.len(), 5);
+        assert_eq!(validate_content(&Content::default()), Ok(()));
+
+        fs::write(dir.join(FILE_NAME), SMALL).unwrap();
+        assert_eq!(Content::load(&dir).unwrap().buildings.len(), 3);
+    }
+
+    #[test]
+    fn unreadable_content_files_are_errors() {
+        let errors = Content::parse("{\"buildings\": [").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("EOF"), "{}", errors[0]);
+
+        let errors = Content::parse(r#"{"minions": []}"#).unwrap_err();
+        assert!(errors[0].starts_with("unknown field `minions`"), "{}", errors[0]);
+
+        let dir = TempDir::new("content-unreadable");
+        fs::create_dir(dir.join(FILE_NAME)).unwrap();
+        assert!(Content::load(&dir).is_err());
+    }
+
+    #[test]
+    fn invalid_packs_are_skipped_whole() {
+        let dir = TempDir::new("packs-invalid");
+        fs::write(dir.join("a.pack.json"), r#"{"buildings": [
+            {"key": "crypt", "name": "Crypt", "base_cost": 0, "base_production": 100.0, "cost_multiplier": 1.15}
+        ]}"#).unwrap();
+        // Needs the crypt from the skipped pack
+        fs::write(dir.join("b.pack.json"), r#"{"upgrades": [
+            {"name": "Bone Keys", "description": "", "cost": 500000, "building_multiplier": ["crypt", 2.0], "click_multiplier": null}
+        ]}"#).unwrap();
+        fs::write(dir.join("c.pack.json"), "{").unwrap();
+
+        let mut content = Content::default();
+        let warnings = content.add_packs(&dir);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[0], "Skipped the pack a.pack.json: minion 1 ('crypt'): base_cost must be positive");
+        assert!(warnings[1].starts_with("Skipped the pack c.pack.json: EOF"), "{}", warnings[1]);
+        assert_eq!(warnings[2], "Skipped the pack b.pack.json: artifact 1 ('Bone Keys'): there is no minion with the key 'crypt'");
+        assert_eq!((content.buildings.len(), content.upgrades.len()), (7, 7));
+
+        // No directory, no packs
+        assert!(Content::default().add_packs(&dir.join("missing")).is_empty());
+    }
+
+    // The built-in content with one change, and what validating it finds
+    fn errors_with(change: impl FnOnce(&mut Content)) -> Vec<String> {
+        let mut content = Content::default();
+        change(&mut content);
+        validate_content(&content).unwrap_err()
+    }
+
+    #[test]
+    fn every_problem_is_reported_by_entry() {
+        assert_eq!(errors_with(|content| {
+            content.buildings.clear();
+            content.upgrades.clear();
+        }), ["there must be at least one minion"]);
+        assert_eq!(errors_with(|content| content.buildings[2].key = "cursor".to_string()),
+            ["minion 3 ('cursor'): the key is already used"]);
+        assert_eq!(errors_with(|content| content.buildings[0].base_cost = 0),
+            ["minion 1 ('cursor'): base_cost must be positive"]);
+        assert_eq!(errors_with(|content| content.buildings[0].cost_multiplier = 1.0),
+            ["minion 1 ('cursor'): cost_multiplier must be greater than 1"]);
+        assert_eq!(errors_with(|content| content.buildings[0].cost_multiplier = f64::INFINITY),
+            ["minion 1 ('cursor'): cost_multiplier must be greater than 1"]);
+        assert_eq!(errors_with(|content| content.buildings[0].base_production = -1.0),
+            [format!("minion 1 ('cursor'): base_production must be between 0 and {}", MAX_BASE_PRODUCTION)]);
+        assert_eq!(errors_with(|content| content.buildings[2].clicks_per_second = 1000.0),
+            ["minion 3 ('idol'): clicks_per_second must be between 0 and 100"]);
+        assert_eq!(errors_with(|content| content.buildings[0].key = "all".to_string()), [
+            "minion 1 ('all'): the key must not be empty or \"all\"",
+            "artifact 1 ('Necronomicon Pages'): there is no minion with the key 'cursor'",
+        ]);
+    }
+
+    #[test]
+    fn artifact_and_relic_problems() {
+        assert_eq!(errors_with(|content| content.upgrades[0].cost = 0),
+            ["artifact 1 ('Necronomicon Pages'): cost must be positive"]);
+        assert_eq!(errors_with(|content| content.upgrades[0].building_multiplier = Some(("shoggoth".to_string(), 2.0))),
+            ["artifact 1 ('Necronomicon Pages'): there is no minion with the key 'shoggoth'"]);
+        assert_eq!(errors_with(|content| content.upgrades[0].building_multiplier = Some(("all".to_string(), 0.0))),
+            ["artifact 1 ('Necronomicon Pages'): the minion multiplier must be positive"]);
+        assert_eq!(errors_with(|content| content.upgrades[1].click_multiplier = Some(f64::NAN)),
+            ["artifact 2 ('Eldritch Incantation'): click_multiplier must be positive"]);
+        assert_eq!(errors_with(|content| content.relics[0].cost = 0),
+            ["relic 1 ('Echo of R'lyeh'): cost must be positive"]);
+        assert_eq!(errors_with(|content| content.relics[0].production_bonus = Some(-0.5)),
+            ["relic 1 ('Echo of R'lyeh'): production_bonus must not be negative"]);
+    }
+
+    #[test]
+    fn all_the_problems_are_collected() {
+        let errors = errors_with(|content| {
+            content.buildings[0].base_cost = 0;
+            content.buildings[3].cost_multiplier = 0.9;
+            content.relics[4].cost = 0;
+        });
+        assert_eq!(errors.len(), 3);
+        let parsed = Content::parse(r#"{"buildings": [
+            {"key": "a", "name": "A", "base_cost": 0, "base_production": 1.0, "cost_multiplier": 1.0}
+        ], "upgrades": []}"#);
+        assert_eq!(parsed.unwrap_err().len(), 2);
+    }
+}
@@ -0,0 +1,182 @@
+// Statistics of a run for spreadsheets and scripts, as CSV or JSON
+//
+// Both formats carry the same numbers. The CSV has one row per item under a
+// fixed header, so it reads the same however many minions or artifacts the
+// content has:
+//
+//     section,key,name,value,production_per_second
+//     run,points,Followers,1500,
+//     building,cursor,Cultist,12,1.2
+//     upgrade,1,Necronomicon Pages,1,
+//
+// For upgrades, relics and achievements the value is 1 when bought or
+// unlocked and 0 otherwise. Upgrades and relics are keyed by their position.
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::GameState;
+
+pub const CSV_HEADER: &str = "section,key,name,value,production_per_second";
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Serialize)]
+struct BuildingStats {
+    key: String,
+    name: String,
+    count: u64,
+    production_per_second: f64, // With every multiplier applied
+}
+
+#[derive(Serialize)]
+struct Owned {
+    key: String,
+    name: String,
+    owned: bool, // Bought, or unlocked for achievements
+}
+
+#[derive(Serialize)]
+pub struct RunStats {
+    points: f64, // Very large counts lose their last digits
+    lifetime_points: f64,
+    click_power: u64,
+    playtime_secs: u64,
+    production_per_second: f64,
+    buildings: Vec<BuildingStats>,
+    upgrades: Vec<Owned>,
+    relics: Vec<Owned>,
+    achievements: Vec<Owned>,
+}
+
+impl RunStats {
+    pub fn of(state: &GameState) -> Self {
+        RunStats {
+            points: state.points.to_f64(),
+            lifetime_points: state.lifetime_points.to_f64(),
+            click_power: state.click_power,
+            playtime_secs: state.playtime_secs,
+            production_per_second: state.production_per_second(),
+            buildings: state.buildings.iter()
+                .map(|building| BuildingStats {
+                    key: building.key.clone(),
+                    name: building.name.clone(),
+                    count: building.count,
+                    production_per_second: state.effective_production(&building.key),
+                })
+                .collect(),
+            upgrades: state.upgrades.iter().enumerate()
+                .map(|(index, upgrade)| Owned { key: (index + 1).to_string(), name: upgrade.name.clone(), owned: upgrade.purchased })
+                .collect(),
+            relics: state.prestige_upgrades.iter().enumerate()
+                .map(|(index, relic)| Owned { key: (index + 1).to_string(), name: relic.name.clone(), owned: relic.purchased })
+                .collect(),
+            achievements: state.achievements.iter()
+                .map(|achievement| Owned { key: achievement.key.to_string(), name: achievement.name.to_string(), owned: achievement.unlocked })
+                .collect(),
+        }
+    }
+
+    pub fn write(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => self.to_csv(),
+            // Nothing in RunStats can fail to serialize
+            ExportFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default() + "\n",
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec![CSV_HEADER.to_string()];
+        let mut row = |section: &str, key: &str, name: &str, value: String, production: Option<f64>| {
+            let production = production.map(|production| production.to_string()).unwrap_or_default();
+            rows.push([section, key, name, &value, &production].map(csv_field).join(","));
+        };
+
+        row("run", "points", "Followers", self.points.to_string(), None);
+        row("run", "lifetime_points", "Total converts", self.lifetime_points.to_string(), None);
+        row("run", "click_power", "Influence power", self.click_power.to_string(), None);
+        row("run", "playtime_secs", "Playtime", self.playtime_secs.to_string(), None);
+        row("run", "production_per_second", "Production", String::new(), Some(self.production_per_second));
+        for building in &self.buildings {
+            row("building", &building.key, &building.name, building.count.to_string(), Some(building.production_per_second));
+        }
+        for (section, items) in [("upgrade", &self.upgrades), ("relic", &self.relics), ("achievement", &self.achievements)] {
+            for item in items {
+                row(section, &item.key, &item.name, (item.owned as u8).to_string(), None);
+            }
+        }
+        rows.join("\n") + "\n"
+    }
+}
+
+// Quoted when it holds a comma, a quote or a line break, with quotes doubled
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bignum::BigNum;
+
+    fn known_state() -> GameState {
+        let mut state = GameState::new();
+        state.points = BigNum::from(1500);
+        state.lifetime_points = BigNum::from(2000);
+        state.buildings[0].count = 10;
+        state.upgrades[0].purchased = true; // Doubles what the cultists make
+        state
+    }
+
+    #[test]
+    fn fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("Cultist"), "Cultist");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("Keys of R'lyeh"), "Keys of R'lyeh");
+        assert_eq!(csv_field("one, two"), "\"one, two\"");
+        assert_eq!(csv_field("the \"Old\" Ones"), "\"the \"\"Old\"\" Ones\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("line\r\nbreak"), "\"line\r\nbreak\"");
+    }
+
+    #[test]
+    fn csv_has_one_row_per_item_under_the_header() {
+        let state = known_state();
+        let csv = RunStats::of(&state).write(ExportFormat::Csv);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], CSV_HEADER);
+        assert_eq!(rows[1], "run,points,Followers,1500,");
+        assert_eq!(rows[2], "run,lifetime_points,Total converts,2000,");
+        assert_eq!(rows[6], "building,cursor,Cultist,10,2");
+        assert!(rows.contains(&"upgrade,1,Necronomicon Pages,1,"));
+        assert!(rows.contains(&"upgrade,2,Eldritch Incantation,0,"));
+        let expected = 1 + 5 + state.buildings.len() + state.upgrades.len()
+            + state.prestige_upgrades.len() + state.achievements.len();
+        assert_eq!(rows.len(), expected);
+        assert!(rows.iter().all(|row| row.split(',').count() == 5), "{}", csv);
+        assert!(csv.ends_with('\n'));
+    }
+
+    #[test]
+    fn json_carries_the_same_numbers() {
+        let state = known_state();
+        let json: serde_json::Value = serde_json::from_str(&RunStats::of(&state).write(ExportFormat::Json)).unwrap();
+        assert_eq!(json["points"], 1500.0);
+        assert_eq!(json["lifetime_points"], 2000.0);
+        assert_eq!(json["buildings"][0]["key"], "cursor");
+        assert_eq!(json["buildings"][0]["count"], 10);
+        assert_eq!(json["buildings"][0]["production_per_second"], 2.0);
+        assert_eq!(json["upgrades"][0]["owned"], true);
+        assert_eq!(json["upgrades"][1]["owned"], false);
+        assert_eq!(json["buildings"].as_array().unwrap().len(), state.buildings.len());
+        assert_eq!(json["achievements"].as_array().unwrap().len(), state.achievements.len());
+    }
+}
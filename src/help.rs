@@ -0,0 +1,77 @@
+// Contents of the in-game help screen
+//
+// Kept as data rather than as print calls, so a new key only needs a new
+// entry here. The help screen lays the sections out one line per entry and
+// scrolls when they don't fit.
+
+pub struct Section {
+    pub title: &'static str,
+    pub entries: &'static [(&'static str, &'static str)], // Key (or topic) and what it does
+}
+
+pub const SECTIONS: &[Section] = &[
+    Section {
+        title: "Everywhere",
+        entries: &[
+            (".", "Spread your influence and gain followers; hold it to keep clicking"),
+            ("1-9", "Sanctum, Minions, Artifacts, Chronicle, Annals, Deeds, Relics, Achievements, Settings"),
+            ("0", "Rebirth and its skill tree"),
+            ("?", "This help"),
+            ("Up/Down", "Move the selection (also k/j)"),
+            ("Home/End", "Jump to the first or last row (also g and G)"),
+            ("PgUp/PgDn", "Jump a page in long lists"),
+            ("Enter", "Buy or use the selected row"),
+            ("a", "Spend everything on the best value minions and artifacts"),
+            ("r", "Hold back your current followers as a reserve, or release it"),
+            ("f", "Catch a golden follower while one is on screen"),
+            ("p", "Pause or resume production"),
+            ("s", "Save now"),
+            ("x", "Toggle the sandbox (after victory, or with --debug)"),
+            ("Ctrl+C", "Save and quit"),
+        ],
+    },
+    Section {
+        title: "Sanctum",
+        entries: &[
+            ("n", "Name this run"),
+            ("e", "Export this run as a string"),
+            ("i", "Import a run from a string"),
+            ("Shift+A", "Open the Ascension screen"),
+            ("Shift+R", "Reset this run, deleting its save"),
+            ("y/n", "Confirm or keep, when asked before a reset, ascension or import"),
+        ],
+    },
+    Section {
+        title: "Minions",
+        entries: &[
+            ("1-9", "Summon the minion on that row"),
+            ("Esc", "Return to the Sanctum"),
+            ("b", "Summon 1, 10 or 100 at a time"),
+            ("m", "Summon as many as you can afford"),
+            ("o", "Sort by cost, production, how many you own or efficiency"),
+            ("-", "Release one for half its price (also Shift+Enter)"),
+        ],
+    },
+    Section {
+        title: "Other menus",
+        entries: &[
+            ("a", "In Artifacts, buy every affordable artifact, cheapest first"),
+            ("l", "In Artifacts, show more or fewer details per artifact"),
+            ("Shift+B", "In Rebirth, trade your shards for rebirth tokens"),
+            ("c", "In Annals, clear the purchase history"),
+            ("Left/Right", "In Settings, change the selected setting"),
+        ],
+    },
+    Section {
+        title: "How it works",
+        entries: &[
+            ("Production", "Minions gather followers every second, even while you are away"),
+            ("Click power", "Grows at 1K, 10K, 100K, 1M, 10M and 100M total converts"),
+            ("Artifacts", "Multiply the production of a minion, or of all of them"),
+            ("Prices", "Each minion costs more than the one before it"),
+            ("Ascension", "Trade a run for shards, each adding to all production"),
+            ("Rebirth", "Trade shards and relics for tokens that buy lasting skills"),
+            ("Keys", "Rebind them in keybindings.toml in the save directory"),
+        ],
+    },
+];
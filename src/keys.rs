@@ -0,0 +1,292 @@
+// Key bindings, read from `keybindings.toml` in the save directory
+//
+// The file holds one binding per line, the action's name and then its key:
+//
+//     click = ","
+//     save = "w"
+//     buildings = "F2"
+//
+// A binding replaces every default key of that action, and takes its key away
+// from whichever action had it before. Keys are a single character or one of
+// the names in NAMED_KEYS. Blank lines, `#` comments and `[section]` headers
+// are ignored. Ctrl+C always quits, and the row keys of the Minions menu stay
+// on the digits.
+
+use crossterm::event::KeyCode;
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use clickercurse_game::{Action, Menu};
+
+pub const FILE_NAME: &str = "keybindings.toml";
+
+// Every action that can be bound, by the name used in the file
+const ACTIONS: &[(&str, Action)] = &[
+    ("click", Action::Click),
+    ("pause", Action::TogglePause),
+    ("sandbox", Action::ToggleSandbox),
+    ("save", Action::Save),
+    ("clear", Action::ClearHistory),
+    ("reserve", Action::ToggleReserve),
+    ("spendall", Action::SpendAll),
+    ("golden", Action::CatchGolden),
+    ("lines", Action::ToggleUpgradeLines),
+    ("bulk", Action::CycleBuyAmount),
+    ("sort", Action::CycleBuildingSort),
+    ("max", Action::BuyMax),
+    ("sell", Action::SellSelection),
+    ("rename", Action::StartRename),
+    ("export", Action::ShowExport),
+    ("import", Action::StartImport),
+    ("reset", Action::StartReset),
+    ("ascend", Action::StartAscension),
+    ("reborn", Action::StartRebirth),
+    ("main", Action::OpenMenu(Menu::Main)),
+    ("buildings", Action::OpenMenu(Menu::Buildings)),
+    ("upgrades", Action::OpenMenu(Menu::Upgrades)),
+    ("stats", Action::OpenMenu(Menu::Stats)),
+    ("history", Action::OpenMenu(Menu::History)),
+    ("deeds", Action::OpenMenu(Menu::Deeds)),
+    ("relics", Action::OpenMenu(Menu::Relics)),
+    ("rebirth", Action::OpenMenu(Menu::Rebirth)),
+    ("achievements", Action::OpenMenu(Menu::Achievements)),
+    ("settings", Action::OpenMenu(Menu::Settings)),
+    ("help", Action::OpenMenu(Menu::Help)),
+    ("up", Action::SelectPrevious),
+    ("down", Action::SelectNext),
+    ("pageup", Action::PagePrevious),
+    ("pagedown", Action::PageNext),
+    ("first", Action::SelectFirst),
+    ("last", Action::SelectLast),
+    ("left", Action::SettingPrevious),
+    ("right", Action::SettingNext),
+    ("select", Action::ConfirmSelection),
+];
+
+// Keys that aren't a single character
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Space", KeyCode::Char(' ')),
+    ("F1", KeyCode::F(1)),
+    ("F2", KeyCode::F(2)),
+    ("F3", KeyCode::F(3)),
+    ("F4", KeyCode::F(4)),
+    ("F5", KeyCode::F(5)),
+    ("F6", KeyCode::F(6)),
+    ("F7", KeyCode::F(7)),
+    ("F8", KeyCode::F(8)),
+    ("F9", KeyCode::F(9)),
+    ("F10", KeyCode::F(10)),
+    ("F11", KeyCode::F(11)),
+    ("F12", KeyCode::F(12)),
+];
+
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    keys: HashMap<KeyCode, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = [
+            (KeyCode::Char('.'), Action::Click),
+            (KeyCode::Char('p'), Action::TogglePause),
+            (KeyCode::Char('x'), Action::ToggleSandbox),
+            (KeyCode::Char('s'), Action::Save),
+            (KeyCode::Char('c'), Action::ClearHistory),
+            (KeyCode::Char('r'), Action::ToggleReserve),
+            (KeyCode::Char('a'), Action::SpendAll),
+            (KeyCode::Char('f'), Action::CatchGolden),
+            (KeyCode::Char('l'), Action::ToggleUpgradeLines),
+            (KeyCode::Char('b'), Action::CycleBuyAmount),
+            (KeyCode::Char('o'), Action::CycleBuildingSort),
+            (KeyCode::Char('m'), Action::BuyMax),
+            (KeyCode::Char('-'), Action::SellSelection),
+            (KeyCode::Char('n'), Action::StartRename),
+            (KeyCode::Char('e'), Action::ShowExport),
+            (KeyCode::Char('i'), Action::StartImport),
+            (KeyCode::Char('R'), Action::StartReset),
+            (KeyCode::Char('A'), Action::StartAscension),
+            (KeyCode::Char('B'), Action::StartRebirth),
+            (KeyCode::Char('1'), Action::OpenMenu(Menu::Main)),
+            (KeyCode::Char('2'), Action::OpenMenu(Menu::Buildings)),
+            (KeyCode::Char('3'), Action::OpenMenu(Menu::Upgrades)),
+            (KeyCode::Char('4'), Action::OpenMenu(Menu::Stats)),
+            (KeyCode::Char('5'), Action::OpenMenu(Menu::History)),
+            (KeyCode::Char('6'), Action::OpenMenu(Menu::Deeds)),
+            (KeyCode::Char('7'), Action::OpenMenu(Menu::Relics)),
+            (KeyCode::Char('8'), Action::OpenMenu(Menu::Achievements)),
+            (KeyCode::Char('9'), Action::OpenMenu(Menu::Settings)),
+            (KeyCode::Char('0'), Action::OpenMenu(Menu::Rebirth)),
+            (KeyCode::Char('?'), Action::OpenMenu(Menu::Help)),
+            (KeyCode::Up, Action::SelectPrevious),
+            (KeyCode::Char('k'), Action::SelectPrevious),
+            (KeyCode::Down, Action::SelectNext),
+            (KeyCode::Char('j'), Action::SelectNext),
+            (KeyCode::PageUp, Action::PagePrevious),
+            (KeyCode::PageDown, Action::PageNext),
+            (KeyCode::Home, Action::SelectFirst),
+            (KeyCode::Char('g'), Action::SelectFirst),
+            (KeyCode::End, Action::SelectLast),
+            (KeyCode::Char('G'), Action::SelectLast),
+            (KeyCode::Left, Action::SettingPrevious),
+            (KeyCode::Right, Action::SettingNext),
+            (KeyCode::Enter, Action::ConfirmSelection),
+        ];
+        KeyBindings { keys: keys.into_iter().collect() }
+    }
+}
+
+impl KeyBindings {
+    // The defaults with the file's bindings applied. A missing file is the
+    // same as an empty one; an unreadable or malformed one is an error, and
+    // the caller falls back to the defaults.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        match fs::read_to_string(dir.join(FILE_NAME)) {
+            Ok(text) => Self::parse(&text),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut bindings = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            let (name, key) = parse_line(line).map_err(|err| format!("line {}: {}", number + 1, err))?;
+            bindings.bind(name, key);
+        }
+        Ok(bindings)
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.keys.get(&key).cloned()
+    }
+
+    fn bind(&mut self, action: Action, key: KeyCode) {
+        self.keys.retain(|_, bound| *bound != action);
+        self.keys.insert(key, action);
+    }
+}
+
+fn parse_line(line: &str) -> Result<(Action, KeyCode), String> {
+    let (name, value) = line.split_once('=').ok_or("expected `action = \"key\"`")?;
+    let name = name.trim();
+    let action = ACTIONS.iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, action)| action.clone())
+        .ok_or_else(|| format!("unknown action '{}'", name))?;
+
+    // The value is a quoted string, optionally followed by a comment
+    let value = value.trim();
+    let quote = value.chars().next().filter(|ch| *ch == '"' || *ch == '\'')
+        .ok_or("the key must be in quotes")?;
+    let (key, rest) = value[1..].split_once(quote).ok_or("missing closing quote")?;
+    let rest = rest.trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("unexpected '{}' after the key", rest));
+    }
+    Ok((action, parse_key(key)?))
+}
+
+fn parse_key(key: &str) -> Result<KeyCode, String> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(KeyCode::Char(ch)),
+        _ => NAMED_KEYS.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, code)| *code)
+            .ok_or_else(|| format!("unknown key '{}'", key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn defaults_cover_the_usual_keys() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.action(KeyCode::Char('.')), Some(Action::Click));
+        assert_eq!(bindings.action(KeyCode::Char('2')), Some(Action::OpenMenu(Menu::Buildings)));
+        assert_eq!(bindings.action(KeyCode::Enter), Some(Action::ConfirmSelection));
+        assert_eq!(bindings.action(KeyCode::Char('z')), None);
+    }
+
+    #[test]
+    fn a_binding_replaces_the_default_keys() {
+        let bindings = KeyBindings::parse("click = \",\"\n").unwrap();
+        assert_eq!(bindings.action(KeyCode::Char(',')), Some(Action::Click));
+        assert_eq!(bindings.action(KeyCode::Char('.')), None);
+
+        // Both of the defaults for moving down go
+        let bindings = KeyBindings::parse("down = \"Tab\"").unwrap();
+        assert_eq!(bindings.action(KeyCode::Tab), Some(Action::SelectNext));
+        assert_eq!(bindings.action(KeyCode::Down), None);
+        assert_eq!(bindings.action(KeyCode::Char('j')), None);
+    }
+
+    #[test]
+    fn a_bound_key_is_taken_from_its_old_action() {
+        let bindings = KeyBindings::parse("save = \"p\"").unwrap();
+        assert_eq!(bindings.action(KeyCode::Char('p')), Some(Action::Save));
+        assert_eq!(bindings.action(KeyCode::Char('s')), None);
+    }
+
+    #[test]
+    fn comments_sections_and_named_keys() {
+        let text = "
+            # Function keys for the menus
+            [menus]
+            buildings = 'F2'  # single quotes work too
+            upgrades = \"f3\"
+            click = \"Space\"
+        ";
+        let bindings = KeyBindings::parse(text).unwrap();
+        assert_eq!(bindings.action(KeyCode::F(2)), Some(Action::OpenMenu(Menu::Buildings)));
+        assert_eq!(bindings.action(KeyCode::F(3)), Some(Action::OpenMenu(Menu::Upgrades)));
+        assert_eq!(bindings.action(KeyCode::Char(' ')), Some(Action::Click));
+    }
+
+    #[test]
+    fn malformed_lines_name_their_line() {
+        let error = |text| KeyBindings::parse(text).unwrap_err();
+        assert_eq!(error("\nclick"), "line 2: expected `action = \"key\"`");
+        assert_eq!(error("dance = \"d\""), "line 1: unknown action 'dance'");
+        assert_eq!(error("click = d"), "line 1: the key must be in quotes");
+        assert_eq!(error("click = \"d"), "line 1: missing closing quote");
+        assert_eq!(error("click = \"d\" e"), "line 1: unexpected 'e' after the key");
+        assert_eq!(error("click = \"F13\""), "line 1: unknown key 'F13'");
+        assert_eq!(error("click = \"\""), "line 1: unknown key ''");
+    }
+
+    #[test]
+    fn missing_files_give_the_defaults() {
+        let dir = TempDir::new("keys");
+        let bindings = KeyBindings::load(&dir).unwrap();
+        assert_eq!(bindings.action(KeyCode::Char('.')), Some(Action::Click));
+
+        fs::write(dir.join(FILE_NAME), "click = \"c\"\n").unwrap();
+        assert_eq!(KeyBindings::load(&dir).unwrap().action(KeyCode::Char('c')), Some(Action::Click));
+
+        fs::write(dir.join(FILE_NAME), "click = c\n").unwrap();
+        assert!(KeyBindings::load(&dir).is_err());
+    }
+}
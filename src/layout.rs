@@ -0,0 +1,102 @@
+// Column layout for the list menus, and the progress bars drawn in them
+//
+// Offsets are computed from the widest cell in each column instead of fixed
+// positions, so long names push the following columns right rather than
+// being overwritten by them.
+
+// Below this terminal width the menus always use the stacked layout
+pub const MIN_WIDTH: u16 = 60;
+
+// Indent of the detail line in the stacked layout
+pub const STACK_INDENT: u16 = 4;
+
+// Space left between two columns
+const GAP: usize = 2;
+
+// Width of the widest cell in a column
+pub fn column_width<'a>(cells: impl IntoIterator<Item = &'a str>) -> usize {
+    cells.into_iter().map(|cell| cell.chars().count()).max().unwrap_or(0)
+}
+
+// Returns the x offset of each column, or None when the columns don't fit
+// side by side and the caller should stack them instead
+pub fn columns(widths: &[usize], terminal_width: u16) -> Option<Vec<u16>> {
+    if terminal_width < MIN_WIDTH {
+        return None;
+    }
+
+    let mut offsets = Vec::with_capacity(widths.len());
+    let mut x = 0;
+    for width in widths {
+        offsets.push(u16::try_from(x).ok()?);
+        x += width + GAP;
+    }
+
+    // The last column doesn't need a trailing gap
+    if x.saturating_sub(GAP) > terminal_width as usize {
+        None
+    } else {
+        Some(offsets)
+    }
+}
+
+// First row of a list window of `visible` rows, moved no further from
+// `offset` than it takes to keep `selected` inside the window
+pub fn scroll_offset(offset: usize, selected: usize, visible: usize) -> usize {
+    let visible = visible.max(1);
+    if selected < offset {
+        selected
+    } else if selected >= offset + visible {
+        selected + 1 - visible
+    } else {
+        offset
+    }
+}
+
+// A bar like `[####----]` with `width` cells between the brackets, filled to
+// `fraction`, which is clamped to 0..=1
+pub fn render_bar(fraction: f64, width: usize) -> String {
+    let fraction = if fraction.is_nan() { 0.0 } else { fraction.clamp(0.0, 1.0) };
+    let filled = (fraction * width as f64).floor() as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bars_fill_with_the_fraction() {
+        assert_eq!(render_bar(0.0, 8), "[--------]");
+        assert_eq!(render_bar(0.5, 8), "[####----]");
+        assert_eq!(render_bar(0.99, 8), "[#######-]");
+        assert_eq!(render_bar(1.0, 8), "[########]");
+    }
+
+    #[test]
+    fn bars_clamp_what_falls_outside() {
+        assert_eq!(render_bar(2.5, 8), "[########]");
+        assert_eq!(render_bar(-1.0, 8), "[--------]");
+        assert_eq!(render_bar(f64::NAN, 8), "[--------]");
+        assert_eq!(render_bar(f64::INFINITY, 4), "[####]");
+        assert_eq!(render_bar(0.5, 0), "[]");
+    }
+
+    #[test]
+    fn scrolling_keeps_the_selection_in_view() {
+        for visible in 0..6 {
+            for offset in 0..12 {
+                for selected in 0..12 {
+                    let first = scroll_offset(offset, selected, visible);
+                    assert!((first..first + visible.max(1)).contains(&selected), "offset {} selected {} visible {}", offset, selected, visible);
+                    // A selection already in view never scrolls
+                    if (offset..offset + visible).contains(&selected) {
+                        assert_eq!(first, offset);
+                    }
+                }
+            }
+        }
+        assert_eq!(scroll_offset(0, 9, 4), 6);
+        assert_eq!(scroll_offset(6, 2, 4), 2);
+    }
+}
@@ -0,0 +1,3760 @@
+// Game logic: the state of a run, its economy, and saving and loading it
+//
+// Nothing in here touches the terminal. main.rs turns key presses into
+// Actions, applies them to a GameState and draws its ViewModel, so everything
+// below can be driven by replays or tests without a screen.
+
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use clap::ValueEnum;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::io::Result as IoResult;
+
+pub mod achievements;
+pub mod bignum;
+pub mod content;
+pub mod export;
+pub mod help;
+pub mod layout;
+pub mod numbers;
+pub mod rebirth;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod slots;
+#[cfg(test)]
+mod testing;
+pub mod theme;
+pub mod view;
+
+use achievements::Achievement;
+use bignum::BigNum;
+use numbers::{format_number, NumberFormat};
+use rng::Rng;
+use theme::ThemePreset;
+
+// Game structures. The definitions of minions, artifacts and relics can be
+// read from a content file (see content.rs), which leaves out their progress.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Building {
+    pub key: String, // Names the building in saves, replays and upgrades
+    pub name: String,
+    #[allow(dead_code)]
+    #[serde(default)]
+    description: String,
+    base_cost: u64,
+    base_production: f64,
+    #[serde(default)]
+    clicks_per_second: f64, // Clicks made for you by each one, for auto-clickers
+    #[serde(skip)]
+    pub count: u64,
+    cost_multiplier: f64,
+    #[serde(skip, default = "full_price")]
+    price_factor: f64, // Fraction of the price paid, lowered by rebirth skills
+}
+
+fn full_price() -> f64 {
+    1.0
+}
+
+impl Building {
+    pub fn new(key: &str, name: &str, description: &str, base_cost: u64, base_production: f64, cost_multiplier: f64) -> Self {
+        Building {
+            key: key.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            base_cost,
+            base_production,
+            clicks_per_second: 0.0,
+            count: 0,
+            cost_multiplier,
+            price_factor: full_price(),
+        }
+    }
+    
+    // A minion that clicks instead of producing followers itself
+    pub fn auto_clicker(key: &str, name: &str, description: &str, base_cost: u64, clicks_per_second: f64, cost_multiplier: f64) -> Self {
+        Building { clicks_per_second, ..Building::new(key, name, description, base_cost, 0.0, cost_multiplier) }
+    }
+
+    // Never falls as the count grows: past BigNum's range the price stays at
+    // BigNum::MAX instead of wrapping around to something affordable. A
+    // discount never makes a minion free.
+    pub fn current_cost(&self) -> BigNum {
+        let full = if self.count == 0 {
+            BigNum::from(self.base_cost)
+        } else {
+            BigNum::from(self.base_cost) * BigNum::pow(self.cost_multiplier, self.count as f64)
+        };
+        if self.price_factor < 1.0 {
+            (full * self.price_factor).floor().max(BigNum::from(1))
+        } else {
+            full.floor()
+        }
+    }
+
+    // Cost of the next n purchases, each priced as a single buy would be
+    fn cost_for_n(&self, n: u64) -> BigNum {
+        (0..n)
+            .map(|i| Building { count: self.count.saturating_add(i), ..self.clone() }.current_cost())
+            .fold(BigNum::ZERO, |total, cost| total + cost)
+    }
+
+    // Most of this building the points can pay for, estimated from the
+    // geometric series and then corrected against the exact costs
+    fn max_affordable(&self, points: BigNum) -> u64 {
+        let price = self.current_cost();
+        let ratio = self.cost_multiplier;
+        let estimate = if ratio > 1.0 && !price.is_zero() && !points.is_zero() {
+            let budget_in_prices = 10f64.powf(points.log10() - price.log10());
+            ((budget_in_prices * (ratio - 1.0) + 1.0).ln() / ratio.ln()).floor()
+        } else {
+            0.0
+        };
+        let mut n = (estimate.max(0.0) as u64).min(BUY_MAX_LIMIT);
+        
+        let mut total = self.cost_for_n(n);
+        while n > 0 && total > points {
+            n -= 1;
+            total -= Building { count: self.count.saturating_add(n), ..self.clone() }.current_cost();
+        }
+        while n < BUY_MAX_LIMIT {
+            let next = Building { count: self.count.saturating_add(n), ..self.clone() }.current_cost();
+            if total + next > points {
+                break;
+            }
+            total += next;
+            n += 1;
+        }
+        n
+    }
+
+    pub fn total_production(&self) -> f64 {
+        self.base_production * self.count as f64
+    }
+    
+    pub fn total_clicks_per_second(&self) -> f64 {
+        self.clicks_per_second * self.count as f64
+    }
+    
+    pub fn is_auto_clicker(&self) -> bool {
+        self.clicks_per_second > 0.0
+    }
+
+    fn buy(&mut self) -> BigNum {
+        let cost = self.current_cost();
+        self.count = self.count.saturating_add(1);
+        cost
+    }
+
+    // Gives one back for part of what it cost, or nothing when none are owned
+    fn sell(&mut self) -> BigNum {
+        if self.count == 0 {
+            return BigNum::ZERO;
+        }
+        self.count -= 1;
+        (self.current_cost() * SELL_REFUND).floor()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Upgrade {
+    name: String,
+    description: String,
+    cost: u64,
+    #[serde(skip)]
+    purchased: bool,
+    building_multiplier: Option<(String, f64)>,
+    click_multiplier: Option<f64>,
+    #[serde(skip)]
+    produced_at_purchase: Option<u64>, // Snapshot of produced_points when bought
+}
+
+impl Upgrade {
+    pub fn new(
+        name: &str,
+        description: &str,
+        cost: u64,
+        building_multiplier: Option<(String, f64)>,
+        click_multiplier: Option<f64>,
+    ) -> Self {
+        Upgrade {
+            name: name.to_string(),
+            description: description.to_string(),
+            cost,
+            purchased: false,
+            building_multiplier,
+            click_multiplier,
+            produced_at_purchase: None,
+        }
+    }
+}
+
+// Permanent perks bought with eldritch shards; they survive ascension
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrestigeUpgrade {
+    name: String,
+    description: String,
+    cost: u64, // In eldritch shards
+    #[serde(skip)]
+    purchased: bool,
+    production_bonus: Option<f64>, // Added to the minion production multiplier
+    click_bonus: Option<u64>, // Added to influence power
+}
+
+impl PrestigeUpgrade {
+    pub fn new(name: &str, description: &str, cost: u64, production_bonus: Option<f64>, click_bonus: Option<u64>) -> Self {
+        PrestigeUpgrade {
+            name: name.to_string(),
+            description: description.to_string(),
+            cost,
+            purchased: false,
+            production_bonus,
+            click_bonus,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PurchaseRecord {
+    pub timestamp: u64, // Unix seconds
+    pub item: String,
+    pub cost: u64,
+    pub count_after: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Milestone {
+    pub tier: String,
+    timestamp: u64, // Unix seconds
+    pub playtime_secs: u64,
+}
+
+// Running tallies of what the player has done, shown in the Deeds menu
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub total_clicks: u64,
+    pub buildings_bought: u64,
+    pub upgrades_bought: u64,
+    pub souls_spent: u64, // Followers spent on purchases
+}
+
+// A golden follower on screen, waiting to be caught with 'f'
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoldenEvent {
+    pub x: f64, // Position as a fraction of the screen width and height
+    pub y: f64,
+    remaining: f64, // Seconds until it vanishes
+}
+
+// Seconds until the next golden follower for a roll in [0, 1)
+fn golden_spawn_delay(roll: f64) -> f64 {
+    GOLDEN_MIN_DELAY + roll * (GOLDEN_MAX_DELAY - GOLDEN_MIN_DELAY)
+}
+
+fn golden_reward(production_per_second: f64, click_power: u64) -> BigNum {
+    let rate = production_per_second.max(click_power as f64);
+    BigNum::from_f64((rate * GOLDEN_REWARD_SECS).round())
+}
+
+// What a buff multiplies
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuffKind {
+    Production, // All minion production
+    Click,      // Followers from each click
+}
+
+// A temporary multiplier
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Buff {
+    kind: BuffKind,
+    multiplier: f64,
+    remaining: f64, // Seconds until it wears off
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    text: String,
+    shown_since: Option<Instant>, // Starts when it reaches the front of the queue
+}
+
+// How long each notification stays on screen
+const NOTIFICATION_SECS: f64 = 3.0;
+
+// Longest profile name the rename prompt accepts
+const PROFILE_NAME_LIMIT: usize = 40;
+
+// Most time away credited as offline progress, unless --offline-cap says otherwise
+pub const DEFAULT_OFFLINE_CAP_HOURS: f64 = 8.0;
+
+// Seconds between autosaves, unless --autosave-secs says otherwise
+pub const DEFAULT_AUTOSAVE_SECS: u64 = 30;
+
+// Sanity limit for base production read from saves
+const MAX_BASE_PRODUCTION: f64 = 1_000_000.0;
+
+// Longest save string the import prompt accepts
+const IMPORT_LIMIT: usize = 1 << 20;
+
+// Clicks older than this no longer count towards the click rate
+const CLICK_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+// Seconds between golden followers, and how long each one can be caught
+const GOLDEN_MIN_DELAY: f64 = 60.0;
+const GOLDEN_MAX_DELAY: f64 = 180.0;
+const GOLDEN_LIFETIME: f64 = 13.0;
+
+// Frenzy a golden follower can grant instead of a burst
+const FRENZY_MULTIPLIER: f64 = 7.0;
+const FRENZY_SECS: f64 = 77.0;
+const CLICK_FRENZY_MULTIPLIER: f64 = 777.0;
+const CLICK_FRENZY_SECS: f64 = 13.0;
+
+// Chance that a catch grants a clicking frenzy, and otherwise a frenzy
+const CLICK_FRENZY_CHANCE: f64 = 0.1;
+const FRENZY_CHANCE: f64 = 0.5;
+
+// A catch is worth this many seconds of production, or of frantic clicking
+// before any minions exist
+const GOLDEN_REWARD_SECS: f64 = 30.0;
+
+// Rows of the Settings menu, in order
+const SETTING_NUMBER_FORMAT: usize = 0;
+const SETTING_THEME: usize = 1;
+const SETTING_AUTOSAVE: usize = 2;
+const SETTING_TICK_RATE: usize = 3;
+const SETTING_MOUSE: usize = 4;
+const SETTING_PAUSED_CLICKS: usize = 5;
+const SETTING_SOUND: usize = 6;
+const SETTING_RESERVE: usize = 7;
+const SETTING_ACHIEVEMENT_BONUS: usize = 8;
+const SETTINGS_COUNT: usize = 9;
+
+// Smallest reserve Right steps up to from none; Left steps below it down to none
+const RESERVE_STEP: u64 = 1000;
+
+// Characters a typed reserve amount may have
+const RESERVE_LIMIT: usize = 24;
+
+// Rows of the victory screen, in order
+const VICTORY_KEEP_PLAYING: usize = 0;
+const VICTORY_ASCEND: usize = 1;
+const VICTORY_START_OVER: usize = 2;
+const VICTORY_CHOICES: usize = 3;
+
+// Milliseconds between production ticks the Settings menu offers
+const TICK_RATES_MS: [u64; 4] = [50, 100, 250, 500];
+const DEFAULT_TICK_MS: u64 = 100;
+
+// Most purchases a single spend-everything press makes
+const SPEND_ALL_LIMIT: usize = 1000;
+
+// Fraction of a minion's price refunded when it is sold
+const SELL_REFUND: f64 = 0.5;
+
+// Most of one minion a single buy-max press summons
+const BUY_MAX_LIMIT: u64 = 1000;
+
+// Oldest purchases are dropped once the history grows past this
+const HISTORY_LIMIT: usize = 200;
+
+// Only the progress fields are saved; the rest is per-session and comes from
+// GameState::new() when loading, as do fields missing from older saves
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameState {
+    version: u32, // Save format version, see save.rs
+    pub points: BigNum,
+    pub lifetime_points: BigNum,
+    produced_points: u64, // Lifetime points generated by buildings, capped at u64::MAX
+    pub click_power: u64,
+    #[serde(with = "save::buildings")]
+    pub buildings: Vec<Building>, // In the order of GameState::new, cheapest first
+    #[serde(with = "save::upgrades")]
+    upgrades: Vec<Upgrade>,
+    #[serde(skip)]
+    pub current_menu: Menu,
+    #[serde(skip)]
+    selected_index: usize,
+    #[serde(skip)]
+    scroll_offset: usize, // First row shown by the scrolling list menus
+    #[serde(skip)]
+    pub terminal_size: (u16, u16), // As of the last frame, for paging through lists
+    #[serde(skip)]
+    cached_pps: Cell<Option<f64>>, // See production_per_second
+    production_remainder: f64, // Track fractional production
+    auto_click_remainder: f64, // Auto-clicks owed but not yet made
+    #[serde(skip)]
+    pub io_in_progress: bool, // Set while a save is being written
+    #[serde(skip)]
+    pub paused: bool, // Production is frozen while set
+    victory_reached: bool, // The victory screen only ever shows once
+    #[serde(skip)]
+    pub debug_mode: bool, // Unlocks the sandbox without reaching victory
+    sandbox: bool, // Purchases are free while set
+    sandbox_tainted: bool, // Sandbox was used at some point in this save
+    #[serde(skip)]
+    pub focused: bool, // Whether the terminal window has focus
+    #[serde(skip)]
+    pub unfocused_rate: f64, // Fraction of production credited while unfocused
+    purchase_history: VecDeque<PurchaseRecord>, // Oldest first
+    reserve: BigNum, // Building purchases never spend below this
+    #[serde(skip)]
+    pending_upgrade: Option<usize>, // Upgrade awaiting confirmation to break the reserve
+    pub playtime_secs: u64, // Whole seconds spent with the game open
+    playtime_fraction: f64, // Part of a second not yet counted in playtime_secs
+    milestones: Vec<Milestone>, // Domination tiers reached, in order
+    stats: Stats,
+    shards: u64, // Eldritch shards carried over from ascending
+    #[serde(with = "save::prestige_upgrades")]
+    prestige_upgrades: Vec<PrestigeUpgrade>,
+    rebirth_tokens: u64, // Unspent, from being reborn
+    skills: Vec<String>, // Keys of the rebirth skill nodes bought, kept for good
+    #[serde(with = "save::achievements")]
+    achievements: Vec<Achievement>,
+    #[serde(skip)]
+    notifications: VecDeque<Notification>,
+    #[serde(skip)]
+    pub rounding: RoundingMode,
+    #[serde(skip)]
+    pub rng: Rng,
+    #[serde(skip)]
+    recent_clicks: VecDeque<(Instant, u64)>, // Points from clicks within CLICK_RATE_WINDOW
+    upgrade_lines: u16, // Lines per upgrade in the Artifacts menu, 2 or 3
+    buy_amount: u8, // Minions bought per Enter, 1, 10 or 100
+    building_sort: BuildingSort,
+    number_format: NumberFormat,
+    theme: ThemePreset,
+    pub autosave: bool, // Autosaves are skipped while off; the game still saves on exit
+    #[serde(skip)]
+    pub autosave_secs: u64, // From --autosave-secs, 0 when autosaving is disabled
+    pub tick_ms: u64, // Milliseconds between production ticks, one of TICK_RATES_MS
+    pub mouse: bool, // Whether the terminal reports mouse clicks to the game
+    paused_clicks: bool, // Whether clicks still count while production is paused
+    pub sound: bool, // Whether the terminal bell rings for golden followers, tier-ups and achievements
+    pub achievement_bonus: bool, // Whether each unlocked achievement adds ACHIEVEMENT_BONUS to production
+    #[serde(skip)]
+    pub bell: bool, // A ring waiting for the next frame
+    profile_name: String, // Player's label for this run, may be empty
+    #[serde(skip)]
+    pub prompt: Option<Prompt>, // Text being typed into a prompt, if one is open
+    #[serde(skip)]
+    pub confirmation: Option<Confirmation>, // Takes all input until answered
+    #[serde(skip)]
+    export_text: Option<String>, // Shown by the Export menu
+    #[serde(skip)]
+    pub slot: String, // Save slot this game is written to
+    #[serde(skip)]
+    pub save_dir: PathBuf, // Directory of the save slots
+    last_saved_at: u64, // Unix seconds, 0 if never saved
+    #[serde(skip)]
+    offline_summary: Option<String>, // Shown on the Sanctum after a load
+    #[serde(skip)]
+    golden: Option<GoldenEvent>,
+    #[serde(skip)]
+    next_golden_in: Option<f64>, // Seconds until the next golden follower, once scheduled
+    #[serde(skip)]
+    golden_sighted_ago: Option<f64>, // Seconds since the last golden follower appeared
+    #[serde(skip)]
+    buffs: Vec<Buff>,
+    #[serde(skip)]
+    pub dirty: bool, // Something on screen changed since the last frame was drawn
+}
+
+// Order of the Minions menu, which Enter and the row keys follow too
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildingSort {
+    #[default]
+    Cost, // Cheapest first
+    Production, // Most followers per second first
+    Count, // Most owned first
+    Efficiency, // Lowest price per follower per second first
+}
+
+impl BuildingSort {
+    fn next(self) -> Self {
+        match self {
+            BuildingSort::Cost => BuildingSort::Production,
+            BuildingSort::Production => BuildingSort::Count,
+            BuildingSort::Count => BuildingSort::Efficiency,
+            BuildingSort::Efficiency => BuildingSort::Cost,
+        }
+    }
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            BuildingSort::Cost => "cost",
+            BuildingSort::Production => "production",
+            BuildingSort::Count => "count",
+            BuildingSort::Efficiency => "efficiency",
+        }
+    }
+}
+
+// How fractional production becomes whole followers
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum RoundingMode {
+    // Keep the fraction for the next tick; exact over time
+    #[default]
+    Floor,
+    // Round up with probability equal to the fraction; exact on average
+    Stochastic,
+}
+
+// How `simulate` spends followers while fast-forwarding
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Strategy {
+    // Whatever minion or artifact costs least
+    BuyCheapest,
+    // The most production per follower spent, as spend-all picks
+    BuyBestEfficiency,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Menu {
+    Main,
+    Buildings,
+    Upgrades,
+    Stats,
+    History,
+    Deeds,
+    Ascension,
+    Relics,
+    Rebirth,
+    Achievements,
+    Settings,
+    Help,
+    Export,
+    Victory,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PromptKind {
+    Rename,
+    Import,
+    Reserve,
+}
+
+// A line of text the player is typing, drawn in the Sanctum header
+#[derive(Clone, Debug)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub text: String,
+}
+
+impl Prompt {
+    fn limit(&self) -> usize {
+        match self.kind {
+            PromptKind::Rename => PROFILE_NAME_LIMIT,
+            PromptKind::Import => IMPORT_LIMIT,
+            PromptKind::Reserve => RESERVE_LIMIT,
+        }
+    }
+}
+
+// An action that can't be undone, held until the player answers y or n
+#[derive(Clone, Debug)]
+pub enum Confirmation {
+    Reset,
+    Ascend,
+    Rebirth,
+    Import(Box<GameState>), // Already checked, so a yes can't fail
+}
+
+impl Confirmation {
+    fn question(&self) -> &'static str {
+        match self {
+            Confirmation::Reset => "Banish this dominion? Its save and backups are deleted.",
+            Confirmation::Ascend => "Ascend? Your followers, minions and artifacts are given up.",
+            Confirmation::Rebirth => "Be reborn? Your shards, relics and this run are given up.",
+            Confirmation::Import(_) => "Replace this run with the imported one?",
+        }
+    }
+}
+
+// Lifetime converts at which Cthulhu rises (the last domination tier)
+const VICTORY_THRESHOLD: u64 = 1_000_000_000;
+
+// Lifetime converts that earn the first eldritch shard on ascension; more
+// shards need the cube of their count times this
+pub const SHARD_DIVISOR: f64 = 1e12;
+
+// Production bonus granted by each eldritch shard
+pub const SHARD_BONUS: f64 = 0.02;
+
+// Production bonus granted by each unlocked achievement, once the player
+// turns it on in Settings
+pub const ACHIEVEMENT_BONUS: f64 = 0.01;
+
+
+// Everything the player can do; keys and replay scripts both map onto these
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Click,
+    TogglePause,
+    ToggleSandbox,
+    ClearHistory,
+    ToggleReserve,
+    SpendAll,
+    ToggleUpgradeLines,
+    CycleBuyAmount,
+    CycleBuildingSort,
+    BuyMax,
+    SellSelection,
+    QuickBuy(usize), // Row of the Minions menu, from 0
+    CatchGolden,
+    StartRename,
+    StartImport,
+    StartReset,
+    StartAscension,
+    StartRebirth,
+    ShowExport,
+    PromptInput(char),
+    PromptPaste(String),
+    PromptBackspace,
+    PromptConfirm,
+    PromptCancel,
+    Answer(bool), // Yes or no to the pending confirmation
+    MouseClick(u16), // Left button pressed on this screen row
+    SetProfileName(String),
+    SetReserve(BigNum),
+    Save,
+    Quit,
+    OpenMenu(Menu),
+    SelectPrevious,
+    SelectNext,
+    PagePrevious,
+    PageNext,
+    SelectFirst,
+    SelectLast,
+    ConfirmSelection,
+    SettingPrevious,
+    SettingNext,
+    BuyBuilding(String),
+    BuyUpgrade(usize),
+    BuyPrestigeUpgrade(usize),
+    BuySkill(usize),
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        GameState::new()
+    }
+}
+
+impl GameState {
+    pub fn new() -> Self {
+        let content::Content { buildings, upgrades, relics: prestige_upgrades } = content::current().clone();
+        
+        GameState {
+            version: save::SAVE_VERSION,
+            points: BigNum::ZERO,
+            lifetime_points: BigNum::ZERO,
+            produced_points: 0,
+            click_power: 1,
+            buildings,
+            upgrades,
+            current_menu: Menu::Main,
+            selected_index: 0,
+            scroll_offset: 0,
+            terminal_size: (80, 24),
+            cached_pps: Cell::new(None),
+            production_remainder: 0.0,
+            auto_click_remainder: 0.0,
+            io_in_progress: false,
+            paused: false,
+            victory_reached: false,
+            debug_mode: false,
+            sandbox: false,
+            sandbox_tainted: false,
+            focused: true,
+            unfocused_rate: 1.0,
+            purchase_history: VecDeque::new(),
+            reserve: BigNum::ZERO,
+            pending_upgrade: None,
+            playtime_secs: 0,
+            playtime_fraction: 0.0,
+            milestones: Vec::new(),
+            stats: Stats::default(),
+            shards: 0,
+            prestige_upgrades,
+            rebirth_tokens: 0,
+            skills: Vec::new(),
+            achievements: achievements::definitions(),
+            notifications: VecDeque::new(),
+            rounding: RoundingMode::Floor,
+            rng: Rng::new(0),
+            recent_clicks: VecDeque::new(),
+            upgrade_lines: 3,
+            buy_amount: 1,
+            building_sort: BuildingSort::Cost,
+            number_format: NumberFormat::Abbreviated,
+            theme: ThemePreset::Classic,
+            autosave: true,
+            autosave_secs: DEFAULT_AUTOSAVE_SECS,
+            tick_ms: DEFAULT_TICK_MS,
+            mouse: false,
+            paused_clicks: true,
+            sound: true,
+            achievement_bonus: false,
+            bell: false,
+            profile_name: String::new(),
+            prompt: None,
+            confirmation: None,
+            export_text: None,
+            slot: slots::DEFAULT_SLOT.to_string(),
+            save_dir: PathBuf::from(slots::DEFAULT_SAVE_DIR),
+            last_saved_at: 0,
+            offline_summary: None,
+            golden: None,
+            next_golden_in: None,
+            golden_sighted_ago: None,
+            buffs: Vec::new(),
+            dirty: true,
+        }
+    }
+    
+    // Production per second, recomputed only after production_changed. Every
+    // tick and every frame asks for it, and the full sum walks every building
+    // through every upgrade.
+    pub fn production_per_second(&self) -> f64 {
+        if let Some(pps) = self.cached_pps.get() {
+            return pps;
+        }
+        let pps = self.calculate_production_per_second();
+        self.cached_pps.set(Some(pps));
+        pps
+    }
+    
+    // Call after anything that goes into building_multiplier or a building's
+    // count changes
+    fn production_changed(&self) {
+        self.cached_pps.set(None);
+    }
+    
+    fn calculate_production_per_second(&self) -> f64 {
+        let mut total = 0.0;
+        
+        for building in &self.buildings {
+            total += self.effective_production(&building.key);
+        }
+        
+        total
+    }
+    
+    // Position of a building in `buildings`. Taking `&mut self.buildings[index]`
+    // afterwards leaves the other fields free to use.
+    fn building_index(&self, key: &str) -> Option<usize> {
+        self.buildings.iter().position(|building| building.key == key)
+    }
+    
+    fn building(&self, key: &str) -> Option<&Building> {
+        self.building_index(key).map(|index| &self.buildings[index])
+    }
+    
+    // Production of one building type with its upgrades applied
+    fn effective_production(&self, key: &str) -> f64 {
+        match self.building(key) {
+            Some(building) => building.total_production() * self.building_multiplier(key),
+            None => 0.0,
+        }
+    }
+    
+    // Price of the next one of a building per follower per second it adds,
+    // counting an auto-clicker's clicks at what a click brings now
+    fn building_efficiency(&self, key: &str) -> Option<f64> {
+        let building = self.building(key)?;
+        let gain = if building.is_auto_clicker() {
+            building.clicks_per_second * self.click_value()
+        } else {
+            building.base_production * self.building_multiplier(key)
+        };
+        efficiency(building.current_cost(), gain)
+    }
+    
+    // The most efficient minion that can be summoned now, or failing that the
+    // most efficient one to save up for
+    fn best_buy(&self) -> Option<String> {
+        let most_efficient = |affordable_only: bool| {
+            self.buildings.iter()
+                .filter(|building| !affordable_only || self.can_spend(building.current_cost()))
+                .filter_map(|building| Some((&building.key, self.building_efficiency(&building.key)?)))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(key, _)| key.clone())
+        };
+        most_efficient(true).or_else(|| most_efficient(false))
+    }
+    
+    // Combined multiplier of the shards, relics, achievements, active buffs and
+    // purchased upgrades for one building
+    fn building_multiplier(&self, key: &str) -> f64 {
+        let mut multiplier = self.shard_multiplier() * self.prestige_multiplier()
+            * self.achievement_multiplier() * self.buff_multiplier(BuffKind::Production);
+        
+        for upgrade in &self.upgrades {
+            if upgrade.purchased {
+                if let Some((building_key, building_mult)) = &upgrade.building_multiplier {
+                    if building_key == "all" || building_key == key {
+                        multiplier *= building_mult;
+                    }
+                }
+            }
+        }
+        
+        multiplier
+    }
+    
+    // Eldritch shards earned by ascending with this many lifetime converts
+    pub fn shards_from_lifetime(lifetime: BigNum) -> u64 {
+        (lifetime.to_f64() / SHARD_DIVISOR).cbrt().floor() as u64
+    }
+    
+    // Lifetime converts at which ascending earns this many shards
+    pub fn lifetime_for_shards(shards: u64) -> BigNum {
+        BigNum::from_f64((shards as f64).powi(3) * SHARD_DIVISOR)
+    }
+    
+    fn shard_multiplier(&self) -> f64 {
+        1.0 + self.shards as f64 * SHARD_BONUS
+    }
+    
+    fn prestige_multiplier(&self) -> f64 {
+        1.0 + self.prestige_upgrades.iter()
+            .filter(|relic| relic.purchased)
+            .filter_map(|relic| relic.production_bonus)
+            .sum::<f64>()
+    }
+    
+    // Product of the rewards of every unlocked achievement, times the bonus
+    // for how many are unlocked while that is turned on
+    fn achievement_multiplier(&self) -> f64 {
+        let unlocked = self.achievements.iter().filter(|achievement| achievement.unlocked);
+        let rewards: f64 = unlocked.clone().filter_map(|achievement| achievement.reward_multiplier).product();
+        if self.achievement_bonus {
+            rewards * achievement_bonus_multiplier(unlocked.count())
+        } else {
+            rewards
+        }
+    }
+    
+    // Influence power added by relics
+    fn prestige_click_bonus(&self) -> u64 {
+        self.prestige_upgrades.iter()
+            .filter(|relic| relic.purchased)
+            .filter_map(|relic| relic.click_bonus)
+            .fold(0, u64::saturating_add)
+    }
+    
+    // Counts down to the next golden follower and the current one's escape
+    pub fn advance_golden(&mut self, elapsed: f64) {
+        if let Some(ago) = &mut self.golden_sighted_ago {
+            *ago += elapsed;
+        }
+        
+        if let Some(golden) = &mut self.golden {
+            golden.remaining -= elapsed;
+            if golden.remaining <= 0.0 {
+                self.golden = None;
+                self.dirty = true;
+            }
+            return;
+        }
+        
+        let next = match self.next_golden_in {
+            Some(next) => next - elapsed,
+            None => golden_spawn_delay(self.rng.next_f64()),
+        };
+        if next > 0.0 {
+            self.next_golden_in = Some(next);
+            return;
+        }
+        
+        self.next_golden_in = None;
+        self.golden_sighted_ago = Some(0.0);
+        self.golden = Some(GoldenEvent {
+            x: self.rng.next_f64(),
+            y: self.rng.next_f64(),
+            remaining: GOLDEN_LIFETIME,
+        });
+        debug!("A golden follower appears");
+        self.notify("A golden follower appears! Press 'f' to catch it".to_string());
+        self.ring();
+    }
+    
+    // Active buffs of one kind stack multiplicatively
+    fn buff_multiplier(&self, kind: BuffKind) -> f64 {
+        self.buffs.iter()
+            .filter(|buff| buff.kind == kind)
+            .map(|buff| buff.multiplier)
+            .product()
+    }
+    
+    // Seconds until the last buff of one kind wears off
+    fn buff_remaining(&self, kind: BuffKind) -> Option<f64> {
+        self.buffs.iter()
+            .filter(|buff| buff.kind == kind)
+            .map(|buff| buff.remaining)
+            .max_by(f64::total_cmp)
+    }
+    
+    // Counts down the active buffs and drops the expired ones
+    pub fn advance_buffs(&mut self, elapsed: f64) {
+        for buff in &mut self.buffs {
+            buff.remaining -= elapsed;
+        }
+        let before = self.buffs.len();
+        self.buffs.retain(|buff| buff.remaining > 0.0);
+        if self.buffs.len() != before {
+            self.dirty = true;
+            self.production_changed();
+        }
+    }
+    
+    fn catch_golden(&mut self) {
+        if self.golden.take().is_none() {
+            return;
+        }
+        
+        let roll = self.rng.next_f64();
+        if roll < CLICK_FRENZY_CHANCE {
+            self.buffs.push(Buff { kind: BuffKind::Click, multiplier: CLICK_FRENZY_MULTIPLIER, remaining: CLICK_FRENZY_SECS });
+            debug!("Caught a golden follower: clicking frenzy");
+            self.notify(format!("Clicking Frenzy! Clicks x{} for {} seconds", CLICK_FRENZY_MULTIPLIER, CLICK_FRENZY_SECS));
+            return;
+        }
+        // A frenzy is only worth anything once minions are producing
+        if roll < CLICK_FRENZY_CHANCE + FRENZY_CHANCE && self.production_per_second() > 0.0 {
+            self.buffs.push(Buff { kind: BuffKind::Production, multiplier: FRENZY_MULTIPLIER, remaining: FRENZY_SECS });
+            debug!("Caught a golden follower: frenzy");
+            self.production_changed();
+            self.notify(format!("Frenzy! Production x{} for {} seconds", FRENZY_MULTIPLIER, FRENZY_SECS));
+            return;
+        }
+        
+        let reward = golden_reward(self.production_per_second(), self.click_power);
+        debug!("Caught a golden follower: {} followers", reward);
+        let previous_lifetime = self.lifetime_points;
+        self.points += reward;
+        self.lifetime_points += reward;
+        self.notify(format!("The golden follower brings {} more", format_number(reward, self.number_format)));
+        self.check_click_power_upgrade();
+        self.check_progress(previous_lifetime);
+    }
+    
+    // Production is summed and carried as f64 and only the whole part is
+    // credited, so slow minions still pay out over many short ticks
+    pub fn credit_production(&mut self, elapsed: f64) {
+        // A clock that stepped backwards (or a NaN) credits nothing rather
+        // than taking followers away
+        let elapsed = elapsed.max(0.0);
+        let production = self.production_per_second() * elapsed;
+        
+        // Add the current production to any remainder from previous ticks
+        self.production_remainder += production;
+        
+        let points_to_add = self.take_whole_points();
+        
+        if points_to_add > 0.0 {
+            // Add the points
+            let previous_lifetime = self.lifetime_points;
+            self.points += BigNum::from_f64(points_to_add);
+            self.lifetime_points += BigNum::from_f64(points_to_add);
+            self.produced_points = self.produced_points.saturating_add(points_to_add as u64);
+            self.dirty = true;
+            
+            self.check_progress(previous_lifetime);
+        }
+        
+        // Whole points are always flushed above, so anything outside [0, 1)
+        // means a bad rate (negative, NaN, or infinite)
+        if !(0.0..1.0).contains(&self.production_remainder) {
+            warn!("Production remainder out of range: {}", self.production_remainder);
+            self.production_remainder = 0.0;
+        }
+    }
+    
+    // Counts `elapsed` seconds of the game being open, carrying the part
+    // of a second over to the next call
+    pub fn add_playtime(&mut self, elapsed: f64) {
+        self.playtime_fraction += elapsed.max(0.0);
+        if !self.playtime_fraction.is_finite() {
+            self.playtime_fraction = 0.0;
+        }
+        let whole = self.playtime_fraction.floor();
+        self.playtime_fraction -= whole;
+        self.playtime_secs = self.playtime_secs.saturating_add(whole as u64);
+    }
+    
+    // Clicks per second made by the auto-clicking minions
+    pub fn auto_clicks_per_second(&self) -> f64 {
+        self.buildings.iter().map(Building::total_clicks_per_second).sum()
+    }
+    
+    // Credits the auto-clicks owed for `elapsed` seconds in one go, each
+    // worth a click of the player's with multipliers, buffs and influence
+    // power applied, and carries the fraction over like production. They
+    // aren't the player's clicks, so they stay out of the click count and
+    // the click rate. Nothing is owed while clicks are held by the pause.
+    pub fn credit_auto_clicks(&mut self, elapsed: f64) {
+        if self.paused && !self.paused_clicks {
+            return;
+        }
+        self.auto_click_remainder += self.auto_clicks_per_second() * elapsed.max(0.0);
+        if !self.auto_click_remainder.is_finite() {
+            self.auto_click_remainder = 0.0;
+        }
+        let whole = self.auto_click_remainder.floor();
+        self.auto_click_remainder -= whole;
+        if whole < 1.0 {
+            return;
+        }
+        
+        let gained = BigNum::from_f64(whole * self.click_value());
+        let previous_lifetime = self.lifetime_points;
+        self.points += gained;
+        self.lifetime_points += gained;
+        self.dirty = true;
+        self.check_click_power_upgrade();
+        self.check_progress(previous_lifetime);
+    }
+    
+    // Moves the whole number part of the remainder out as points
+    fn take_whole_points(&mut self) -> f64 {
+        let whole = self.production_remainder.floor();
+        match self.rounding {
+            RoundingMode::Floor => {
+                self.production_remainder -= whole;
+                whole
+            },
+            RoundingMode::Stochastic => {
+                let round_up = self.rng.next_f64() < self.production_remainder - whole;
+                self.production_remainder = 0.0;
+                whole + round_up as u8 as f64
+            },
+        }
+    }
+    
+    // Points per second gained from clicking lately
+    fn click_rate(&self) -> f64 {
+        let total: u64 = self.recent_clicks.iter()
+            .filter(|(at, _)| at.elapsed() < CLICK_RATE_WINDOW)
+            .map(|(_, points)| points)
+            .sum();
+        total as f64 / CLICK_RATE_WINDOW.as_secs_f64()
+    }
+    
+    pub fn forget_old_clicks(&mut self) {
+        while let Some((at, _)) = self.recent_clicks.front() {
+            if at.elapsed() < CLICK_RATE_WINDOW {
+                break;
+            }
+            self.recent_clicks.pop_front();
+        }
+    }
+    
+    // Scale applied to elapsed time in the tick thread
+    pub fn production_scale(&self) -> f64 {
+        if self.focused { 1.0 } else { self.unfocused_rate }
+    }
+    
+    // Followers one click brings right now
+    fn click_value(&self) -> f64 {
+        let mut click_multiplier = self.buff_multiplier(BuffKind::Click);
+        
+        // Apply click upgrades
+        for upgrade in &self.upgrades {
+            if upgrade.purchased {
+                if let Some(mult) = upgrade.click_multiplier {
+                    click_multiplier *= mult;
+                }
+            }
+        }
+        
+        let power = self.click_power.saturating_add(self.prestige_click_bonus());
+        (power as f64 * click_multiplier).floor()
+    }
+    
+    fn click(&mut self) {
+        if self.paused && !self.paused_clicks {
+            return;
+        }
+        let points_to_add = self.click_value();
+        let previous_lifetime = self.lifetime_points;
+        self.points += BigNum::from_f64(points_to_add);
+        self.lifetime_points += BigNum::from_f64(points_to_add);
+        self.recent_clicks.push_back((Instant::now(), points_to_add as u64));
+        self.stats.total_clicks = self.stats.total_clicks.saturating_add(1);
+        
+        // Check if we should increase click power based on lifetime points
+        self.check_click_power_upgrade();
+        self.check_progress(previous_lifetime);
+    }
+    
+    // Celebrates tier-ups and the final victory after lifetime points grow
+    fn check_progress(&mut self, previous_lifetime: BigNum) {
+        if domination_tier(self.lifetime_points.to_u64()) > domination_tier(previous_lifetime.to_u64()) {
+            let tier = get_domination_status(self.lifetime_points.to_u64());
+            self.notify(format!("Your dominion grows: {}", tier));
+            self.ring();
+            self.milestones.push(Milestone {
+                tier,
+                timestamp: unix_now(),
+                playtime_secs: self.playtime_secs,
+            });
+        }
+        
+        if !self.victory_reached && self.lifetime_points >= BigNum::from(VICTORY_THRESHOLD) {
+            self.victory_reached = true;
+            self.current_menu = Menu::Victory;
+            self.selected_index = 0;
+        }
+        
+        self.check_achievements();
+    }
+    
+    // Unlocks every achievement whose condition is newly met
+    fn check_achievements(&mut self) {
+        let newly_met: Vec<usize> = self.achievements.iter()
+            .enumerate()
+            .filter(|(_, achievement)| !achievement.unlocked && achievement.is_met(self))
+            .map(|(index, _)| index)
+            .collect();
+        for index in newly_met {
+            self.achievements[index].unlocked = true;
+            self.production_changed();
+            let name = self.achievements[index].name;
+            info!("Achievement unlocked: {}", name);
+            self.notify(format!("Achievement unlocked: {}", name));
+            self.ring();
+        }
+    }
+    
+    // Asks the front-end for a bell, unless the sound is off
+    fn ring(&mut self) {
+        self.bell |= self.sound;
+    }
+    
+    pub fn notify(&mut self, text: String) {
+        self.notifications.push_back(Notification { text, shown_since: None });
+        self.dirty = true;
+    }
+    
+    // Drops the front notification once it has been shown long enough
+    pub fn advance_notifications(&mut self) {
+        if let Some(Notification { shown_since: Some(since), .. }) = self.notifications.front() {
+            if since.elapsed().as_secs_f64() >= NOTIFICATION_SECS {
+                self.notifications.pop_front();
+                self.dirty = true;
+            }
+        }
+        if let Some(front) = self.notifications.front_mut() {
+            front.shown_since.get_or_insert_with(Instant::now);
+        }
+    }
+    
+    fn check_click_power_upgrade(&mut self) {
+        // Increase click power based on lifetime points milestones
+        let milestone: u64 = match self.lifetime_points.to_u64() {
+            0..=999 => 1,
+            1000..=9999 => 2,
+            10000..=99999 => 5,
+            100000..=999999 => 10,
+            1000000..=9999999 => 25,
+            10000000..=99999999 => 50,
+            _ => 100,
+        };
+        // Rebirth skills scale what the milestones grant
+        let new_click_power = (milestone as f64 * rebirth::click_scaling(&self.skills)).round() as u64;
+        
+        if new_click_power > self.click_power {
+            self.click_power = new_click_power;
+        }
+    }
+    
+    fn sandbox_unlocked(&self) -> bool {
+        self.victory_reached || self.debug_mode
+    }
+    
+    fn toggle_sandbox(&mut self) {
+        if self.sandbox_unlocked() {
+            self.sandbox = !self.sandbox;
+            // Once used, the save stays marked even after switching back
+            self.sandbox_tainted |= self.sandbox;
+        }
+    }
+    
+    // Summons buy_amount of the minion on one row of the Minions menu,
+    // stopping early once the next one is unaffordable
+    fn buy_row(&mut self, row: usize) {
+        if let Some(key) = self.sorted_building_keys().get(row) {
+            let key = key.clone();
+            for bought in 0..self.buy_amount {
+                if !self.buy_building(&key) {
+                    if bought == 0 {
+                        self.refuse_building(&key);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    
+    // Building keys in the order the Minions menu lists them. The view and
+    // every row-based purchase go through here, so they can't disagree.
+    fn sorted_building_keys(&self) -> Vec<String> {
+        let mut entries: Vec<&Building> = self.buildings.iter().collect();
+        // The sorts are stable, so ties stay cheapest first
+        entries.sort_by_key(|building| building.current_cost());
+        match self.building_sort {
+            BuildingSort::Cost => {},
+            BuildingSort::Production => {
+                entries.sort_by(|a, b| self.effective_production(&b.key).total_cmp(&self.effective_production(&a.key)));
+            },
+            BuildingSort::Count => entries.sort_by_key(|building| std::cmp::Reverse(building.count)),
+            // Minions that produce nothing have no efficiency and go last
+            BuildingSort::Efficiency => entries.sort_by(|a, b| {
+                match (self.building_efficiency(&a.key), self.building_efficiency(&b.key)) {
+                    (Some(a), Some(b)) => a.total_cmp(&b),
+                    (a, b) => b.is_some().cmp(&a.is_some()),
+                }
+            }),
+        }
+        entries.into_iter().map(|building| building.key.clone()).collect()
+    }
+    
+    // Whether a building purchase of this cost leaves the reserve intact
+    fn can_spend(&self, cost: BigNum) -> bool {
+        self.sandbox || self.points >= cost + self.reserve
+    }
+    
+    fn toggle_reserve(&mut self) {
+        self.reserve = if self.reserve.is_zero() { self.points } else { BigNum::ZERO };
+    }
+    
+    // Rows in the list of the current menu
+    fn list_len(&self) -> usize {
+        match self.current_menu {
+            Menu::Buildings => self.buildings.len(),
+            Menu::Upgrades => self.upgrades.len(),
+            Menu::History => self.purchase_history.len(),
+            Menu::Relics => self.prestige_upgrades.len(),
+            Menu::Rebirth => rebirth::NODES.len(),
+            Menu::Achievements => self.achievements.len(),
+            Menu::Settings => SETTINGS_COUNT,
+            Menu::Victory => VICTORY_CHOICES,
+            // Scrolled a line at a time, so the last page is the furthest it goes
+            Menu::Help => help_line_count().saturating_sub(self.list_rows()) + 1,
+            _ => 0,
+        }
+    }
+    
+    // Rows the current menu fits on screen at once, matching its draw function
+    fn list_rows(&self) -> usize {
+        let (width, height) = self.terminal_size;
+        let rows = match self.current_menu {
+            Menu::Buildings => {
+                let row_height = if width < layout::MIN_WIDTH { 2 } else { 1 };
+                buildings_visible(height, row_height)
+            },
+            Menu::Upgrades => upgrades_visible(height, self.upgrade_lines),
+            Menu::Achievements => achievements_visible(height),
+            Menu::Help => help_visible(height),
+            _ => self.list_len(),
+        };
+        rows.max(1)
+    }
+    
+    // Row of the current list drawn at screen row `y`, matching its draw function
+    fn list_index_at(&self, y: u16) -> Option<usize> {
+        let first = layout::scroll_offset(self.scroll_offset, self.selected_index, self.list_rows());
+        let last = (first + self.list_rows()).min(self.list_len());
+        match self.current_menu {
+            Menu::Buildings => {
+                let row_height = if self.terminal_size.0 < layout::MIN_WIDTH { 2 } else { 1 };
+                // Rows below the selected one are moved down by its progress bar
+                (first..last).find(|&i| {
+                    let y_pos = (i - first) as u16 * row_height + 4 + (i > self.selected_index) as u16;
+                    (y_pos..y_pos + row_height).contains(&y)
+                })
+            },
+            Menu::Upgrades => {
+                let index = first + (y.checked_sub(3)? / self.upgrade_lines) as usize;
+                (index < last).then_some(index)
+            },
+            _ => None,
+        }
+    }
+    
+    // Moves the selection to `index`, clamped to the list, and scrolls to it
+    fn select(&mut self, index: usize) {
+        self.pending_upgrade = None;
+        self.selected_index = index.min(self.list_len().saturating_sub(1));
+        self.scroll_offset = layout::scroll_offset(self.scroll_offset, self.selected_index, self.list_rows());
+    }
+    
+    // Steps the selected row of the Settings menu forwards or backwards
+    fn change_setting(&mut self, forward: bool) {
+        match self.selected_index {
+            SETTING_NUMBER_FORMAT => {
+                self.number_format = if forward { self.number_format.next() } else { self.number_format.previous() };
+            },
+            SETTING_THEME => {
+                self.theme = if forward { self.theme.next() } else { self.theme.previous() };
+            },
+            SETTING_AUTOSAVE => self.autosave = !self.autosave,
+            SETTING_MOUSE => self.mouse = !self.mouse,
+            SETTING_PAUSED_CLICKS => self.paused_clicks = !self.paused_clicks,
+            SETTING_SOUND => self.sound = !self.sound,
+            SETTING_ACHIEVEMENT_BONUS => {
+                self.achievement_bonus = !self.achievement_bonus;
+                self.production_changed();
+            },
+            SETTING_RESERVE => {
+                let step = BigNum::from(RESERVE_STEP);
+                self.reserve = match (forward, self.reserve < step) {
+                    (true, true) => step,
+                    (true, false) => self.reserve * BigNum::from(10),
+                    (false, true) => BigNum::ZERO,
+                    (false, false) => (self.reserve * 0.1).floor(),
+                };
+            },
+            SETTING_TICK_RATE => {
+                let len = TICK_RATES_MS.len();
+                let current = TICK_RATES_MS.iter().position(|&ms| ms == self.tick_ms).unwrap_or(0);
+                let next = if forward { (current + 1) % len } else { (current + len - 1) % len };
+                self.tick_ms = TICK_RATES_MS[next];
+            },
+            _ => {},
+        }
+    }
+    
+    // Upgrades may dip into the reserve, but only after confirmation
+    fn upgrade_breaks_reserve(&self, index: usize) -> bool {
+        match self.upgrades.get(index) {
+            Some(upgrade) => {
+                !upgrade.purchased && !self.sandbox
+                    && self.points >= BigNum::from(upgrade.cost) && !self.can_spend(BigNum::from(upgrade.cost))
+            },
+            None => false,
+        }
+    }
+    
+    fn buy_building(&mut self, key: &str) -> bool {
+        let can_spend = match self.building(key) {
+            Some(building) => self.can_spend(building.current_cost()),
+            None => false,
+        };
+        if !can_spend {
+            return false;
+        }
+        
+        if let Some(index) = self.building_index(key) {
+            let building = &mut self.buildings[index];
+            let cost = if self.sandbox { BigNum::ZERO } else { building.current_cost() };
+            if self.points >= cost {
+                self.points -= cost;
+                building.buy();
+                let (name, count) = (building.name.clone(), building.count);
+                self.production_changed();
+                self.stats.buildings_bought = self.stats.buildings_bought.saturating_add(1);
+                self.stats.souls_spent = self.stats.souls_spent.saturating_add(cost.to_u64());
+                self.record_purchase(name, cost.to_u64(), count);
+                self.check_achievements();
+                return true;
+            }
+        }
+        false
+    }
+    
+    // Tells the player why a minion could not be summoned
+    fn refuse_building(&mut self, key: &str) {
+        let Some(building) = self.building(key) else {
+            return;
+        };
+        let cost = building.current_cost();
+        let text = if self.points < cost {
+            format!("Not enough followers to summon {}: {} more needed",
+                building.name, format_number(cost - self.points, self.number_format))
+        } else {
+            format!("Summoning {} would dip into your reserve", building.name)
+        };
+        self.notify(text);
+    }
+    
+    // Summons as many of one minion as the followers above the reserve can
+    // pay for, recorded as a single purchase
+    fn buy_max(&mut self, key: &str) -> u64 {
+        let budget = if self.sandbox { BigNum::pow(10.0, 400.0) } else { self.points - self.reserve };
+        let Some(index) = self.building_index(key) else {
+            return 0;
+        };
+        let building = &mut self.buildings[index];
+        let bought = building.max_affordable(budget);
+        if bought == 0 {
+            return 0;
+        }
+        
+        let cost = if self.sandbox { BigNum::ZERO } else { building.cost_for_n(bought) };
+        building.count = building.count.saturating_add(bought);
+        let (name, count) = (building.name.clone(), building.count);
+        self.production_changed();
+        self.points -= cost;
+        self.stats.buildings_bought = self.stats.buildings_bought.saturating_add(bought);
+        self.stats.souls_spent = self.stats.souls_spent.saturating_add(cost.to_u64());
+        self.record_purchase(name, cost.to_u64(), count);
+        self.check_achievements();
+        bought
+    }
+    
+    // Sandbox purchases were free, so selling there refunds nothing
+    fn sell_building(&mut self, key: &str) -> bool {
+        let sandbox = self.sandbox;
+        let Some(index) = self.building_index(key) else {
+            return false;
+        };
+        let building = &mut self.buildings[index];
+        if building.count == 0 {
+            return false;
+        }
+        
+        let refund = building.sell();
+        let refund = if sandbox { BigNum::ZERO } else { refund };
+        let name = building.name.clone();
+        self.production_changed();
+        self.points += refund;
+        self.notify(format!("Released a {} for {} followers", name, format_number(refund, self.number_format)));
+        true
+    }
+    
+    fn buy_upgrade(&mut self, index: usize) -> bool {
+        if index < self.upgrades.len() {
+            let cost = if self.sandbox { 0 } else { self.upgrades[index].cost };
+            if !self.upgrades[index].purchased && self.points >= BigNum::from(cost) {
+                self.points -= BigNum::from(cost);
+                self.upgrades[index].purchased = true;
+                self.production_changed();
+                self.upgrades[index].produced_at_purchase = Some(self.produced_points);
+                self.stats.upgrades_bought = self.stats.upgrades_bought.saturating_add(1);
+                self.stats.souls_spent = self.stats.souls_spent.saturating_add(cost);
+                let name = self.upgrades[index].name.clone();
+                self.record_purchase(name, cost, 1);
+                self.check_achievements();
+                return true;
+            }
+        }
+        false
+    }
+    
+    // Tells the player why an artifact could not be bought
+    fn refuse_upgrade(&mut self, index: usize) {
+        let Some(upgrade) = self.upgrades.get(index) else {
+            return;
+        };
+        let text = if upgrade.purchased {
+            format!("{} is already yours", upgrade.name)
+        } else {
+            format!("Not enough followers for {}: {} more needed",
+                upgrade.name, format_number(BigNum::from(upgrade.cost) - self.points, self.number_format))
+        };
+        self.notify(text);
+    }
+    
+    // Relics are paid for with eldritch shards, or free in the sandbox
+    fn buy_prestige_upgrade(&mut self, index: usize) -> bool {
+        let sandbox = self.sandbox;
+        let Some(relic) = self.prestige_upgrades.get_mut(index) else {
+            return false;
+        };
+        let cost = if sandbox { 0 } else { relic.cost };
+        if relic.purchased || self.shards < cost {
+            return false;
+        }
+        self.shards -= cost;
+        relic.purchased = true;
+        self.production_changed();
+        self.check_achievements();
+        true
+    }
+    
+    fn refuse_prestige_upgrade(&mut self, index: usize) {
+        let Some(relic) = self.prestige_upgrades.get(index) else {
+            return;
+        };
+        let text = if relic.purchased {
+            format!("{} is already yours", relic.name)
+        } else {
+            format!("Not enough eldritch shards for {}: {} more needed", relic.name, relic.cost - self.shards)
+        };
+        self.notify(text);
+    }
+    
+    // The affordable purchase adding the most production per follower spent.
+    // Upgrades that don't add production (click power only) rank last.
+    fn best_purchase(&self) -> Option<Action> {
+        let mut best: Option<(f64, Action)> = None;
+        let mut consider = |value: f64, action: Action| {
+            if best.as_ref().is_none_or(|(best_value, _)| value > *best_value) {
+                best = Some((value, action));
+            }
+        };
+        
+        for building in &self.buildings {
+            let cost = building.current_cost();
+            if self.can_spend(cost) {
+                let gain = building.base_production * self.building_multiplier(&building.key);
+                consider(gain / cost.to_f64().max(1.0), Action::BuyBuilding(building.key.clone()));
+            }
+        }
+        
+        for (index, upgrade) in self.upgrades.iter().enumerate() {
+            if upgrade.purchased || !self.can_spend(BigNum::from(upgrade.cost)) {
+                continue;
+            }
+            consider(self.upgrade_gain(upgrade) / upgrade.cost.max(1) as f64, Action::BuyUpgrade(index));
+        }
+        
+        best.map(|(_, action)| action)
+    }
+    
+    // Production per second an upgrade would add at the current counts
+    fn upgrade_gain(&self, upgrade: &Upgrade) -> f64 {
+        match &upgrade.building_multiplier {
+            Some((key, mult)) if key == "all" => self.production_per_second() * (mult - 1.0),
+            Some((key, mult)) => self.effective_production(key) * (mult - 1.0),
+            None => 0.0,
+        }
+    }
+    
+    // Keeps buying the best value purchase until nothing more is affordable
+    fn spend_all(&mut self) {
+        let points_before = self.points;
+        let mut purchases = 0;
+        
+        // Purchases are free in the sandbox, so cap how many one press can make
+        while purchases < SPEND_ALL_LIMIT {
+            let bought = match self.best_purchase() {
+                Some(Action::BuyBuilding(key)) => self.buy_building(&key),
+                Some(Action::BuyUpgrade(index)) => self.buy_upgrade(index),
+                _ => false,
+            };
+            if !bought {
+                break;
+            }
+            purchases += 1;
+        }
+        
+        if purchases == 0 {
+            self.notify("Nothing you can afford".to_string());
+        } else {
+            self.notify(format!("Spent {} followers on {} purchases", format_number(points_before - self.points, self.number_format), purchases));
+        }
+    }
+    
+    // The purchase `strategy` makes next, None when nothing is affordable
+    fn strategy_purchase(&self, strategy: Strategy) -> Option<Action> {
+        match strategy {
+            Strategy::BuyBestEfficiency => self.best_purchase(),
+            Strategy::BuyCheapest => {
+                let buildings = self.buildings.iter()
+                    .map(|building| (building.current_cost(), Action::BuyBuilding(building.key.clone())));
+                let upgrades = self.upgrades.iter().enumerate()
+                    .filter(|(_, upgrade)| !upgrade.purchased)
+                    .map(|(index, upgrade)| (BigNum::from(upgrade.cost), Action::BuyUpgrade(index)));
+                buildings.chain(upgrades)
+                    .filter(|(cost, _)| self.can_spend(*cost))
+                    .min_by_key(|(cost, _)| *cost)
+                    .map(|(_, action)| action)
+            },
+        }
+    }
+    
+    // Buys what `strategy` picks until nothing more is affordable, quietly,
+    // and returns how many purchases were made
+    pub fn auto_buy(&mut self, strategy: Strategy) -> usize {
+        let mut purchases = 0;
+        while purchases < SPEND_ALL_LIMIT {
+            let bought = match self.strategy_purchase(strategy) {
+                Some(Action::BuyBuilding(key)) => self.buy_building(&key),
+                Some(Action::BuyUpgrade(index)) => self.buy_upgrade(index),
+                _ => false,
+            };
+            if !bought {
+                break;
+            }
+            purchases += 1;
+        }
+        purchases
+    }
+    
+    // Buys every artifact still affordable, cheapest first, leaving the reserve alone
+    fn buy_all_upgrades(&mut self) {
+        let points_before = self.points;
+        let mut order: Vec<usize> = (0..self.upgrades.len()).collect();
+        order.sort_by_key(|&index| self.upgrades[index].cost);
+        
+        let mut purchases = 0;
+        for index in order {
+            let upgrade = &self.upgrades[index];
+            if !upgrade.purchased && self.can_spend(BigNum::from(upgrade.cost)) && self.buy_upgrade(index) {
+                purchases += 1;
+            }
+        }
+        
+        if purchases == 0 {
+            self.notify("No artifact you can afford".to_string());
+        } else {
+            self.notify(format!("Spent {} followers on {} artifacts", format_number(points_before - self.points, self.number_format), purchases));
+        }
+    }
+    
+    fn record_purchase(&mut self, item: String, cost: u64, count_after: u64) {
+        if self.purchase_history.len() == HISTORY_LIMIT {
+            self.purchase_history.pop_front();
+        }
+        self.purchase_history.push_back(PurchaseRecord {
+            timestamp: unix_now(),
+            item,
+            cost,
+            count_after,
+        });
+    }
+    
+    // Saving and quitting need more than the state and are handled by `dispatch`
+    pub fn apply_action(&mut self, action: &Action) {
+        self.dirty = true;
+        if self.confirmation.is_some() {
+            if let Action::Answer(yes) = action {
+                self.answer_confirmation(*yes);
+            }
+            return;
+        }
+        match action {
+            Action::Click => self.click(),
+            Action::TogglePause => self.paused = !self.paused,
+            Action::ToggleSandbox => self.toggle_sandbox(),
+            Action::ToggleReserve => self.toggle_reserve(),
+            Action::SpendAll if self.current_menu == Menu::Upgrades => self.buy_all_upgrades(),
+            Action::SpendAll => self.spend_all(),
+            Action::CatchGolden => self.catch_golden(),
+            // Prompts are drawn in the Sanctum header
+            Action::StartRename if self.current_menu == Menu::Main => {
+                self.prompt = Some(Prompt { kind: PromptKind::Rename, text: self.profile_name.clone() });
+            },
+            Action::StartImport if self.current_menu == Menu::Main => {
+                self.prompt = Some(Prompt { kind: PromptKind::Import, text: String::new() });
+            },
+            Action::StartReset if self.current_menu == Menu::Main => {
+                self.confirmation = Some(Confirmation::Reset);
+            },
+            Action::StartAscension if matches!(self.current_menu, Menu::Main | Menu::Victory) => {
+                self.current_menu = Menu::Ascension;
+                self.selected_index = 0;
+            },
+            // Without a token to gain there is nothing to confirm
+            Action::StartRebirth if self.current_menu == Menu::Rebirth => {
+                if rebirth::tokens_from_shards(self.shards) > 0 {
+                    self.confirmation = Some(Confirmation::Rebirth);
+                } else {
+                    self.notify(format!("Rebirth needs at least {} eldritch shards", rebirth::SHARDS_PER_TOKEN));
+                }
+            },
+            Action::StartRename | Action::StartImport | Action::StartReset | Action::StartAscension | Action::StartRebirth => {},
+            Action::ShowExport => {
+                if self.current_menu == Menu::Main {
+                    match self.export_string() {
+                        Ok(text) => {
+                            self.export_text = Some(text);
+                            self.current_menu = Menu::Export;
+                        },
+                        Err(err) => self.notify(format!("Could not export: {}", err)),
+                    }
+                }
+            },
+            Action::PromptInput(ch) => self.type_into_prompt(&ch.to_string()),
+            Action::PromptPaste(text) => self.type_into_prompt(text),
+            Action::PromptBackspace => {
+                if let Some(prompt) = &mut self.prompt {
+                    prompt.text.pop();
+                }
+            },
+            Action::PromptConfirm => {
+                if let Some(prompt) = self.prompt.take() {
+                    match prompt.kind {
+                        PromptKind::Rename => self.profile_name = prompt.text.trim().to_string(),
+                        PromptKind::Import => match GameState::from_import_string(&prompt.text) {
+                            Ok(state) => self.confirmation = Some(Confirmation::Import(Box::new(state))),
+                            Err(err) => self.notify(format!("Could not import: {}", err)),
+                        },
+                        PromptKind::Reserve => match parse_reserve(&prompt.text) {
+                            Some(amount) => self.reserve = amount,
+                            None => self.notify(format!("'{}' is not a number of followers", prompt.text.trim())),
+                        },
+                    }
+                }
+            },
+            Action::PromptCancel => self.prompt = None,
+            Action::Answer(_) => {},
+            Action::SetProfileName(name) => self.profile_name = name.trim().to_string(),
+            Action::SetReserve(amount) => self.reserve = *amount,
+            Action::ToggleUpgradeLines => {
+                self.upgrade_lines = if self.upgrade_lines == 3 { 2 } else { 3 };
+            },
+            Action::BuyMax if self.current_menu == Menu::Buildings => {
+                if let Some(key) = self.sorted_building_keys().get(self.selected_index) {
+                    let key = key.clone();
+                    if self.buy_max(&key) == 0 {
+                        self.refuse_building(&key);
+                    }
+                }
+            },
+            Action::BuyMax => {},
+            Action::SellSelection if self.current_menu == Menu::Buildings => {
+                if let Some(key) = self.sorted_building_keys().get(self.selected_index) {
+                    let key = key.clone();
+                    self.sell_building(&key);
+                }
+            },
+            Action::SellSelection => {},
+            Action::QuickBuy(row) if self.current_menu == Menu::Buildings => {
+                if *row < self.buildings.len() {
+                    self.select(*row);
+                    self.buy_row(*row);
+                }
+            },
+            Action::QuickBuy(_) => {},
+            Action::MouseClick(y) => match self.current_menu {
+                Menu::Main => self.click(),
+                Menu::Buildings | Menu::Upgrades => {
+                    if let Some(index) = self.list_index_at(*y) {
+                        self.select(index);
+                        self.apply_action(&Action::ConfirmSelection);
+                    }
+                },
+                _ => {},
+            },
+            Action::CycleBuildingSort if self.current_menu == Menu::Buildings => {
+                self.building_sort = self.building_sort.next();
+                self.select(0);
+            },
+            Action::CycleBuildingSort => {},
+            Action::CycleBuyAmount => {
+                self.buy_amount = match self.buy_amount {
+                    1 => 10,
+                    10 => 100,
+                    _ => 1,
+                };
+            },
+            Action::ClearHistory => {
+                if self.current_menu == Menu::History {
+                    self.purchase_history.clear();
+                    self.selected_index = 0;
+                }
+            },
+            Action::Save | Action::Quit => {},
+            Action::OpenMenu(menu) => {
+                self.offline_summary = None;
+                self.export_text = None;
+                self.current_menu = menu.clone();
+                self.selected_index = 0;
+                self.scroll_offset = 0;
+                self.pending_upgrade = None;
+            },
+            Action::SelectPrevious => {
+                self.select(self.selected_index.saturating_sub(1));
+            },
+            Action::SelectNext => {
+                self.select(self.selected_index + 1);
+            },
+            Action::SelectFirst => self.select(0),
+            Action::SelectLast => self.select(self.list_len().saturating_sub(1)),
+            Action::PagePrevious => {
+                self.select(self.selected_index.saturating_sub(self.list_rows()));
+            },
+            Action::PageNext => {
+                self.select(self.selected_index + self.list_rows());
+            },
+            Action::ConfirmSelection => {
+                match self.current_menu {
+                    Menu::Buildings => self.buy_row(self.selected_index),
+                    Menu::Upgrades => {
+                        let index = self.selected_index;
+                        if self.upgrade_breaks_reserve(index) && self.pending_upgrade != Some(index) {
+                            // Ask first, a second Enter goes through
+                            self.pending_upgrade = Some(index);
+                        } else {
+                            self.pending_upgrade = None;
+                            if !self.buy_upgrade(index) {
+                                self.refuse_upgrade(index);
+                            }
+                        }
+                    },
+                    Menu::Victory => match self.selected_index {
+                        // Keep playing among the ruins
+                        VICTORY_KEEP_PLAYING => self.current_menu = Menu::Main,
+                        VICTORY_ASCEND => self.apply_action(&Action::StartAscension),
+                        VICTORY_START_OVER => self.confirmation = Some(Confirmation::Reset),
+                        _ => {},
+                    },
+                    Menu::Export => {
+                        self.export_text = None;
+                        self.current_menu = Menu::Main;
+                    },
+                    // Without a shard to gain there is nothing to confirm
+                    Menu::Ascension if GameState::shards_from_lifetime(self.lifetime_points) > 0 => {
+                        self.confirmation = Some(Confirmation::Ascend);
+                    },
+                    Menu::Relics => {
+                        let index = self.selected_index;
+                        if !self.buy_prestige_upgrade(index) {
+                            self.refuse_prestige_upgrade(index);
+                        }
+                    },
+                    Menu::Rebirth => {
+                        let index = self.selected_index;
+                        if !self.buy_skill(index) {
+                            self.refuse_skill(index);
+                        }
+                    },
+                    Menu::Settings if self.selected_index == SETTING_RESERVE => {
+                        self.prompt = Some(Prompt { kind: PromptKind::Reserve, text: String::new() });
+                    },
+                    Menu::Settings => {
+                        self.change_setting(true);
+                    },
+                    _ => {}
+                }
+            },
+            Action::SettingPrevious | Action::SettingNext => {
+                if self.current_menu == Menu::Settings {
+                    self.change_setting(matches!(action, Action::SettingNext));
+                }
+            },
+            Action::BuyBuilding(key) => {
+                if !self.buy_building(key) {
+                    self.refuse_building(key);
+                }
+            },
+            Action::BuyUpgrade(index) => {
+                if !self.buy_upgrade(*index) {
+                    self.refuse_upgrade(*index);
+                }
+            },
+            Action::BuyPrestigeUpgrade(index) => {
+                if !self.buy_prestige_upgrade(*index) {
+                    self.refuse_prestige_upgrade(*index);
+                }
+            },
+            Action::BuySkill(index) => {
+                if !self.buy_skill(*index) {
+                    self.refuse_skill(*index);
+                }
+            },
+        }
+    }
+    
+    pub fn save_game(&self, dir: &Path, slot: &str) -> IoResult<()> {
+        let result = self.write_save(dir, slot);
+        match &result {
+            Ok(()) => debug!("Saved the slot '{}' in {}", slot, dir.display()),
+            Err(err) => error!("Could not save the slot '{}' in {}: {}", slot, dir.display(), err),
+        }
+        result
+    }
+    
+    fn write_save(&self, dir: &Path, slot: &str) -> IoResult<()> {
+        fs::create_dir_all(dir)?;
+        
+        // Loading skips backups that don't read, so any save is moved along
+        let path = slots::slot_path(dir, slot);
+        if path.exists() {
+            save::rotate_backups(&path)?;
+        }
+        
+        let contents = self.save_contents()?;
+        save::write_atomic(&path, |file| file.write_all(contents.as_bytes()))
+    }
+    
+    // The sealed JSON written to save files and export strings
+    fn save_contents(&self) -> IoResult<String> {
+        let mut value = serde_json::to_value(self)?;
+        value["last_saved_at"] = serde_json::json!(unix_now());
+        Ok(save::seal(&serde_json::to_string_pretty(&value)?))
+    }
+    
+    // The whole state as plain JSON, unsealed, for scripts to read
+    pub fn dump_json(&self) -> IoResult<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+    
+    fn export_string(&self) -> IoResult<String> {
+        save::encode_transfer(&self.save_contents()?)
+    }
+    
+    // Reads the state held by an exported save string
+    fn from_import_string(text: &str) -> Result<GameState, String> {
+        let contents = save::decode_transfer(text)?;
+        if !save::is_sealed(&contents) {
+            return Err("the save string has no checksum".to_string());
+        }
+        GameState::from_json_or_legacy(&contents)
+    }
+    
+    // Carries out the pending confirmation on a yes and drops it either way
+    fn answer_confirmation(&mut self, yes: bool) {
+        let Some(confirmation) = self.confirmation.take() else {
+            return;
+        };
+        if !yes {
+            return;
+        }
+        match confirmation {
+            Confirmation::Reset => {
+                self.reset();
+                match save::remove_with_backups(&slots::slot_path(&self.save_dir, &self.slot)) {
+                    Ok(()) => self.notify("Your dominion has been banished".to_string()),
+                    Err(err) => self.notify(format!("Could not delete the save: {}", err)),
+                }
+            },
+            Confirmation::Ascend => {
+                self.ascend();
+            },
+            Confirmation::Rebirth => {
+                self.be_reborn();
+            },
+            Confirmation::Import(state) => {
+                self.replace_progress(*state);
+                self.notify("Your dominion has been restored from the string".to_string());
+            },
+        }
+    }
+    
+    // Trades this run's progress for eldritch shards and returns to the
+    // Sanctum. Playtime, deeds and the annals carry over. Returns false when
+    // no shard would be gained.
+    fn ascend(&mut self) -> bool {
+        let gained = GameState::shards_from_lifetime(self.lifetime_points);
+        if gained == 0 {
+            return false;
+        }
+        
+        let fresh = GameState::new();
+        self.shards = self.shards.saturating_add(gained);
+        self.points = BigNum::ZERO;
+        self.lifetime_points = BigNum::ZERO;
+        self.produced_points = 0;
+        self.click_power = fresh.click_power;
+        self.buildings = fresh.buildings;
+        self.upgrades = fresh.upgrades;
+        self.production_remainder = 0.0;
+        self.auto_click_remainder = 0.0;
+        self.reserve = BigNum::ZERO;
+        self.pending_upgrade = None;
+        self.milestones.clear();
+        self.recent_clicks.clear();
+        self.buffs.clear();
+        self.apply_skills();
+        self.current_menu = Menu::Main;
+        self.production_changed();
+        info!("Ascended for {} shards, {} in all", gained, self.shards);
+        self.notify(format!("You ascend, carrying {} eldritch shards into the next cycle", self.shards));
+        true
+    }
+    
+    // Trades every shard and relic, along with this run, for rebirth tokens.
+    // Skills, playtime, deeds and achievements carry over. Returns false when
+    // no token would be gained.
+    fn be_reborn(&mut self) -> bool {
+        let gained = rebirth::tokens_from_shards(self.shards);
+        if gained == 0 {
+            return false;
+        }
+        
+        let fresh = GameState::new();
+        self.rebirth_tokens = self.rebirth_tokens.saturating_add(gained);
+        self.shards = 0;
+        self.prestige_upgrades = fresh.prestige_upgrades;
+        self.points = BigNum::ZERO;
+        self.lifetime_points = BigNum::ZERO;
+        self.produced_points = 0;
+        self.click_power = fresh.click_power;
+        self.buildings = fresh.buildings;
+        self.upgrades = fresh.upgrades;
+        self.production_remainder = 0.0;
+        self.auto_click_remainder = 0.0;
+        self.reserve = BigNum::ZERO;
+        self.pending_upgrade = None;
+        self.milestones.clear();
+        self.recent_clicks.clear();
+        self.buffs.clear();
+        self.apply_skills();
+        self.current_menu = Menu::Rebirth;
+        self.selected_index = 0;
+        info!("Reborn for {} tokens, {} unspent", gained, self.rebirth_tokens);
+        self.notify(format!("You are reborn with {} rebirth tokens to spend", self.rebirth_tokens));
+        true
+    }
+    
+    // Skill nodes are paid for with rebirth tokens, or free in the sandbox,
+    // once every node they require is owned
+    fn buy_skill(&mut self, index: usize) -> bool {
+        let Some(node) = rebirth::NODES.get(index) else {
+            return false;
+        };
+        let cost = if self.sandbox { 0 } else { node.cost };
+        if self.skills.iter().any(|key| key == node.key) || !rebirth::unlocked(node, &self.skills) || self.rebirth_tokens < cost {
+            return false;
+        }
+        self.rebirth_tokens -= cost;
+        self.skills.push(node.key.to_string());
+        self.apply_skills();
+        self.notify(format!("{} is yours", node.name));
+        true
+    }
+    
+    fn refuse_skill(&mut self, index: usize) {
+        let Some(node) = rebirth::NODES.get(index) else {
+            return;
+        };
+        let text = if self.skills.iter().any(|key| key == node.key) {
+            format!("{} is already yours", node.name)
+        } else if !rebirth::unlocked(node, &self.skills) {
+            let names: Vec<&str> = node.requires.iter().filter_map(|key| rebirth::node(key)).map(|node| node.name).collect();
+            format!("{} needs {} first", node.name, names.join(", "))
+        } else {
+            format!("Not enough rebirth tokens for {}: {} more needed", node.name, node.cost - self.rebirth_tokens)
+        };
+        self.notify(text);
+    }
+    
+    // Brings the minion prices and influence power in line with the skills
+    // owned. Call after the skills or the buildings are replaced.
+    fn apply_skills(&mut self) {
+        let price_factor = rebirth::price_factor(&self.skills);
+        for building in &mut self.buildings {
+            building.price_factor = price_factor;
+        }
+        self.check_click_power_upgrade();
+    }
+    
+    // Starts the run over from nothing
+    fn reset(&mut self) {
+        self.replace_progress(GameState::new());
+    }
+    
+    // Keeps the slot and this session's command-line settings
+    fn replace_progress(&mut self, state: GameState) {
+        *self = GameState {
+            slot: self.slot.clone(),
+            save_dir: self.save_dir.clone(),
+            paused: self.paused,
+            debug_mode: self.debug_mode,
+            focused: self.focused,
+            unfocused_rate: self.unfocused_rate,
+            rounding: self.rounding,
+            rng: self.rng.clone(),
+            autosave_secs: self.autosave_secs,
+            terminal_size: self.terminal_size,
+            ..state
+        };
+        self.apply_skills();
+    }
+    
+    fn type_into_prompt(&mut self, text: &str) {
+        if let Some(prompt) = &mut self.prompt {
+            let limit = prompt.limit();
+            for ch in text.chars().filter(|ch| !ch.is_control()) {
+                if prompt.text.chars().count() >= limit {
+                    break;
+                }
+                prompt.text.push(ch);
+            }
+        }
+    }
+    
+    // Replaces the state with the newest save of the slot that loads, or
+    // leaves it untouched if none does
+    pub fn load_game(&mut self, dir: &Path, slot: &str) -> IoResult<()> {
+        let path = slots::slot_path(dir, slot);
+        
+        let result = GameState::load_latest_valid(dir, slot);
+        let bad_path = path.with_extension("save.bad");
+        let mut kept_bad = false;
+        if !matches!(result, Ok(None) | Ok(Some((_, 0)))) && path.exists() {
+            // Keep a copy of the unreadable file for the player to look at.
+            // Not being able to is no reason to refuse the backups.
+            match fs::copy(&path, &bad_path) {
+                Ok(_) => kept_bad = true,
+                Err(err) => warn!("Could not keep the unreadable save as {}: {}", bad_path.display(), err),
+            }
+        }
+        
+        match result {
+            Ok(None) => {
+                info!("No save in the slot '{}', starting a new run", slot);
+                Ok(())
+            },
+            Ok(Some((state, generation))) => {
+                *self = GameState { slot: slot.to_string(), save_dir: dir.to_path_buf(), ..state };
+                self.apply_skills();
+                if generation > 0 {
+                    warn!("The save in the slot '{}' could not be read, restored backup {}", slot, generation);
+                    self.notify(format!("The save could not be read, restored backup {}", generation));
+                } else {
+                    info!("Loaded the slot '{}' from {}", slot, path.display());
+                }
+                Ok(())
+            },
+            Err(err) => {
+                let message = if kept_bad {
+                    format!("{} (kept as {})", err, bad_path.display())
+                } else {
+                    err
+                };
+                warn!("Could not load the slot '{}': {}", slot, message);
+                Err(io::Error::new(io::ErrorKind::InvalidData, message))
+            },
+        }
+    }
+    
+    // Credits the production of the time since the last save, up to `cap`
+    pub fn apply_offline_progress(&mut self, cap: Duration) {
+        if self.last_saved_at == 0 {
+            return;
+        }
+        // Skills lengthen the cap, unless offline progress is turned off
+        let cap = if cap.is_zero() {
+            cap
+        } else {
+            cap + Duration::from_secs_f64(rebirth::offline_hours(&self.skills) * 3600.0)
+        };
+        let elapsed = unix_now().saturating_sub(self.last_saved_at).min(cap.as_secs());
+        
+        let points_before = self.points;
+        self.credit_production(elapsed as f64);
+        let gained = self.points - points_before;
+        if !gained.is_zero() {
+            self.offline_summary = Some(format!("While you were gone, your cult gained {} followers.", format_number(gained, self.number_format)));
+        }
+    }
+    
+    // Tries the slot file and then its backups, newest first. Returns the
+    // first that loads with its backup generation (0 for the slot file
+    // itself), None for a slot that was never saved, or the first error.
+    fn load_latest_valid(dir: &Path, slot: &str) -> Result<Option<(GameState, usize)>, String> {
+        let path = slots::slot_path(dir, slot);
+        let mut first_error = None;
+        
+        for generation in 0..=save::BACKUPS {
+            let candidate = if generation == 0 { path.clone() } else { save::backup_path(&path, generation) };
+            if !candidate.exists() {
+                continue;
+            }
+            match GameState::read_save(&candidate) {
+                Ok(state) => return Ok(Some((state, generation))),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                },
+            }
+        }
+        
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(None),
+        }
+    }
+    
+    fn read_save(path: &Path) -> Result<GameState, String> {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|err| err.to_string())?;
+        GameState::from_json_or_legacy(&contents)
+    }
+    
+    fn from_json_or_legacy(contents: &str) -> Result<GameState, String> {
+        let contents = save::unseal(contents)?;
+        let mut state = match serde_json::from_str::<serde_json::Value>(contents) {
+            Ok(value) => {
+                let value = save::migrate(save::save_version(&value), value)?;
+                serde_json::from_value(value).map_err(|err| err.to_string())?
+            },
+            // Saves from before the switch to JSON
+            Err(_) if contents.trim_start().starts_with("points:") => GameState::from_legacy_save(contents)?,
+            Err(err) => return Err(err.to_string()),
+        };
+        
+        if state.upgrade_lines != 2 && state.upgrade_lines != 3 {
+            state.upgrade_lines = 3;
+        }
+        if ![1, 10, 100].contains(&state.buy_amount) {
+            state.buy_amount = 1;
+        }
+        if !TICK_RATES_MS.contains(&state.tick_ms) {
+            state.tick_ms = DEFAULT_TICK_MS;
+        }
+        if !(0.0..1.0).contains(&state.production_remainder) {
+            state.production_remainder = 0.0;
+        }
+        // Check if click power should be upgraded based on lifetime points
+        state.check_click_power_upgrade();
+        
+        Ok(state)
+    }
+    
+    // Builds a fresh state from the old `key:value` save format. Unknown keys
+    // are skipped, but a malformed line fails the whole load.
+    fn from_legacy_save(contents: &str) -> Result<GameState, String> {
+        let mut state = GameState::new();
+        
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parts: Vec<&str> = line.split(':').collect();
+            let error = || format!("line {}: malformed entry '{}'", number + 1, line);
+            if parts.len() < 2 {
+                return Err(error());
+            }
+            
+            match parts[0] {
+                "points" => state.points = parse_field(parts[1]).ok_or_else(error)?,
+                "lifetime" => state.lifetime_points = parse_field(parts[1]).ok_or_else(error)?,
+                "sandbox" => state.sandbox = parse_field(parts[1]).ok_or_else(error)?,
+                "sandbox_tainted" => state.sandbox_tainted = parse_field(parts[1]).ok_or_else(error)?,
+                "reserve" => state.reserve = parse_field(parts[1]).ok_or_else(error)?,
+                "playtime" => state.add_playtime(parse_field(parts[1]).ok_or_else(error)?),
+                "victory" => state.victory_reached = parse_field(parts[1]).ok_or_else(error)?,
+                "produced" => state.produced_points = parse_field(parts[1]).ok_or_else(error)?,
+                "click_power" => state.click_power = parse_field(parts[1]).ok_or_else(error)?,
+                "profile" => state.profile_name = parts[1..].join(":"),
+                "upgrade_lines" => {
+                    state.upgrade_lines = parse_field(parts[1])
+                        .filter(|lines| *lines == 2 || *lines == 3)
+                        .ok_or_else(error)?;
+                },
+                "milestone" if parts.len() >= 4 => {
+                    state.milestones.push(Milestone {
+                        tier: parts[3..].join(":"),
+                        timestamp: parse_field(parts[1]).ok_or_else(error)?,
+                        playtime_secs: parse_field(parts[2]).ok_or_else(error)?,
+                    });
+                },
+                "building" if parts.len() >= 4 => {
+                    let count = parse_field(parts[2]).ok_or_else(error)?;
+                    let base_production = parse_field::<f64>(parts[3])
+                        .filter(|production| production.is_finite())
+                        .ok_or_else(error)?;
+                    if let Some(building) = state.buildings.iter_mut().find(|building| building.key == parts[1]) {
+                        building.count = count;
+                        building.base_production = base_production.clamp(0.0, MAX_BASE_PRODUCTION);
+                    }
+                },
+                "history" if parts.len() >= 5 => {
+                    let timestamp = parse_field(parts[1]).ok_or_else(error)?;
+                    let cost = parse_field(parts[2]).ok_or_else(error)?;
+                    let count_after = parse_field(parts[3]).ok_or_else(error)?;
+                    state.record_purchase(parts[4..].join(":"), cost, count_after);
+                    if let Some(record) = state.purchase_history.back_mut() {
+                        record.timestamp = timestamp;
+                    }
+                },
+                "upgrade" if parts.len() >= 3 => {
+                    let index: usize = parse_field(parts[1]).ok_or_else(error)?;
+                    let purchased = parse_field(parts[2]).ok_or_else(error)?;
+                    // Older saves have no snapshot field
+                    let snapshot = match parts.get(3) {
+                        Some(val) => Some(parse_field(val).ok_or_else(error)?),
+                        None => None,
+                    };
+                    if let Some(upgrade) = state.upgrades.get_mut(index) {
+                        upgrade.purchased = purchased;
+                        upgrade.produced_at_purchase = snapshot;
+                    }
+                },
+                "milestone" | "building" | "history" | "upgrade" => return Err(error()),
+                _ => {}
+            }
+        }
+        
+        Ok(state)
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.parse().ok()
+}
+
+// A reserve typed by the player or a replay: a whole number of followers,
+// with or without grouping commas, or in the save's `<mantissa>e<exponent>` form
+fn parse_reserve(text: &str) -> Option<BigNum> {
+    text.trim().replace(',', "").parse().ok()
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+// Rows each scrolling list fits between its header and the two footer lines.
+// The Minions menu also keeps a line for the selected minion's progress bar.
+pub fn buildings_visible(height: u16, row_height: u16) -> usize {
+    (height.saturating_sub(7) / row_height) as usize
+}
+
+pub fn upgrades_visible(height: u16, lines: u16) -> usize {
+    (height.saturating_sub(6) / lines) as usize
+}
+
+pub fn achievements_visible(height: u16) -> usize {
+    (height.saturating_sub(6) / 2) as usize
+}
+
+pub fn help_visible(height: u16) -> usize {
+    height.saturating_sub(4) as usize
+}
+
+// A heading and a blank line per section, then one line per entry
+pub fn help_line_count() -> usize {
+    help::SECTIONS.iter().map(|section| section.entries.len() + 2).sum()
+}
+
+// Lifetime converts needed for each domination tier, in ascending order
+const DOMINATION_TIERS: [(u64, &str); 8] = [
+    (0, "Local Cult (Town)"),
+    (1_000, "Regional Influence (County)"),
+    (10_000, "National Presence (Country)"),
+    (100_000, "Continental Power (Continent)"),
+    (1_000_000, "Global Reach (Earth)"),
+    (10_000_000, "Cosmic Influence (Solar System)"),
+    (100_000_000, "Galactic Dominion (Galaxy)"),
+    (VICTORY_THRESHOLD, "Universal Awakening (Cthulhu Rises!)"),
+];
+
+// Index into DOMINATION_TIERS
+fn domination_tier(lifetime_points: u64) -> usize {
+    DOMINATION_TIERS.iter()
+        .rposition(|&(threshold, _)| lifetime_points >= threshold)
+        .unwrap_or(0)
+}
+
+pub fn get_domination_status(lifetime_points: u64) -> String {
+    DOMINATION_TIERS[domination_tier(lifetime_points)].1.to_string()
+}
+
+// Seconds until the next tier at the given rate, None at the last tier or
+// when nothing is being gained
+pub fn next_tier_eta(lifetime_points: u64, rate: f64) -> Option<u64> {
+    let (threshold, _) = DOMINATION_TIERS.get(domination_tier(lifetime_points) + 1)?;
+    if rate <= 0.0 {
+        return None;
+    }
+    Some(((threshold - lifetime_points) as f64 / rate).ceil() as u64)
+}
+
+// Followers paid per follower per second of production, lower being better.
+// None for a purchase that adds no production, or too much to compare.
+fn efficiency(cost: BigNum, production: f64) -> Option<f64> {
+    let ratio = cost.to_f64() / production;
+    (production > 0.0 && ratio.is_finite()).then_some(ratio)
+}
+
+// What the achievement bonus multiplies production by with `unlocked`
+// achievements
+pub fn achievement_bonus_multiplier(unlocked: usize) -> f64 {
+    1.0 + unlocked as f64 * ACHIEVEMENT_BONUS
+}
+
+// Seconds until `deficit` more followers arrive at `rate` per second, None
+// when they never will
+pub fn eta_seconds(deficit: BigNum, rate: f64) -> Option<f64> {
+    if deficit.is_zero() {
+        Some(0.0)
+    } else if rate > 0.0 {
+        Some(deficit.to_f64() / rate)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    // Keeps the warnings logged on each thread, so tests running side by side
+    // only see their own
+    struct CapturingLogger;
+
+    thread_local! {
+        static WARNINGS: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.with(|warnings| warnings.borrow_mut().push(record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    // The warnings `run` logs
+    fn warnings_of(run: impl FnOnce()) -> Vec<String> {
+        static LOGGER: CapturingLogger = CapturingLogger;
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Warn);
+        }
+        WARNINGS.with(|warnings| warnings.borrow_mut().clear());
+        run();
+        WARNINGS.with(|warnings| warnings.take())
+    }
+
+    // A new game without achievements, so unlocking one can't change the
+    // production multiplier partway through a test
+    fn plain_state() -> GameState {
+        let mut state = GameState::new();
+        state.achievements.clear();
+        state
+    }
+
+    #[test]
+    fn high_rates_flush_every_whole_point() {
+        let mut state = plain_state();
+        let cursor = &mut state.buildings[0];
+        cursor.count = 1;
+        cursor.base_production = 1_234_567.25;
+
+        for _ in 0..40 {
+            state.credit_production(0.5);
+            assert!((0.0..1.0).contains(&state.production_remainder));
+        }
+        // 40 ticks of 617,283.625 followers
+        assert_eq!(state.lifetime_points, BigNum::from(24_691_345u64));
+        assert_eq!(state.points, state.lifetime_points);
+        assert_eq!(state.production_remainder, 0.0);
+    }
+
+    #[test]
+    fn bad_rates_reset_the_remainder() {
+        let mut state = GameState::new();
+        let cursor = &mut state.buildings[0];
+        cursor.count = 1;
+        cursor.base_production = f64::NAN;
+        state.production_remainder = 0.5;
+
+        state.credit_production(1.0);
+        assert_eq!(state.production_remainder, 0.0);
+        assert_eq!(state.points, BigNum::from(0u64));
+
+        state.credit_production(-5.0);
+        assert_eq!(state.points, BigNum::from(0u64));
+    }
+
+    #[test]
+    fn floor_rounding_carries_the_fraction() {
+        let mut state = GameState::new();
+        let cursor = &mut state.buildings[0];
+        cursor.count = 1;
+        cursor.base_production = 0.25;
+
+        for _ in 0..3 {
+            state.credit_production(1.0);
+        }
+        assert_eq!(state.points, BigNum::from(0u64));
+        state.credit_production(1.0);
+        assert_eq!(state.points, BigNum::from(1u64));
+    }
+
+    #[test]
+    fn stochastic_rounding_matches_the_real_total_on_average() {
+        let mut state = GameState::new();
+        state.rounding = RoundingMode::Stochastic;
+        state.rng = Rng::new(7);
+        state.buildings[0].count = 3;
+
+        // 0.3 followers a tick, so about 3,000 over 10,000 ticks
+        for _ in 0..10_000 {
+            state.credit_production(1.0);
+        }
+        let total = state.lifetime_points;
+        assert!((2_850..=3_150).contains(&total.to_u64()), "{} followers", total);
+    }
+
+    #[test]
+    fn malformed_lines_fail_the_whole_load() {
+        let err = GameState::from_legacy_save("points:500\nlifetime:800\nbuilding:cursor:3:0.1\nbuilding:grandma:lots:1.0\n").unwrap_err();
+        assert_eq!(err, "line 4: malformed entry 'building:grandma:lots:1.0'");
+        assert!(GameState::from_legacy_save("points").is_err());
+    }
+
+    #[test]
+    fn saves_load_whole() {
+        let state = GameState::from_legacy_save("points:500\nlifetime:800\nbuilding:cursor:3:0.1\nupgrade:0:true\n").unwrap();
+        assert_eq!(state.points, BigNum::from(500u64));
+        assert_eq!(state.lifetime_points, BigNum::from(800u64));
+        assert_eq!(state.buildings[0].count, 3);
+        assert!(state.upgrades[0].purchased);
+    }
+
+    #[test]
+    fn saves_round_trip_through_json() {
+        let mut state = GameState::new();
+        state.points = BigNum::from(1_234u64);
+        state.lifetime_points = BigNum::from(5_678u64);
+        state.buildings[0].count = 12;
+        state.buildings[2].count = 3;
+        state.upgrades[1].purchased = true;
+
+        let json = serde_json::to_string_pretty(&state).unwrap();
+        assert!(json.starts_with('{'));
+        let loaded = GameState::from_json_or_legacy(&json).unwrap();
+        assert_eq!(loaded.points, state.points);
+        assert_eq!(loaded.lifetime_points, state.lifetime_points);
+        for (loaded, building) in loaded.buildings.iter().zip(&state.buildings) {
+            assert_eq!(loaded.count, building.count, "{}", building.key);
+        }
+        let purchased = |state: &GameState| state.upgrades.iter().map(|upgrade| upgrade.purchased).collect::<Vec<_>>();
+        assert_eq!(purchased(&loaded), purchased(&state));
+    }
+
+    #[test]
+    fn loads_migrate_old_saves_and_refuse_newer_ones() {
+        let v1 = r#"{"points": 300, "lifetime_points": 900, "buildings": {"grandma": {"name": "Elder One", "count": 2, "base_production": 1.0}}}"#;
+        let state = GameState::from_json_or_legacy(v1).unwrap();
+        assert_eq!(state.version, save::SAVE_VERSION);
+        assert_eq!(state.points, BigNum::from(300u64));
+        assert_eq!(state.buildings[1].count, 2);
+
+        let newer = format!(r#"{{"version": {}, "points": 1}}"#, save::SAVE_VERSION + 1);
+        let err = GameState::from_json_or_legacy(&newer).unwrap_err();
+        assert!(err.contains("newer version of the game"), "{}", err);
+    }
+
+    #[test]
+    fn tampered_saves_are_refused() {
+        let mut state = GameState::new();
+        state.points = BigNum::from(10u64);
+        let sealed = save::seal(&serde_json::to_string_pretty(&state).unwrap());
+        assert_eq!(GameState::from_json_or_legacy(&sealed).unwrap().points, BigNum::from(10u64));
+
+        let tampered = sealed.replacen("\"mantissa\": 10.0", "\"mantissa\": 99999.0", 1);
+        let err = GameState::from_json_or_legacy(&tampered).unwrap_err();
+        assert_eq!(err, "save is corrupt or was edited (checksum mismatch)");
+    }
+
+    #[test]
+    fn offline_progress_credits_the_time_away_up_to_the_cap() {
+        let away = |cap_hours: f64| {
+            let mut state = GameState::new();
+            state.buildings[0].count = 5; // Half a follower a second
+            state.last_saved_at = unix_now() - 2 * 3600;
+            state.apply_offline_progress(Duration::from_secs_f64(cap_hours * 3600.0));
+            state
+        };
+
+        let state = away(DEFAULT_OFFLINE_CAP_HOURS);
+        assert_eq!(state.points, BigNum::from(3_600u64));
+        assert_eq!(state.lifetime_points, BigNum::from(3_600u64));
+        assert_eq!(state.offline_summary.as_deref(), Some("While you were gone, your cult gained 3600 followers."));
+
+        assert_eq!(away(1.0).points, BigNum::from(1_800u64));
+        assert!(away(0.0).points.is_zero());
+        assert!(away(0.0).offline_summary.is_none());
+    }
+
+    #[test]
+    fn fresh_games_get_no_offline_progress() {
+        let mut state = GameState::new();
+        state.buildings[0].count = 5;
+        state.apply_offline_progress(Duration::from_secs(3600));
+        assert_eq!(state.points, BigNum::from(0u64));
+    }
+
+    #[test]
+    fn production_remainder_survives_a_save() {
+        let mut state = GameState::new();
+        state.production_remainder = 0.625;
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(GameState::from_json_or_legacy(&json).unwrap().production_remainder, 0.625);
+
+        state.production_remainder = 7.5;
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(GameState::from_json_or_legacy(&json).unwrap().production_remainder, 0.0);
+    }
+
+    #[test]
+    fn saved_base_production_is_applied_and_clamped() {
+        let mut state = GameState::new();
+        state.buildings[0].base_production = 2.5;
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(GameState::from_json_or_legacy(&json).unwrap().buildings[0].base_production, 2.5);
+
+        let absurd = r#"{"version": 2, "buildings": {"cursor": {"count": 1, "base_production": 1e300}, "grandma": {"count": 1, "base_production": -4.0}}}"#;
+        let loaded = GameState::from_json_or_legacy(absurd).unwrap();
+        assert_eq!(loaded.buildings[0].base_production, MAX_BASE_PRODUCTION);
+        assert_eq!(loaded.buildings[1].base_production, 0.0);
+
+        let legacy = GameState::from_json_or_legacy("points:0\nbuilding:cursor:2:0.75\n").unwrap();
+        assert_eq!(legacy.buildings[0].base_production, 0.75);
+    }
+
+    #[test]
+    fn imports_replace_the_run_after_confirmation() {
+        let mut exported = GameState::new();
+        exported.points = BigNum::from(777u64);
+        exported.buildings[1].count = 4;
+        let text = exported.export_string().unwrap();
+
+        let mut state = GameState::new();
+        state.apply_action(&Action::StartImport);
+        state.apply_action(&Action::PromptPaste(text));
+        state.apply_action(&Action::PromptConfirm);
+        assert!(matches!(state.confirmation, Some(Confirmation::Import(_))));
+        assert!(state.points.is_zero());
+
+        state.apply_action(&Action::Answer(true));
+        assert!(state.confirmation.is_none());
+        assert_eq!(state.points, BigNum::from(777u64));
+        assert_eq!(state.buildings[1].count, 4);
+        assert_eq!(state.notifications.back().unwrap().text, "Your dominion has been restored from the string");
+    }
+
+    #[test]
+    fn garbage_imports_leave_the_run_alone() {
+        let mut state = GameState::new();
+        state.points = BigNum::from(5u64);
+        state.apply_action(&Action::StartImport);
+        state.apply_action(&Action::PromptPaste("definitely not a save".to_string()));
+        state.apply_action(&Action::PromptConfirm);
+        assert!(state.confirmation.is_none());
+        assert!(state.prompt.is_none());
+        assert_eq!(state.points, BigNum::from(5u64));
+        assert_eq!(state.notifications.back().unwrap().text, "Could not import: that is not a save string");
+
+        // A string without a checksum is refused even if it decodes
+        let unsealed = save::encode_transfer("{\"points\": 5}").unwrap();
+        assert_eq!(GameState::from_import_string(&unsealed).unwrap_err(), "the save string has no checksum");
+    }
+
+    #[test]
+    fn saves_go_to_the_chosen_directory() {
+        let root = TempDir::new("custom-dir");
+        let dir = root.join("nested").join("saves");
+        GameState::new().save_game(&dir, "game").unwrap();
+        assert!(slots::slot_path(&dir, "game").exists());
+
+        let mut state = GameState::new();
+        state.load_game(&dir, "game").unwrap();
+        assert_eq!(state.save_dir, dir);
+        assert_eq!(state.slot, "game");
+    }
+
+    #[test]
+    fn failed_loads_leave_the_state_untouched() {
+        let dir = TempDir::new("half-load");
+        let path = slots::slot_path(&dir, "game");
+        fs::write(&path, "points:500\nlifetime:800\nbuilding:cursor:3:0.1\nbuilding:grandma:lots:1.0\n").unwrap();
+
+        let mut state = GameState::new();
+        state.points = BigNum::from(42u64);
+        let err = state.load_game(&dir, "game").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 4: malformed entry 'building:grandma:lots:1.0'"));
+        assert_eq!(state.points, BigNum::from(42u64));
+        assert_eq!(state.buildings[0].count, 0);
+        assert!(path.with_extension("save.bad").exists());
+    }
+
+    #[test]
+    fn corrupt_saves_fall_back_to_the_newest_good_backup() {
+        let dir = TempDir::new("backups");
+        let mut state = GameState::new();
+        for points in [1u64, 2, 3] {
+            state.points = BigNum::from(points);
+            state.save_game(&dir, "game").unwrap();
+        }
+        let path = slots::slot_path(&dir, "game");
+        assert!(save::backup_path(&path, 2).exists());
+        assert!(!save::backup_path(&path, 3).exists());
+
+        fs::write(&path, "{\"points\": trunc").unwrap();
+        let (loaded, generation) = GameState::load_latest_valid(&dir, "game").unwrap().unwrap();
+        assert_eq!((loaded.points, generation), (BigNum::from(2u64), 1));
+
+        fs::write(save::backup_path(&path, 1), "").unwrap();
+        let (loaded, generation) = GameState::load_latest_valid(&dir, "game").unwrap().unwrap();
+        assert_eq!((loaded.points, generation), (BigNum::from(1u64), 2));
+
+        // Loading keeps the bad file aside and says which backup was used
+        let mut state = GameState::new();
+        state.load_game(&dir, "game").unwrap();
+        assert_eq!(state.points, BigNum::from(1u64));
+        assert!(path.with_extension("save.bad").exists());
+        assert_eq!(state.notifications.back().unwrap().text, "The save could not be read, restored backup 2");
+    }
+
+    #[test]
+    fn backups_load_even_when_the_bad_save_cannot_be_kept() {
+        let dir = TempDir::new("bad-unkept");
+        let mut state = GameState::new();
+        state.points = BigNum::from(7u64);
+        state.save_game(&dir, "game").unwrap();
+        state.save_game(&dir, "game").unwrap();
+        let path = slots::slot_path(&dir, "game");
+        fs::write(&path, "nonsense").unwrap();
+        // A directory in the way makes the copy fail
+        fs::create_dir(path.with_extension("save.bad")).unwrap();
+
+        let mut state = GameState::new();
+        state.load_game(&dir, "game").unwrap();
+        assert_eq!(state.points, BigNum::from(7u64));
+
+        fs::write(save::backup_path(&path, 1), "nonsense").unwrap();
+        let err = GameState::new().load_game(&dir, "game").unwrap_err();
+        assert_eq!(err.to_string(), "expected ident at line 1 column 2");
+    }
+
+    #[test]
+    fn slots_with_nothing_readable_report_the_first_error() {
+        let dir = TempDir::new("all-corrupt");
+        assert!(GameState::load_latest_valid(&dir, "game").unwrap().is_none());
+
+        let path = slots::slot_path(&dir, "game");
+        fs::write(&path, "nonsense").unwrap();
+        fs::write(save::backup_path(&path, 1), "points:oops").unwrap();
+        let err = GameState::load_latest_valid(&dir, "game").unwrap_err();
+        assert_eq!(err, "expected ident at line 1 column 2");
+    }
+
+    #[test]
+    fn every_save_is_rotated_into_backups() {
+        let dir = TempDir::new("rotate-any");
+        let path = slots::slot_path(&dir, "game");
+        fs::write(&path, "garbage").unwrap();
+        GameState::new().save_game(&dir, "game").unwrap();
+        assert_eq!(fs::read_to_string(save::backup_path(&path, 1)).unwrap(), "garbage");
+        // Loading looks past it to the save just written
+        assert_eq!(GameState::load_latest_valid(&dir, "game").unwrap().unwrap().1, 0);
+
+        for _ in 0..5 {
+            GameState::new().save_game(&dir, "game").unwrap();
+        }
+        assert!(save::backup_path(&path, save::BACKUPS).exists());
+        assert!(!save::backup_path(&path, save::BACKUPS + 1).exists());
+    }
+
+    #[test]
+    fn reset_starts_the_run_over() {
+        let mut state = GameState::new();
+        state.points = BigNum::from(500u64);
+        state.lifetime_points = BigNum::from(5_000u64);
+        state.click_power = 5;
+        state.buildings[0].count = 9;
+        state.upgrades[0].purchased = true;
+        state.slot = "keep".to_string();
+
+        state.reset();
+        assert_eq!(state.points, BigNum::from(0u64));
+        assert_eq!(state.lifetime_points, BigNum::from(0u64));
+        assert_eq!(state.click_power, 1);
+        assert!(state.buildings.iter().all(|building| building.count == 0));
+        assert!(state.upgrades.iter().all(|upgrade| !upgrade.purchased));
+        assert_eq!(state.slot, "keep");
+    }
+
+    #[test]
+    fn reset_waits_for_an_explicit_yes() {
+        let dir = TempDir::new("reset");
+        let mut state = GameState::new();
+        state.save_dir = dir.to_path_buf();
+        state.points = BigNum::from(500u64);
+        state.save_game(&dir, "game").unwrap();
+        state.save_game(&dir, "game").unwrap();
+        let path = slots::slot_path(&dir, "game");
+
+        state.apply_action(&Action::StartReset);
+        // Nothing but an answer gets through while the question is open
+        state.apply_action(&Action::Click);
+        state.apply_action(&Action::Answer(false));
+        assert_eq!(state.points, BigNum::from(500u64));
+        assert!(path.exists());
+
+        state.apply_action(&Action::StartReset);
+        state.apply_action(&Action::Answer(true));
+        assert!(state.points.is_zero());
+        assert!(!path.exists());
+        assert!(!save::backup_path(&path, 1).exists());
+    }
+
+    #[test]
+    fn playtime_is_saved_but_time_away_is_not_played() {
+        let mut state = GameState::new();
+        state.add_playtime(4_321.25);
+        state.add_playtime(0.5);
+        assert_eq!((state.playtime_secs, state.playtime_fraction), (4_321, 0.75));
+        state.add_playtime(0.5);
+        assert_eq!((state.playtime_secs, state.playtime_fraction), (4_322, 0.25));
+        state.buildings[0].count = 10;
+        let mut loaded = GameState::from_json_or_legacy(&state.save_contents().unwrap()).unwrap();
+        assert_eq!((loaded.playtime_secs, loaded.playtime_fraction), (4_322, 0.25));
+
+        loaded.last_saved_at = unix_now() - 3_600;
+        loaded.apply_offline_progress(Duration::from_secs(8 * 3_600));
+        assert!(!loaded.points.is_zero());
+        assert_eq!(loaded.playtime_secs, 4_322);
+    }
+
+    #[test]
+    fn stats_count_clicks_and_purchases() {
+        let mut state = GameState::new();
+        state.points = BigNum::from(1_000u64);
+        state.apply_action(&Action::Click);
+        state.apply_action(&Action::Click);
+        state.apply_action(&Action::BuyBuilding("cursor".to_string()));
+        state.apply_action(&Action::BuyBuilding("cursor".to_string()));
+        let upgrade_cost = state.upgrades[0].cost;
+        state.apply_action(&Action::BuyUpgrade(0));
+
+        assert_eq!(state.stats.total_clicks, 2);
+        assert_eq!(state.stats.buildings_bought, 2);
+        assert_eq!(state.stats.upgrades_bought, 1);
+        // Two clicks of one, then 15 and 17 for the cultists
+        assert_eq!(state.stats.souls_spent, 15 + 17 + upgrade_cost);
+        assert_eq!(state.points, BigNum::from(1_002 - 32 - upgrade_cost));
+    }
+
+    #[test]
+    fn stats_saturate_instead_of_overflowing() {
+        let mut state = GameState::new();
+        state.stats.total_clicks = u64::MAX;
+        state.stats.souls_spent = u64::MAX - 1;
+        state.points = BigNum::from(100u64);
+        state.apply_action(&Action::Click);
+        state.apply_action(&Action::BuyBuilding("cursor".to_string()));
+        assert_eq!(state.stats.total_clicks, u64::MAX);
+        assert_eq!(state.stats.souls_spent, u64::MAX);
+    }
+
+    #[test]
+    fn shards_grow_with_the_cube_root_of_lifetime_converts() {
+        assert_eq!(GameState::shards_from_lifetime(BigNum::from(0u64)), 0);
+        assert_eq!(GameState::shards_from_lifetime(BigNum::from(999_999_999_999u64)), 0);
+        assert_eq!(GameState::shards_from_lifetime(BigNum::from(1_000_000_000_000u64)), 1);
+        assert_eq!(GameState::shards_from_lifetime(BigNum::from(7_999_999_999_999u64)), 1);
+        assert_eq!(GameState::shards_from_lifetime(BigNum::from(8_000_000_000_000u64)), 2);
+        assert_eq!(GameState::shards_from_lifetime(BigNum::from(1_000_000_000_000_000_000u64)), 100);
+    }
+
+    #[test]
+    fn ascending_trades_the_run_for_shards() {
+        let mut state = GameState::new();
+        state.lifetime_points = BigNum::from(27_000_000_000_000u64);
+        state.points = BigNum::from(5_000u64);
+        state.buildings[0].count = 10;
+        state.upgrades[0].purchased = true;
+        state.playtime_secs = 60;
+        state.auto_click_remainder = 0.75;
+        state.buffs.push(Buff { kind: BuffKind::Production, multiplier: FRENZY_MULTIPLIER, remaining: FRENZY_SECS });
+
+        state.apply_action(&Action::StartAscension);
+        assert_eq!(state.current_menu, Menu::Ascension);
+        state.apply_action(&Action::ConfirmSelection);
+        assert!(matches!(state.confirmation, Some(Confirmation::Ascend)));
+        state.apply_action(&Action::Answer(true));
+
+        assert_eq!(state.shards, 3);
+        assert_eq!(state.points, BigNum::from(0u64));
+        assert_eq!(state.lifetime_points, BigNum::from(0u64));
+        assert_eq!(state.buildings[0].count, 0);
+        assert!(!state.upgrades[0].purchased);
+        assert_eq!(state.playtime_secs, 60);
+        assert_eq!(state.auto_click_remainder, 0.0);
+        assert!(state.buffs.is_empty());
+        assert_eq!(state.current_menu, Menu::Main);
+
+        // Each shard adds 2% to production, with no frenzy left over
+        state.buildings[1].count = 1;
+        assert!((state.calculate_production_per_second() - 1.06).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ascending_needs_a_shard_to_gain() {
+        let mut state = GameState::new();
+        state.lifetime_points = BigNum::from(1_000u64);
+        state.apply_action(&Action::StartAscension);
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(state.shards, 0);
+        assert!(!state.ascend());
+        assert_eq!(state.lifetime_points, BigNum::from(1_000u64));
+    }
+
+    #[test]
+    fn relics_survive_ascension() {
+        let mut state = GameState::new();
+        state.shards = 3;
+        state.apply_action(&Action::BuyPrestigeUpgrade(0));
+        state.apply_action(&Action::BuyPrestigeUpgrade(1));
+        assert_eq!(state.shards, 0);
+        assert_eq!(state.prestige_multiplier(), 1.05);
+        assert_eq!(state.prestige_click_bonus(), 1);
+
+        state.lifetime_points = BigNum::from(1_000_000_000_000u64);
+        assert!(state.ascend());
+        assert!(state.prestige_upgrades[0].purchased);
+        assert_eq!(state.prestige_multiplier(), 1.05);
+
+        // Influence power is 1, plus the Mark of the Deep
+        state.apply_action(&Action::Click);
+        assert_eq!(state.points, BigNum::from(2u64));
+
+        let loaded = GameState::from_json_or_legacy(&state.save_contents().unwrap()).unwrap();
+        assert!(loaded.prestige_upgrades[0].purchased && loaded.prestige_upgrades[1].purchased);
+    }
+
+    #[test]
+    fn relics_cost_shards() {
+        let mut state = GameState::new();
+        state.shards = 4;
+        state.apply_action(&Action::BuyPrestigeUpgrade(2));
+        assert!(!state.prestige_upgrades[2].purchased);
+        assert_eq!(state.shards, 4);
+        assert_eq!(state.notifications.back().unwrap().text, "Not enough eldritch shards for Chorus of the Drowned: 1 more needed");
+
+        state.apply_action(&Action::BuyPrestigeUpgrade(0));
+        state.apply_action(&Action::BuyPrestigeUpgrade(0));
+        assert_eq!(state.shards, 3);
+        assert_eq!(state.notifications.back().unwrap().text, "Echo of R'lyeh is already yours");
+    }
+
+    #[test]
+    fn rewarded_achievements_raise_production() {
+        let mut state = GameState::new();
+        state.buildings[0].count = 100;
+        state.buildings[1].count = 99;
+        let before = state.calculate_production_per_second();
+
+        // A Hundred Whisperers is already met, the purchase unlocks Council of Elders too
+        state.points = BigNum::from(1_000_000_000_000u64);
+        state.apply_action(&Action::BuyBuilding("grandma".to_string()));
+        assert_eq!(state.achievement_multiplier(), 1.02 * 1.02);
+        let expected = (10.0 + 100.0) * 1.02 * 1.02;
+        assert!((state.calculate_production_per_second() - expected).abs() < 1e-9);
+        assert!(state.calculate_production_per_second() > before + 1.0);
+    }
+
+    #[test]
+    fn the_achievement_bonus_grows_by_a_percent_per_achievement() {
+        assert_eq!(achievement_bonus_multiplier(0), 1.0);
+        assert_eq!(achievement_bonus_multiplier(1), 1.01);
+        assert_eq!(achievement_bonus_multiplier(9), 1.09);
+    }
+
+    #[test]
+    fn the_achievement_bonus_is_opt_in_and_layers_on_rewards() {
+        let mut state = GameState::new();
+        state.buildings[0].count = 100;
+        state.apply_action(&Action::Click);
+        let unlocked = state.achievements.iter().filter(|achievement| achievement.unlocked).count();
+        assert_eq!(unlocked, 2);
+        assert!(!state.achievement_bonus);
+        assert_eq!(state.achievement_multiplier(), 1.02);
+        let before = state.calculate_production_per_second();
+
+        state.current_menu = Menu::Settings;
+        state.selected_index = SETTING_ACHIEVEMENT_BONUS;
+        state.apply_action(&Action::ConfirmSelection);
+        assert!(state.achievement_bonus);
+        assert_eq!(state.achievement_multiplier(), 1.02 * 1.02);
+        assert!((state.calculate_production_per_second() - before * 1.02).abs() < 1e-9);
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(GameState::from_json_or_legacy(&json).unwrap().achievement_bonus);
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(state.achievement_multiplier(), 1.02);
+    }
+
+    #[test]
+    fn golden_followers_follow_their_formulas() {
+        assert_eq!(golden_spawn_delay(0.0), GOLDEN_MIN_DELAY);
+        assert_eq!(golden_spawn_delay(0.5), 120.0);
+        assert!(golden_spawn_delay(0.999_999) < GOLDEN_MAX_DELAY);
+        assert_eq!(golden_reward(10.0, 1), BigNum::from(300u64));
+        // Before any minions, frantic clicking is what a catch is worth
+        assert_eq!(golden_reward(0.0, 4), BigNum::from(120u64));
+    }
+
+    #[test]
+    fn golden_followers_appear_and_escape() {
+        let mut state = GameState::new();
+        state.rng = Rng::new(0); // First roll 0.883, so the first one comes after 166 seconds
+        state.advance_golden(0.0);
+        state.advance_golden(165.0);
+        assert!(state.golden.is_none());
+
+        state.advance_golden(1.0);
+        assert!(state.golden.is_some());
+        assert_eq!(state.notifications.back().unwrap().text, "A golden follower appears! Press 'f' to catch it");
+
+        state.advance_golden(GOLDEN_LIFETIME - 0.5);
+        assert!(state.golden.is_some());
+        state.advance_golden(0.5);
+        assert!(state.golden.is_none());
+    }
+
+    #[test]
+    fn catching_a_golden_follower_grants_a_reward() {
+        let mut state = GameState::new();
+        state.apply_action(&Action::CatchGolden);
+        assert_eq!(state.points, BigNum::from(0u64));
+
+        state.golden = Some(GoldenEvent { x: 0.5, y: 0.5, remaining: 5.0 });
+        state.apply_action(&Action::CatchGolden);
+        assert!(state.golden.is_none());
+        assert_eq!(state.points, BigNum::from(30u64));
+        assert_eq!(state.notifications.back().unwrap().text, "The golden follower brings 30 more");
+    }
+
+    #[test]
+    fn seeded_games_see_the_same_events() {
+        let sightings = |seed: u64| {
+            let mut state = GameState::new();
+            state.rng = Rng::new(seed);
+            let mut seen = Vec::new();
+            for tick in 0..3_000 {
+                state.advance_golden(1.0);
+                if let Some(golden) = state.golden.filter(|golden| golden.remaining == GOLDEN_LIFETIME) {
+                    seen.push((tick, golden.x, golden.y));
+                }
+            }
+            seen
+        };
+
+        let first = sightings(99);
+        assert!(first.len() >= 10);
+        assert_eq!(first, sightings(99));
+        assert_ne!(first, sightings(100));
+    }
+
+    #[test]
+    fn frenzies_multiply_production_until_they_wear_off() {
+        let mut state = GameState::new();
+        state.rng = Rng::new(5); // 0.387, a frenzy once minions produce
+        state.buildings[1].count = 1;
+        state.golden = Some(GoldenEvent { x: 0.5, y: 0.5, remaining: 5.0 });
+        state.apply_action(&Action::CatchGolden);
+        assert_eq!(state.buff_multiplier(BuffKind::Production), FRENZY_MULTIPLIER);
+        assert_eq!(state.calculate_production_per_second(), FRENZY_MULTIPLIER);
+
+        state.advance_buffs(FRENZY_SECS);
+        assert_eq!(state.calculate_production_per_second(), 1.0);
+    }
+
+    #[test]
+    fn clicking_frenzies_multiply_clicks_until_they_wear_off() {
+        let mut state = GameState::new();
+        state.upgrades[1].purchased = true; // Clicks x2
+        state.rng = Rng::new(10); // A roll of 0.033, a clicking frenzy
+        state.golden = Some(GoldenEvent { x: 0.5, y: 0.5, remaining: 5.0 });
+        state.apply_action(&Action::CatchGolden);
+        assert_eq!(state.buff_remaining(BuffKind::Click), Some(CLICK_FRENZY_SECS));
+
+        state.apply_action(&Action::Click);
+        assert_eq!(state.points, BigNum::from(2 * 777u64));
+
+        state.advance_buffs(CLICK_FRENZY_SECS);
+        assert!(state.buffs.is_empty());
+        let before = state.points;
+        state.apply_action(&Action::Click);
+        // 1,554 converts raised influence power to 2
+        assert_eq!(state.points - before, BigNum::from(4u64));
+    }
+
+    #[test]
+    fn cost_for_n_matches_buying_one_at_a_time() {
+        let mut building = Building::new("test", "Test", "", 15, 0.1, 1.15);
+        building.count = 7;
+        let bulk = building.cost_for_n(10);
+
+        let mut single = building.clone();
+        let paid = (0..10).fold(BigNum::ZERO, |total, _| total + single.buy());
+        assert_eq!(bulk, paid);
+        assert_eq!(single.count, 17);
+        assert_eq!(building.cost_for_n(0), BigNum::ZERO);
+        assert_eq!(building.cost_for_n(1), building.current_cost());
+    }
+
+    #[test]
+    fn bulk_buys_stop_when_followers_run_out() {
+        let mut state = GameState::new();
+        state.apply_action(&Action::CycleBuyAmount);
+        assert_eq!(state.buy_amount, 10);
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+
+        // Enough for ten cultists but not for eleven
+        state.points = state.buildings[0].cost_for_n(10);
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(state.buildings[0].count, 10);
+        assert_eq!(state.points, BigNum::from(0u64));
+
+        state.points = state.buildings[0].cost_for_n(3);
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(state.buildings[0].count, 13);
+
+        state.apply_action(&Action::CycleBuyAmount);
+        state.apply_action(&Action::CycleBuyAmount);
+        assert_eq!(state.buy_amount, 1);
+    }
+
+    #[test]
+    fn max_affordable_matches_a_brute_force_count() {
+        for (base, count) in [(15, 0), (100, 12), (1100, 40), (130000, 3)] {
+            let building = Building { count, ..Building::new("test", "Test", "", base, 1.0, 1.15) };
+            for points in [0u64, 14, 15, 999, 123_456, 9_876_543_210] {
+                let points = BigNum::from(points);
+                let mut brute = building.clone();
+                let mut spent = BigNum::ZERO;
+                while spent + brute.current_cost() <= points && brute.count - count < BUY_MAX_LIMIT {
+                    spent += brute.buy();
+                }
+                assert_eq!(building.max_affordable(points), brute.count - count, "base {} count {} points {}", base, count, points);
+            }
+        }
+    }
+
+    #[test]
+    fn buy_max_spends_the_exact_total() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        let expected = state.buildings[0].max_affordable(BigNum::from(1000u64));
+        let cost = state.buildings[0].cost_for_n(expected);
+        state.points = BigNum::from(1000u64);
+        state.apply_action(&Action::BuyMax);
+        assert_eq!(state.buildings[0].count, expected);
+        assert_eq!(state.points, BigNum::from(1000u64) - cost);
+
+        state.apply_action(&Action::BuyMax);
+        assert_eq!(state.buildings[0].count, expected);
+    }
+
+    #[test]
+    fn selling_refunds_half_the_last_price() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        state.points = BigNum::from(1000u64);
+        state.apply_action(&Action::ConfirmSelection);
+        state.apply_action(&Action::ConfirmSelection);
+        let pps = state.calculate_production_per_second();
+        assert_eq!(BigNum::from(1000u64) - state.points, BigNum::from(15 + 17u64));
+
+        state.apply_action(&Action::SellSelection);
+        assert_eq!(state.buildings[0].count, 1);
+        // The second cultist cost 17, so 8 comes back
+        assert_eq!(state.points, BigNum::from(1000 - 32 + 8u64));
+        assert!(state.calculate_production_per_second() < pps);
+        assert_eq!(state.notifications.back().unwrap().text, "Released a Cultist for 8 followers");
+    }
+
+    #[test]
+    fn nothing_is_sold_when_none_are_owned() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        state.points = BigNum::from(50u64);
+        state.apply_action(&Action::SellSelection);
+        assert_eq!(state.buildings[0].count, 0);
+        assert_eq!(state.points, BigNum::from(50u64));
+
+        let mut building = state.buildings[0].clone();
+        assert!(building.sell().is_zero());
+    }
+
+    #[test]
+    fn buying_every_upgrade_stops_at_the_first_unaffordable() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Upgrades));
+        state.upgrades[1].purchased = true;
+        state.points = BigNum::from(2000u64);
+        state.apply_action(&Action::SpendAll);
+
+        // 100 and 1000 bought, 500 already owned, 11000 out of reach
+        assert_eq!(state.points, BigNum::from(900u64));
+        let purchased: Vec<bool> = state.upgrades.iter().map(|upgrade| upgrade.purchased).collect();
+        assert_eq!(purchased, [true, true, true, false, false, false, false]);
+        assert_eq!(state.notifications.back().unwrap().text, "Spent 1100 followers on 2 artifacts");
+
+        state.apply_action(&Action::SpendAll);
+        assert_eq!(state.points, BigNum::from(900u64));
+        assert_eq!(state.notifications.back().unwrap().text, "No artifact you can afford");
+    }
+
+    #[test]
+    fn buying_every_upgrade_leaves_the_reserve() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Upgrades));
+        state.points = BigNum::from(2000u64);
+        state.reserve = BigNum::from(1500u64);
+        state.apply_action(&Action::SpendAll);
+        assert_eq!(state.points, BigNum::from(1900u64));
+        assert!(state.upgrades[0].purchased);
+        assert!(!state.upgrades[1].purchased);
+    }
+
+    #[test]
+    fn the_number_format_is_saved() {
+        let mut state = GameState::new();
+        state.current_menu = Menu::Settings;
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(state.number_format, NumberFormat::Scientific);
+
+        let loaded = GameState::from_json_or_legacy(&state.save_contents().unwrap()).unwrap();
+        assert_eq!(loaded.number_format, NumberFormat::Scientific);
+    }
+
+    #[test]
+    fn the_autosave_setting_is_saved() {
+        let mut state = GameState::new();
+        state.current_menu = Menu::Settings;
+        state.selected_index = SETTING_AUTOSAVE;
+        state.apply_action(&Action::ConfirmSelection);
+        assert!(!state.autosave);
+
+        let loaded = GameState::from_json_or_legacy(&state.save_contents().unwrap()).unwrap();
+        assert!(!loaded.autosave);
+        // The interval comes from the command line each session
+        assert_eq!(loaded.autosave_secs, DEFAULT_AUTOSAVE_SECS);
+    }
+
+    #[test]
+    fn the_theme_is_saved() {
+        let mut state = GameState::new();
+        state.current_menu = Menu::Settings;
+        state.selected_index = SETTING_THEME;
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(state.theme, ThemePreset::Eldritch);
+
+        let loaded = GameState::from_json_or_legacy(&state.save_contents().unwrap()).unwrap();
+        assert_eq!(loaded.theme, ThemePreset::Eldritch);
+    }
+
+    #[test]
+    fn eta_covers_the_deficit_at_the_rate() {
+        assert_eq!(eta_seconds(BigNum::from(100u64), 4.0), Some(25.0));
+        assert_eq!(eta_seconds(BigNum::from(1u64), 0.5), Some(2.0));
+        // Already affordable, even with nothing coming in
+        assert_eq!(eta_seconds(BigNum::ZERO, 0.0), Some(0.0));
+        assert_eq!(eta_seconds(BigNum::from(100u64), 0.0), None);
+        assert_eq!(eta_seconds(BigNum::from(100u64), -3.0), None);
+        assert_eq!(eta_seconds(BigNum::from(100u64), f64::NAN), None);
+    }
+
+    #[test]
+    fn paging_jumps_a_screen_and_scrolls_with_it() {
+        let mut state = GameState::new();
+        state.terminal_size = (80, 24);
+        state.apply_action(&Action::OpenMenu(Menu::Upgrades));
+        // Three lines each leave room for six of the seven artifacts
+        assert_eq!(state.list_rows(), 6);
+
+        state.apply_action(&Action::PageNext);
+        assert_eq!((state.selected_index, state.scroll_offset), (6, 1));
+        state.apply_action(&Action::PageNext);
+        assert_eq!((state.selected_index, state.scroll_offset), (6, 1));
+        state.apply_action(&Action::SelectPrevious);
+        assert_eq!((state.selected_index, state.scroll_offset), (5, 1));
+        state.apply_action(&Action::PagePrevious);
+        assert_eq!((state.selected_index, state.scroll_offset), (0, 0));
+    }
+
+    #[test]
+    fn declined_ascension_keeps_the_run() {
+        let mut state = plain_state();
+        state.lifetime_points = BigNum::from(27_000_000_000_000u64);
+        state.buildings[0].count = 10;
+        state.apply_action(&Action::StartAscension);
+        state.apply_action(&Action::ConfirmSelection);
+
+        // Anything but an answer leaves the question open
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        assert_eq!(state.current_menu, Menu::Ascension);
+        assert!(matches!(state.confirmation, Some(Confirmation::Ascend)));
+
+        state.apply_action(&Action::Answer(false));
+        assert!(state.confirmation.is_none());
+        assert_eq!(state.shards, 0);
+        assert_eq!(state.buildings[0].count, 10);
+        assert_eq!(state.lifetime_points, BigNum::from(27_000_000_000_000u64));
+    }
+
+    #[test]
+    fn rebirth_is_confirmed_before_shards_are_traded() {
+        let mut state = plain_state();
+        state.current_menu = Menu::Rebirth;
+        state.shards = 9;
+        state.apply_action(&Action::StartRebirth);
+        assert!(state.confirmation.is_none());
+        assert_eq!(state.notifications.back().unwrap().text, "Rebirth needs at least 10 eldritch shards");
+
+        state.shards = 25;
+        state.apply_action(&Action::StartRebirth);
+        state.apply_action(&Action::Answer(false));
+        assert_eq!((state.shards, state.rebirth_tokens), (25, 0));
+
+        state.apply_action(&Action::StartRebirth);
+        assert_eq!(state.confirmation.as_ref().unwrap().question(), "Be reborn? Your shards, relics and this run are given up.");
+        state.apply_action(&Action::Answer(true));
+        assert_eq!((state.shards, state.rebirth_tokens), (0, 2));
+    }
+
+    #[test]
+    fn refused_purchases_say_why() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        state.points = BigNum::from(5u64);
+        state.apply_action(&Action::QuickBuy(0));
+        assert_eq!(state.buildings[0].count, 0);
+        assert_eq!(state.notifications.back().unwrap().text, "Not enough followers to summon Cultist: 10 more needed");
+
+        state.points = BigNum::from(20u64);
+        state.reserve = BigNum::from(10u64);
+        state.apply_action(&Action::QuickBuy(0));
+        assert_eq!(state.notifications.back().unwrap().text, "Summoning Cultist would dip into your reserve");
+
+        state.upgrades[0].purchased = true;
+        state.refuse_upgrade(0);
+        assert_eq!(state.notifications.back().unwrap().text, "Necronomicon Pages is already yours");
+        state.refuse_upgrade(1);
+        assert_eq!(state.notifications.back().unwrap().text, "Not enough followers for Eldritch Incantation: 480 more needed");
+    }
+
+    #[test]
+    fn notifications_are_shown_one_at_a_time() {
+        let mut state = GameState::new();
+        state.notify("first".to_string());
+        state.notify("second".to_string());
+        state.advance_notifications();
+        assert_eq!(state.notifications.len(), 2);
+        assert!(state.notifications[0].shown_since.is_some());
+        assert!(state.notifications[1].shown_since.is_none());
+
+        // Once the first has had its time the second takes its place
+        let long_ago = Instant::now() - std::time::Duration::from_secs_f64(NOTIFICATION_SECS + 1.0);
+        state.notifications[0].shown_since = Some(long_ago);
+        state.advance_notifications();
+        assert_eq!(state.notifications.len(), 1);
+        assert_eq!(state.notifications[0].text, "second");
+        assert!(state.notifications[0].shown_since.is_some());
+    }
+
+    #[test]
+    fn multipliers_combine_specific_and_global_upgrades() {
+        let mut state = plain_state();
+        assert_eq!(state.building_multiplier("cursor"), 1.0);
+
+        state.upgrades[0].purchased = true;
+        assert_eq!(state.building_multiplier("cursor"), 2.0);
+        assert_eq!(state.building_multiplier("grandma"), 1.0);
+
+        // The Stars Are Right doubles every minion
+        state.upgrades[6].purchased = true;
+        assert_eq!(state.building_multiplier("cursor"), 4.0);
+        assert_eq!(state.building_multiplier("grandma"), 2.0);
+
+        state.shards = 5;
+        assert!((state.building_multiplier("grandma") - 2.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn production_uses_the_same_multiplier() {
+        let mut state = plain_state();
+        state.buildings[0].count = 10;
+        state.buildings[1].count = 3;
+        state.upgrades[0].purchased = true;
+        state.upgrades[6].purchased = true;
+        let expected = 10.0 * 0.1 * state.building_multiplier("cursor") + 3.0 * state.building_multiplier("grandma");
+        assert!((state.calculate_production_per_second() - expected).abs() < 1e-9);
+        assert!((state.calculate_production_per_second() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sorts_order_the_minions() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        for (index, count) in [(0, 5), (1, 20), (2, 1), (3, 2)] {
+            state.buildings[index].count = count;
+        }
+        let mut orders = Vec::new();
+        for _ in 0..4 {
+            orders.push((state.building_sort.name(), state.sorted_building_keys().join(" ")));
+            state.apply_action(&Action::CycleBuildingSort);
+        }
+        assert_eq!(state.building_sort, BuildingSort::Cost);
+        assert_eq!(orders, [
+            ("cost", "cursor idol farm grandma mine temple portal".to_string()),
+            ("production", "grandma farm cursor idol mine temple portal".to_string()),
+            ("count", "grandma cursor farm idol mine temple portal".to_string()),
+            ("efficiency", "farm mine cursor temple portal grandma idol".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn the_selected_row_is_the_one_summoned_in_every_sort() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        for (index, count) in [(0, 5), (1, 20), (2, 1), (3, 2)] {
+            state.buildings[index].count = count;
+        }
+        state.points = BigNum::pow(10.0, 9.0);
+        for _ in 0..4 {
+            let rows = view::ViewModel::capture(&state).buildings;
+            for (row, shown) in rows.iter().enumerate() {
+                let mut summoned = state.clone();
+                summoned.select(row);
+                summoned.apply_action(&Action::ConfirmSelection);
+                let bought = summoned.buildings.iter().find(|building| building.name == shown.name).unwrap();
+                assert_eq!(bought.count, shown.count + 1, "row {} sorted by {}", row, state.building_sort.name());
+            }
+            state.apply_action(&Action::CycleBuildingSort);
+        }
+    }
+
+    #[test]
+    fn efficiency_is_price_per_follower_per_second() {
+        assert_eq!(efficiency(BigNum::from(15), 0.1), Some(150.0));
+        assert_eq!(efficiency(BigNum::from(1100), 8.0), Some(137.5));
+        assert_eq!(efficiency(BigNum::from(100), 0.0), None);
+        assert_eq!(efficiency(BigNum::from(100), -1.0), None);
+        assert_eq!(efficiency(BigNum::pow(10.0, 400.0), 1.0), None);
+    }
+
+    #[test]
+    fn efficiency_worsens_as_minions_get_pricier() {
+        let mut state = plain_state();
+        assert_eq!(state.building_efficiency("grandma"), Some(100.0));
+        state.buildings[1].count = 10;
+        // 100 * 1.15^10 rounded down, for one follower a second
+        assert_eq!(state.building_efficiency("grandma"), Some(404.0));
+
+        // Artifacts make each one produce more, and so more efficient
+        state.upgrades[2].purchased = true;
+        assert_eq!(state.building_efficiency("grandma"), Some(202.0));
+
+        // Auto-clickers are measured by what their clicks bring
+        assert_eq!(state.building_efficiency("idol"), Some(2500.0));
+        state.upgrades[1].purchased = true;
+        assert_eq!(state.building_efficiency("idol"), Some(1250.0));
+        assert_eq!(state.building_efficiency("nothing"), None);
+    }
+
+    #[test]
+    fn best_buy_prefers_what_can_be_summoned_now() {
+        let mut state = plain_state();
+        // Nothing affordable, so the best value to save up for
+        assert_eq!(state.best_buy().as_deref(), Some("grandma"));
+
+        state.points = BigNum::from(20u64);
+        assert_eq!(state.best_buy().as_deref(), Some("cursor"));
+        state.points = BigNum::from(200u64);
+        assert_eq!(state.best_buy().as_deref(), Some("grandma"));
+
+        // Held back followers don't count as affordable
+        state.reserve = BigNum::from(150u64);
+        assert_eq!(state.best_buy().as_deref(), Some("cursor"));
+
+        state.reserve = BigNum::ZERO;
+        state.buildings[1].count = 10;
+        state.points = BigNum::from(2_000u64);
+        assert_eq!(state.best_buy().as_deref(), Some("farm"));
+    }
+
+    #[test]
+    fn first_and_last_follow_the_menu_length() {
+        let mut state = GameState::new();
+        state.apply_action(&Action::OpenMenu(Menu::Upgrades));
+        state.apply_action(&Action::SelectLast);
+        assert_eq!(state.selected_index, state.upgrades.len() - 1);
+        state.apply_action(&Action::SelectNext);
+        assert_eq!(state.selected_index, state.upgrades.len() - 1);
+        state.apply_action(&Action::SelectFirst);
+        state.apply_action(&Action::SelectPrevious);
+        assert_eq!(state.selected_index, 0);
+
+        // An empty list stays on the first row
+        state.apply_action(&Action::OpenMenu(Menu::History));
+        state.apply_action(&Action::SelectLast);
+        assert_eq!(state.selected_index, 0);
+    }
+
+    #[test]
+    fn clicks_while_paused_follow_the_setting() {
+        let mut state = plain_state();
+        state.apply_action(&Action::TogglePause);
+        state.apply_action(&Action::Click);
+        assert_eq!(state.points, BigNum::from(1u64));
+
+        state.current_menu = Menu::Settings;
+        state.selected_index = SETTING_PAUSED_CLICKS;
+        state.apply_action(&Action::ConfirmSelection);
+        assert!(!state.paused_clicks);
+        state.apply_action(&Action::Click);
+        state.buildings[2].count = 10;
+        state.credit_auto_clicks(1.25);
+        assert_eq!(state.points, BigNum::from(1u64));
+        assert_eq!(state.auto_click_remainder, 0.0);
+        assert_eq!(state.stats.total_clicks, 1);
+
+        state.apply_action(&Action::TogglePause);
+        state.apply_action(&Action::Click);
+        assert_eq!(state.points, BigNum::from(2u64));
+    }
+
+    #[test]
+    fn cached_production_follows_every_change() {
+        let mut state = plain_state();
+        let in_sync = |state: &GameState| state.production_per_second() == state.calculate_production_per_second();
+        assert_eq!(state.production_per_second(), 0.0);
+
+        state.points = BigNum::pow(10.0, 12.0);
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        state.apply_action(&Action::QuickBuy(1));
+        assert_eq!(state.production_per_second(), 1.0);
+        state.apply_action(&Action::BuyMax);
+        assert!(in_sync(&state));
+
+        state.apply_action(&Action::OpenMenu(Menu::Upgrades));
+        state.apply_action(&Action::SpendAll);
+        assert!(in_sync(&state));
+
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        state.apply_action(&Action::SellSelection);
+        assert!(in_sync(&state));
+
+        let mut loaded = GameState::from_json_or_legacy(&state.save_contents().unwrap()).unwrap();
+        assert_eq!(loaded.production_per_second(), state.production_per_second());
+        loaded.lifetime_points = BigNum::pow(10.0, 12.0);
+        assert!(loaded.ascend());
+        assert_eq!(loaded.production_per_second(), 0.0);
+    }
+
+    #[test]
+    fn minions_keep_their_order_through_a_save() {
+        let keys = |state: &GameState| state.buildings.iter().map(|building| building.key.as_str()).collect::<Vec<_>>().join(" ");
+        let mut state = plain_state();
+        assert_eq!(keys(&state), "cursor grandma idol farm mine temple portal");
+        state.buildings[4].count = 3;
+        state.buildings[0].count = 40;
+
+        // Saved by key, but loaded back into the same order
+        let contents = state.save_contents().unwrap();
+        let loaded = GameState::from_json_or_legacy(&contents).unwrap();
+        assert_eq!(keys(&loaded), keys(&state));
+        assert_eq!((loaded.buildings[0].count, loaded.buildings[4].count), (40, 3));
+
+        let legacy = GameState::from_json_or_legacy("points:0\nbuilding:mine:3:47\nbuilding:cursor:1:0.1\n").unwrap();
+        assert_eq!(keys(&legacy), keys(&state));
+        assert_eq!((legacy.buildings[0].count, legacy.buildings[4].count), (1, 3));
+    }
+
+    #[test]
+    fn row_keys_summon_the_row_shown() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        state.points = BigNum::pow(10.0, 9.0);
+        // Enough cultists to push them behind the mine in price
+        state.buildings[0].count = 60;
+        assert_eq!(state.sorted_building_keys().join(" "), "grandma idol farm mine cursor temple portal");
+        for row in 0..state.buildings.len() {
+            let key = state.sorted_building_keys().swap_remove(row);
+            let index = state.building_index(&key).unwrap();
+            let before = state.buildings[index].count;
+            state.apply_action(&Action::QuickBuy(row));
+            assert_eq!(state.selected_index, row);
+            assert_eq!(state.buildings[index].count, before + 1, "row {}", row);
+        }
+    }
+
+    #[test]
+    fn prices_never_fall_as_counts_grow() {
+        let mut building = Building::new("test", "Test", "", 15, 0.1, 1.15);
+        let counts = (0..=2_000).chain([10_000, 1_000_000, u64::MAX / 2, u64::MAX - 1, u64::MAX]);
+        for price_factor in [1.0, 0.5] {
+            building.price_factor = price_factor;
+            let mut previous = BigNum::ZERO;
+            for count in counts.clone() {
+                building.count = count;
+                let cost = building.current_cost();
+                assert!(cost >= previous, "count {} costs {} after {}", count, cost, previous);
+                assert!(!cost.is_zero());
+                previous = cost;
+            }
+            // Saturated, less any discount
+            assert_eq!(previous, BigNum::MAX * price_factor);
+        }
+        assert!(building.cost_for_n(3) >= BigNum::MAX);
+    }
+
+    #[test]
+    fn huge_counts_are_not_affordable() {
+        let mut state = plain_state();
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        state.buildings[0].count = u64::MAX - 1;
+        state.points = BigNum::pow(10.0, 300.0);
+        assert_eq!(state.buildings[0].max_affordable(state.points), 0);
+        state.apply_action(&Action::QuickBuy(6));
+        state.apply_action(&Action::BuyMax);
+        assert_eq!(state.buildings[0].count, u64::MAX - 1);
+        assert_eq!(state.points, BigNum::pow(10.0, 300.0));
+    }
+
+    #[test]
+    fn followers_saturate_instead_of_wrapping() {
+        let mut state = plain_state();
+        state.points = BigNum::MAX;
+        state.lifetime_points = BigNum::MAX;
+        state.produced_points = u64::MAX - 1;
+        state.buildings[6].count = 1_000_000;
+        state.apply_action(&Action::Click);
+        state.credit_production(10.0);
+        state.credit_auto_clicks(10.0);
+        assert_eq!(state.points, BigNum::MAX);
+        assert_eq!(state.lifetime_points, BigNum::MAX);
+        assert_eq!(state.produced_points, u64::MAX);
+
+        // Past u64::MAX is still ordinary arithmetic
+        state.points = BigNum::from(u64::MAX);
+        state.lifetime_points = BigNum::from(u64::MAX);
+        state.credit_production(1.0);
+        assert!(state.points > BigNum::from(u64::MAX));
+        assert!(state.lifetime_points > BigNum::from(u64::MAX));
+
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        let before = state.points;
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(state.buildings[0].count, 1);
+        // A price below the last digit kept is lost in the subtraction
+        assert_eq!(state.points, before);
+    }
+
+    #[test]
+    fn small_rates_add_up_over_many_ticks() {
+        let mut state = plain_state();
+        // 0.3 + 0.25 = 0.55 a second, no building producing a whole follower
+        state.buildings[0].count = 3;
+        state.buildings[1].count = 1;
+        state.buildings[1].base_production = 0.25;
+        for _ in 0..36_000 {
+            state.credit_production(0.1);
+        }
+        let expected = 0.55 * 3_600.0;
+        assert!((state.points.to_f64() - expected).abs() <= 1.0, "{} for {}", state.points, expected);
+        assert_eq!(state.points, state.lifetime_points);
+    }
+
+    #[test]
+    fn clocks_running_backwards_credit_nothing() {
+        let mut state = plain_state();
+        state.buildings[1].count = 1;
+        state.buildings[2].count = 10;
+        state.credit_production(0.5);
+        state.credit_auto_clicks(0.25);
+        state.credit_production(-60.0);
+        state.credit_auto_clicks(-60.0);
+        assert!(state.points.is_zero());
+        assert_eq!(state.production_remainder, 0.5);
+        assert_eq!(state.auto_click_remainder, 0.5);
+
+        state.credit_production(0.5);
+        state.credit_auto_clicks(0.25);
+        assert_eq!(state.points, BigNum::from(2u64));
+    }
+
+    #[test]
+    fn strategies_pick_their_purchase() {
+        let mut state = plain_state();
+        state.points = BigNum::from(150);
+        assert_eq!(state.strategy_purchase(Strategy::BuyCheapest), Some(Action::BuyBuilding("cursor".to_string())));
+        assert_eq!(state.strategy_purchase(Strategy::BuyBestEfficiency), Some(Action::BuyBuilding("grandma".to_string())));
+
+        // Cheapest first until nothing is left: cultists at 15, 17, 19 and 22,
+        // the 100 of the Necronomicon Pages being out of reach by then
+        state.points = BigNum::from(80);
+        assert_eq!(state.auto_buy(Strategy::BuyCheapest), 4);
+        assert_eq!(state.buildings[0].count, 4);
+        assert_eq!(state.points, BigNum::from(80 - 73));
+        assert_eq!(state.auto_buy(Strategy::BuyCheapest), 0);
+    }
+
+    #[test]
+    fn failed_loads_are_logged_as_warnings() {
+        let dir = TempDir::new("load-warnings");
+        let path = slots::slot_path(&dir, "game");
+        fs::write(&path, "nonsense").unwrap();
+        let warnings = warnings_of(|| assert!(GameState::new().load_game(&dir, "game").is_err()));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Could not load the slot 'game': expected ident"), "{}", warnings[0]);
+
+        // Falling back to a backup is worth a warning too
+        let state = GameState::new();
+        state.save_game(&dir, "other").unwrap();
+        state.save_game(&dir, "other").unwrap();
+        fs::write(slots::slot_path(&dir, "other"), "").unwrap();
+        let warnings = warnings_of(|| GameState::new().load_game(&dir, "other").unwrap());
+        assert_eq!(warnings, ["The save in the slot 'other' could not be read, restored backup 1"]);
+
+        // A slot never saved to just starts a new run
+        assert!(warnings_of(|| GameState::new().load_game(&dir, "empty").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn idols_click_at_their_rate() {
+        let mut state = plain_state();
+        assert!(state.buildings[2].is_auto_clicker());
+        state.buildings[2].count = 5;
+        assert_eq!(state.auto_clicks_per_second(), 1.0);
+
+        // Ten seconds at once or an eighth at a time come to the same clicks
+        state.credit_auto_clicks(10.0);
+        assert_eq!(state.points, BigNum::from(10));
+        for _ in 0..80 {
+            state.credit_auto_clicks(0.125);
+        }
+        assert_eq!(state.points, BigNum::from(20));
+        assert_eq!(state.stats.total_clicks, 0);
+
+        // The rate is the building's own, whatever the content sets it to
+        state.buildings[2] = Building { count: 5, ..Building::auto_clicker("idol", "Animated Idol", "", 500, 1.5, 1.15) };
+        state.credit_auto_clicks(2.0);
+        assert_eq!(state.points, BigNum::from(35));
+        assert_eq!(state.lifetime_points, BigNum::from(35));
+    }
+
+    #[test]
+    fn auto_clicks_are_worth_a_click() {
+        let mut state = plain_state();
+        state.buildings[2].count = 5;
+        state.upgrades[1].purchased = true; // Doubles the worth of a click
+        state.credit_auto_clicks(1.0);
+        assert_eq!(state.points, BigNum::from(2));
+
+        // And they count towards influence power like any follower
+        state.lifetime_points = BigNum::from(999);
+        state.credit_auto_clicks(1.0);
+        assert_eq!(state.click_power, 2);
+        state.credit_auto_clicks(1.0);
+        assert_eq!(state.points, BigNum::from(8));
+    }
+}
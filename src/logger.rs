@@ -0,0 +1,103 @@
+// A minimal logger writing to `clickercurse.log` in the save directory
+//
+// The terminal belongs to the game while it runs, so log lines can't go to
+// stderr. Each line holds the unix time, the level and the message, and the
+// file is appended to so earlier sessions are kept.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Result as IoResult, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use clickercurse_game::unix_now;
+
+pub const FILE_NAME: &str = "clickercurse.log";
+
+struct FileLogger {
+    file: Mutex<File>,
+    level: LevelFilter,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // A failed write has nowhere better to be reported, so it's dropped
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{} {:<5} {}", unix_now(), record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Level for the number of -v flags given, None leaving logging off
+pub fn level(verbosity: u8) -> Option<LevelFilter> {
+    match verbosity {
+        0 => None,
+        1 => Some(LevelFilter::Info),
+        2 => Some(LevelFilter::Debug),
+        _ => Some(LevelFilter::Trace),
+    }
+}
+
+// Opens the log file in `dir` and routes the `log` macros to it
+pub fn init(dir: &Path, level: LevelFilter) -> IoResult<()> {
+    fs::create_dir_all(dir)?;
+    let file = OpenOptions::new().create(true).append(true).open(dir.join(FILE_NAME))?;
+    // Lives for the rest of the process. Setting it only fails when a logger
+    // is already set, which main never does twice.
+    let logger = Box::leak(Box::new(FileLogger { file: Mutex::new(file), level }));
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use log::Level;
+
+    #[test]
+    fn each_v_flag_logs_more() {
+        assert_eq!(level(0), None);
+        assert_eq!(level(1), Some(LevelFilter::Info));
+        assert_eq!(level(2), Some(LevelFilter::Debug));
+        assert_eq!(level(3), Some(LevelFilter::Trace));
+        assert_eq!(level(9), Some(LevelFilter::Trace));
+    }
+
+    #[test]
+    fn lines_at_the_level_or_above_are_appended() {
+        let dir = TempDir::new("logger");
+        let path = dir.join(FILE_NAME);
+        fs::write(&path, "earlier session\n").unwrap();
+
+        let file = OpenOptions::new().append(true).open(&path).unwrap();
+        let logger = FileLogger { file: Mutex::new(file), level: LevelFilter::Info };
+        for (level, message) in [(Level::Warn, "save failed"), (Level::Debug, "golden follower"), (Level::Info, "loaded")] {
+            logger.log(&Record::builder().level(level).args(format_args!("{}", message)).build());
+        }
+        logger.flush();
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", text);
+        assert_eq!(lines[0], "earlier session");
+        assert!(lines[1].ends_with(" WARN  save failed"), "{}", lines[1]);
+        assert!(lines[2].ends_with(" INFO  loaded"), "{}", lines[2]);
+        assert!(lines[1].split(' ').next().unwrap().parse::<u64>().is_ok());
+    }
+}
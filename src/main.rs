@@ -1,408 +1,562 @@
+// Terminal front-end: reads keys and the mouse, draws each screen, and runs
+// the tick thread, autosaves and the status server around the library's
+// GameState
+
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyModifiers},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind},
     execute,
-    style::{self, Stylize},
-    terminal::{self, ClearType},
+    style::{self, ContentStyle, StyledContent, Stylize},
+    terminal,
 };
 use std::{
-    collections::HashMap,
-    fs::{self, File},
-    io::{stdout, Read, Write},
-    path::Path,
+    fs,
+    io::{self, stdout, Write},
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     thread,
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
+use clap::{Parser, Subcommand};
 use std::io::Result as IoResult;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod keys;
+mod logger;
+mod picker;
+mod screen;
+mod serve;
+#[cfg(test)]
+mod testing;
+mod ticker;
+
+use clickercurse_game::{
+    achievements_visible, buildings_visible, eta_seconds, get_domination_status, help, help_line_count, help_visible, layout,
+    next_tier_eta, replay, save, slots, unix_now, upgrades_visible, Action, GameState, Menu, Prompt, PromptKind, RoundingMode, Strategy,
+    ACHIEVEMENT_BONUS, DEFAULT_AUTOSAVE_SECS, DEFAULT_OFFLINE_CAP_HOURS, SHARD_BONUS, SHARD_DIVISOR,
+};
+use clickercurse_game::bignum::BigNum;
+use clickercurse_game::content::{self, Content};
+use clickercurse_game::export::{ExportFormat, RunStats};
+use clickercurse_game::numbers::{format_multiplier, format_number, format_rate};
+use clickercurse_game::rebirth;
+use clickercurse_game::rng::Rng;
+use clickercurse_game::theme::colored;
+use clickercurse_game::view::{UpgradeView, ViewModel};
+use keys::KeyBindings;
+use screen::{Frame, Screen};
+use ticker::Ticker;
+
+// Fastest a held click key clicks; separate presses are never held back
+const HELD_CLICKS_PER_SEC: f64 = 20.0;
+
+// Presses of the click key closer together than this are the terminal
+// repeating a held key, for terminals that don't report repeats themselves
+const REPEAT_GAP: Duration = Duration::from_millis(45);
+
+// Longest the screen goes without a redraw, even when nothing changed
+const FORCED_REDRAW: Duration = Duration::from_secs(1);
+
+// Cells in the progress bar under the selected minion
+const BUILDING_BAR_WIDTH: usize = 20;
 
-// Game structures
-#[derive(Clone, Debug)]
-struct Building {
-    name: String,
-    description: String,
-    base_cost: u64,
-    base_production: f64,
-    count: u64,
-    cost_multiplier: f64,
+// Cleared at startup when the terminal can't render styling
+static COLORS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+fn detect_color_support() -> bool {
+    // https://no-color.org: any non-empty value disables color
+    if std::env::var_os("NO_COLOR").is_some_and(|val| !val.is_empty()) {
+        return false;
+    }
+    
+    match std::env::var("TERM") {
+        Ok(term) => term != "dumb",
+        // Windows consoles don't set TERM but handle styling fine
+        Err(_) => cfg!(windows),
+    }
 }
 
-impl Building {
-    fn new(name: &str, description: &str, base_cost: u64, base_production: f64, cost_multiplier: f64) -> Self {
-        Building {
-            name: name.to_string(),
-            description: description.to_string(),
-            base_cost,
-            base_production,
-            count: 0,
-            cost_multiplier,
-        }
+fn colors_enabled() -> bool {
+    COLORS_ENABLED.load(Ordering::Relaxed)
+}
+
+// All styled output goes through here so it can be stripped in one place
+fn paint<D: std::fmt::Display + Clone>(content: StyledContent<D>) -> StyledContent<D> {
+    if colors_enabled() {
+        content
+    } else {
+        StyledContent::new(ContentStyle::new(), content.content().clone())
+    }
+}
+
+// Selection marker, plus an affordability marker when color can't show it
+fn row_prefix(is_selected: bool, can_afford: bool) -> &'static str {
+    match (is_selected, can_afford && !colors_enabled()) {
+        (true, true) => ">+",
+        (true, false) => "> ",
+        (false, true) => " +",
+        (false, false) => "  ",
+    }
+}
+
+// Command-line options
+#[derive(Debug, Parser)]
+#[command(about = "A Cthulhu-themed clicker game for the terminal")]
+struct Options {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Start with production paused until 'p' is pressed
+    #[arg(long, global = true)]
+    start_paused: bool,
+    /// Start with the bell off, as if Sound were turned off in Settings
+    #[arg(long, global = true)]
+    muted: bool,
+    /// Play back a script of timed actions
+    #[arg(long, value_name = "FILE", global = true)]
+    replay: Option<String>,
+    /// Run the replay against a fresh game in simulated time and print the result
+    #[arg(long, requires = "replay", global = true)]
+    headless: bool,
+    /// Unlock debug-only features such as the sandbox
+    #[arg(long, global = true)]
+    debug: bool,
+    /// Fraction of production credited while the window is unfocused
+    #[arg(long, value_name = "FRACTION", value_parser = parse_fraction, global = true)]
+    unfocused_rate: Option<f64>,
+    /// Serve a read-only JSON snapshot of the game on this address
+    #[arg(long, value_name = "ADDR", global = true)]
+    serve: Option<String>,
+    /// Save slot to play, skipping the slot menu
+    #[arg(long, value_name = "NAME", value_parser = parse_slot, global = true)]
+    slot: Option<String>,
+    /// Directory holding the save slots
+    #[arg(long, value_name = "PATH", env = "CLICKERCURSE_SAVE_DIR", default_value = slots::DEFAULT_SAVE_DIR, global = true)]
+    save_dir: PathBuf,
+    /// Most offline time credited when a save is loaded
+    #[arg(long = "offline-cap", value_name = "HOURS", value_parser = parse_hours, global = true)]
+    offline_cap_hours: Option<f64>,
+    /// Seconds between autosaves, 0 to turn autosaving off
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_AUTOSAVE_SECS, global = true)]
+    autosave_secs: u64,
+    /// How fractional production becomes whole followers
+    #[arg(long, value_enum, default_value_t = RoundingMode::Floor, global = true)]
+    rounding: RoundingMode,
+    /// Fixed seed for the random number generator
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// Log to clickercurse.log in the save directory; -v for info, -vv for debug
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Play the game (the default)
+    Run,
+    /// Fast-forward the economy in simulated time and print the result
+    Simulate {
+        /// A replay script to run first
+        #[arg(value_name = "FILE", required_unless_present = "seconds")]
+        script: Option<String>,
+        /// Simulated seconds to run for, after the script if there is one
+        #[arg(long, value_name = "SECS")]
+        seconds: Option<u64>,
+        /// Spend followers automatically after each simulated second
+        #[arg(long, value_enum)]
+        strategy: Option<Strategy>,
+    },
+    /// Write the statistics of the save in a slot as CSV or JSON
+    ExportStats {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// File to write instead of printing to stdout
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Print the whole state of the save in a slot as JSON
+    Dump,
+    /// Delete the save in a slot, with its backups
+    Reset {
+        /// Delete without asking first
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    value.parse::<f64>().ok()
+        .filter(|rate| (0.0..=1.0).contains(rate))
+        .ok_or_else(|| "expected a fraction between 0 and 1".to_string())
+}
+
+fn parse_hours(value: &str) -> Result<f64, String> {
+    value.parse::<f64>().ok()
+        .filter(|hours| hours.is_finite() && *hours >= 0.0)
+        .ok_or_else(|| "expected a number of hours".to_string())
+}
+
+fn parse_slot(value: &str) -> Result<String, String> {
+    if slots::valid_slot_name(value) {
+        Ok(value.to_string())
+    } else {
+        Err("use letters, digits, '-' and '_'".to_string())
     }
+}
 
-    fn current_cost(&self) -> u64 {
-        if self.count == 0 {
-            return self.base_cost;
+impl Options {
+    fn rng(&self) -> Rng {
+        match self.seed {
+            Some(seed) => Rng::new(seed),
+            None => Rng::from_time(),
         }
-        (self.base_cost as f64 * self.cost_multiplier.powf(self.count as f64)) as u64
     }
+}
+
+fn action_for_key(key_event: &KeyEvent, bindings: &KeyBindings) -> Option<Action> {
+    match key_event.code {
+        // Not rebindable, so there is always a way out
+        KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => Some(Action::Quit),
+        // Few terminals report Shift+Enter, so '-' sells too
+        KeyCode::Enter if key_event.modifiers.contains(KeyModifiers::SHIFT) => Some(Action::SellSelection),
+        code => bindings.action(code),
+    }
+}
+
+// In the Minions menu the digits summon the minion on that row instead of
+// switching menus, and Esc leaves for the Sanctum
+fn buildings_action_for_key(key_event: &KeyEvent, bindings: &KeyBindings) -> Option<Action> {
+    match key_event.code {
+        KeyCode::Char(digit @ '1'..='9') => Some(Action::QuickBuy(digit as usize - '1' as usize)),
+        KeyCode::Esc => Some(Action::OpenMenu(Menu::Main)),
+        _ => action_for_key(key_event, bindings),
+    }
+}
 
-    fn total_production(&self) -> f64 {
-        self.base_production * self.count as f64
+// Only an answer gets through while a confirmation is pending
+fn confirm_action_for_key(key_event: &KeyEvent) -> Option<Action> {
+    match key_event.code {
+        KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => Some(Action::Quit),
+        KeyCode::Char('y' | 'Y') => Some(Action::Answer(true)),
+        KeyCode::Char('n' | 'N') | KeyCode::Esc => Some(Action::Answer(false)),
+        _ => None,
     }
+}
+
+// Keys while a prompt is open are typed into it instead
+fn prompt_action_for_key(key_event: &KeyEvent) -> Option<Action> {
+    let action = match key_event.code {
+        KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char(ch) => Action::PromptInput(ch),
+        KeyCode::Backspace => Action::PromptBackspace,
+        KeyCode::Enter => Action::PromptConfirm,
+        KeyCode::Esc => Action::PromptCancel,
+        _ => return None,
+    };
+    Some(action)
+}
 
-    fn buy(&mut self) -> u64 {
-        let cost = self.current_cost();
-        self.count += 1;
-        cost
+// Performs an action against the shared state, returning false on quit
+fn dispatch(game_state: &Arc<Mutex<GameState>>, action: Action) -> bool {
+    match action {
+        Action::Quit => return false,
+        Action::Save => {
+            // Write in the background so the UI keeps drawing
+            let game_state = Arc::clone(game_state);
+            thread::spawn(move || {
+                let result = save_shared(&game_state);
+                let mut state = game_state.lock().unwrap();
+                match result {
+                    Ok(()) => state.notify("Your dominion is saved".to_string()),
+                    Err(err) => state.notify(format!("Could not save: {}", err)),
+                }
+            });
+        },
+        action => game_state.lock().unwrap().apply_action(&action),
     }
+    true
 }
 
-#[derive(Clone, Debug)]
-struct Upgrade {
-    name: String,
-    description: String,
-    cost: u64,
-    purchased: bool,
-    building_multiplier: Option<(String, f64)>,
-    click_multiplier: Option<f64>,
+// Moves the game on by `elapsed` seconds, with production and auto-clicks
+// sped up by `scale`. Time still passes while paused, it just isn't credited.
+fn advance(state: &mut GameState, elapsed: f64, scale: f64) {
+    if !state.paused {
+        state.credit_production(elapsed * scale);
+        state.credit_auto_clicks(elapsed * scale);
+        state.advance_golden(elapsed);
+        state.advance_buffs(elapsed);
+    }
 }
 
-impl Upgrade {
-    fn new(
-        name: &str,
-        description: &str,
-        cost: u64,
-        building_multiplier: Option<(String, f64)>,
-        click_multiplier: Option<f64>,
-    ) -> Self {
-        Upgrade {
-            name: name.to_string(),
-            description: description.to_string(),
-            cost,
-            purchased: false,
-            building_multiplier,
-            click_multiplier,
+// Deletes a slot's save and backups for the `reset` command, asking first
+// unless `yes` is set
+fn reset_slot(options: &Options, yes: bool) -> IoResult<()> {
+    let slot = options.slot.as_deref().unwrap_or(slots::DEFAULT_SLOT);
+    let path = slots::slot_path(&options.save_dir, slot);
+    if !path.exists() {
+        eprintln!("There is no save in the slot '{}' ({})", slot, path.display());
+        std::process::exit(1);
+    }
+    
+    if !yes {
+        eprint!("Delete the save in the slot '{}' ({}) and its backups? [y/N] ", slot, path.display());
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            eprintln!("Kept the save");
+            return Ok(());
         }
     }
+    save::remove_with_backups(&path)?;
+    println!("Deleted the save in the slot '{}'", slot);
+    Ok(())
 }
 
-#[derive(Clone, Debug)]
-struct GameState {
-    points: u64,
-    lifetime_points: u64,
-    click_power: u64,
-    buildings: HashMap<String, Building>,
-    upgrades: Vec<Upgrade>,
-    current_menu: Menu,
-    selected_index: usize,
-    production_remainder: f64, // Track fractional production
+// Writes the statistics of the --slot save for the `export-stats` command
+fn export_stats(options: &Options, format: ExportFormat, out: Option<&Path>) -> IoResult<()> {
+    let slot = options.slot.as_deref().unwrap_or(slots::DEFAULT_SLOT);
+    if !slots::slot_path(&options.save_dir, slot).exists() {
+        eprintln!("There is no save in the slot '{}'", slot);
+        std::process::exit(1);
+    }
+    let mut state = GameState::new();
+    if let Err(err) = state.load_game(&options.save_dir, slot) {
+        eprintln!("Could not load the save: {}", err);
+        std::process::exit(1);
+    }
+    
+    let text = RunStats::of(&state).write(format);
+    match out {
+        Some(path) => fs::write(path, text),
+        None => io::stdout().write_all(text.as_bytes()),
+    }
 }
 
-#[derive(Clone, Debug, PartialEq)]
-enum Menu {
-    Main,
-    Buildings,
-    Upgrades,
+// Writes the state of the --slot save as JSON to `out`. A slot without a save
+// gives the state of a new game.
+fn dump_state(options: &Options, out: &mut impl Write) -> IoResult<()> {
+    let slot = options.slot.as_deref().unwrap_or(slots::DEFAULT_SLOT);
+    let mut state = GameState::new();
+    state.load_game(&options.save_dir, slot)
+        .map_err(|err| io::Error::new(err.kind(), format!("Could not load the save: {}", err)))?;
+    out.write_all(state.dump_json()?.as_bytes())
 }
 
-impl GameState {
-    fn new() -> Self {
-        let mut buildings = HashMap::new();
-        let production_remainder = 0.0;
-        
-        // Add Cthulhu-themed buildings
-        buildings.insert(
-            "cursor".to_string(),
-            Building::new("Cultist", "Whispers eldritch secrets", 15, 0.1, 1.15),
-        );
-        buildings.insert(
-            "grandma".to_string(),
-            Building::new("Elder One", "Ancient being from beyond", 100, 1.0, 1.15),
-        );
-        buildings.insert(
-            "farm".to_string(),
-            Building::new("Ritual Site", "Conducts forbidden ceremonies", 1100, 8.0, 1.15),
-        );
-        buildings.insert(
-            "mine".to_string(),
-            Building::new("Deep One Colony", "Underwater servants of Cthulhu", 12000, 47.0, 1.15),
-        );
-        buildings.insert(
-            "temple".to_string(),
-            Building::new("Temple of Dagon", "Ancient place of worship", 130000, 260.0, 1.15),
-        );
-        buildings.insert(
-            "portal".to_string(),
-            Building::new("Dimensional Portal", "Gateway to R'lyeh", 1400000, 1400.0, 1.15),
-        );
-        
-        // Create Cthulhu-themed upgrades
-        let upgrades = vec![
-            Upgrade::new(
-                "Necronomicon Pages",
-                "Cultists are twice as efficient",
-                100,
-                Some(("cursor".to_string(), 2.0)),
-                None,
-            ),
-            Upgrade::new(
-                "Eldritch Incantation",
-                "Your influence is twice as powerful",
-                500,
-                None,
-                Some(2.0),
-            ),
-            Upgrade::new(
-                "Ancient Artifacts",
-                "Elder Ones are twice as efficient",
-                1000,
-                Some(("grandma".to_string(), 2.0)),
-                None,
-            ),
-            Upgrade::new(
-                "Blood Sacrifice",
-                "Ritual Sites are twice as efficient",
-                11000,
-                Some(("farm".to_string(), 2.0)),
-                None,
-            ),
-            Upgrade::new(
-                "Esoteric Geometry",
-                "Deep One Colonies are twice as efficient",
-                120000,
-                Some(("mine".to_string(), 2.0)),
-                None,
-            ),
-            Upgrade::new(
-                "Non-Euclidean Architecture",
-                "Temples of Dagon are twice as efficient",
-                1300000,
-                Some(("temple".to_string(), 2.0)),
-                None,
-            ),
-            Upgrade::new(
-                "The Stars Are Right",
-                "All minions are twice as efficient",
-                10000000,
-                Some(("all".to_string(), 2.0)),
-                Some(5.0),
-            ),
-        ];
-        
-        GameState {
-            points: 0,
-            lifetime_points: 0,
-            click_power: 1,
-            buildings,
-            upgrades,
-            current_menu: Menu::Main,
-            selected_index: 0,
-            production_remainder: 0.0,
-        }
+// Replays a script against a fresh state (or the --slot save, for simulate)
+// using simulated time, then `seconds` more, and prints the result. Nothing
+// is saved.
+fn run_headless(mut state: GameState, script: Vec<replay::TimedAction>, seconds: u64, strategy: Option<Strategy>) {
+    let (clock, purchases) = fast_forward(&mut state, script, seconds, strategy);
+    
+    println!("Simulated time: {:.1}s", clock);
+    println!("Seed: {}", state.rng.seed());
+    println!("Followers: {}", state.points);
+    println!("Total Converts: {}", state.lifetime_points);
+    println!("Influence Power: {}", state.click_power);
+    println!("Production: {:.1}/sec", state.production_per_second());
+    if strategy.is_some() {
+        println!("Purchases: {}", purchases);
     }
     
-    fn calculate_production_per_second(&self) -> f64 {
-        let mut total = 0.0;
-        let mut all_buildings_multiplier = 1.0;
-        
-        // First check for "all" building multipliers
-        for upgrade in &self.upgrades {
-            if upgrade.purchased {
-                if let Some((building_key, building_mult)) = &upgrade.building_multiplier {
-                    if building_key == "all" {
-                        all_buildings_multiplier *= building_mult;
-                    }
-                }
-            }
-        }
-        
-        for (key, building) in &self.buildings {
-            let mut multiplier = all_buildings_multiplier;
-            
-            // Apply specific building upgrades
-            for upgrade in &self.upgrades {
-                if upgrade.purchased {
-                    if let Some((building_key, building_mult)) = &upgrade.building_multiplier {
-                        if building_key == key {
-                            multiplier *= building_mult;
-                        }
-                    }
-                }
-            }
-            
-            total += building.total_production() * multiplier;
-        }
-        
-        total
+    for building in &state.buildings {
+        println!("{}: {}", building.name, building.count);
     }
+}
+
+// Plays the script, then `seconds` more a second at a time, letting
+// `strategy` spend the followers after each one. Returns the simulated time
+// and the purchases the strategy made.
+fn fast_forward(state: &mut GameState, script: Vec<replay::TimedAction>, seconds: u64, strategy: Option<Strategy>) -> (f64, usize) {
+    let mut clock = 0.0;
     
-    fn click(&mut self) {
-        let mut click_multiplier = 1.0;
-        
-        // Apply click upgrades
-        for upgrade in &self.upgrades {
-            if upgrade.purchased {
-                if let Some(mult) = upgrade.click_multiplier {
-                    click_multiplier *= mult;
-                }
-            }
-        }
-        
-        let points_to_add = (self.click_power as f64 * click_multiplier) as u64;
-        self.points += points_to_add;
-        self.lifetime_points += points_to_add;
-        
-        // Check if we should increase click power based on lifetime points
-        self.check_click_power_upgrade();
-    }
-    
-    fn check_click_power_upgrade(&mut self) {
-        // Increase click power based on lifetime points milestones
-        let new_click_power = match self.lifetime_points {
-            0..=999 => 1,
-            1000..=9999 => 2,
-            10000..=99999 => 5,
-            100000..=999999 => 10,
-            1000000..=9999999 => 25,
-            10000000..=99999999 => 50,
-            _ => 100,
-        };
+    let mut quit = false;
+    for step in script {
+        advance(state, step.at - clock, 1.0);
+        clock = step.at;
         
-        if new_click_power > self.click_power {
-            self.click_power = new_click_power;
+        match step.action {
+            Action::Quit => {
+                quit = true;
+                break;
+            },
+            // Headless runs never touch the save file
+            Action::Save => {},
+            action => state.apply_action(&action),
         }
     }
     
-    fn buy_building(&mut self, key: &str) -> bool {
-        if let Some(building) = self.buildings.get_mut(key) {
-            let cost = building.current_cost();
-            if self.points >= cost {
-                self.points -= cost;
-                building.buy();
-                return true;
-            }
+    // A script that quits ends the run there
+    let seconds = if quit { 0 } else { seconds };
+    let mut purchases = 0;
+    for _ in 0..seconds {
+        advance(state, 1.0, 1.0);
+        clock += 1.0;
+        if let Some(strategy) = strategy {
+            purchases += state.auto_buy(strategy);
         }
-        false
     }
+    (clock, purchases)
+}
+
+// Save a copy of the shared state without holding the lock during the write
+fn save_shared(game_state: &Mutex<GameState>) -> IoResult<()> {
+    let snapshot = {
+        let mut state = game_state.lock().unwrap();
+        state.io_in_progress = true;
+        state.dirty = true;
+        state.clone()
+    };
     
-    fn buy_upgrade(&mut self, index: usize) -> bool {
-        if index < self.upgrades.len() {
-            let cost = self.upgrades[index].cost;
-            if !self.upgrades[index].purchased && self.points >= cost {
-                self.points -= cost;
-                self.upgrades[index].purchased = true;
-                return true;
-            }
+    let result = snapshot.save_game(&snapshot.save_dir, &snapshot.slot);
+    
+    let mut state = game_state.lock().unwrap();
+    state.io_in_progress = false;
+    state.dirty = true;
+    result
+}
+
+fn main() -> IoResult<()> {
+    let options = Options::parse();
+    if let Some(level) = logger::level(options.verbose) {
+        if let Err(err) = logger::init(&options.save_dir, level) {
+            eprintln!("Could not open {}: {}", logger::FILE_NAME, err);
         }
-        false
     }
     
-    fn save_game(&self) -> IoResult<()> {
-        // Simple save format - just save the key stats for now
-        let save_dir = "saves";
-        if !Path::new(save_dir).exists() {
-            fs::create_dir(save_dir)?;
-        }
-        
-        let mut file = File::create("saves/game.save")?;
-        
-        // Write points
-        writeln!(file, "points:{}", self.points)?;
-        writeln!(file, "lifetime:{}", self.lifetime_points)?;
-        writeln!(file, "click_power:{}", self.click_power)?;
-        
-        // Write buildings
-        for (key, building) in &self.buildings {
-            writeln!(file, "building:{}:{}:{}", key, building.count, building.base_production)?;
-        }
-        
-        // Write upgrades
-        for (i, upgrade) in self.upgrades.iter().enumerate() {
-            writeln!(file, "upgrade:{}:{}", i, upgrade.purchased)?;
+    // `simulate FILE` runs like `--replay FILE --headless`, but starts from
+    // the --slot save when one is given
+    let (replay, headless) = match &options.command {
+        Some(Command::Reset { yes }) => return reset_slot(&options, *yes),
+        Some(Command::Simulate { script, .. }) => (script.as_ref(), true),
+        Some(Command::Run | Command::ExportStats { .. } | Command::Dump) | None => (options.replay.as_ref(), options.headless),
+    };
+    
+    let script = match replay {
+        Some(path) => {
+            let text = fs::read_to_string(path)?;
+            let script = replay::parse_script(&text).unwrap_or_else(|err| {
+                eprintln!("{}: {}", path, err);
+                std::process::exit(2);
+            });
+            Some(script)
+        },
+        None => None,
+    };
+    
+    // Has to be in place before the first GameState is made. Anything wrong
+    // with it is reported once a screen is up, or on stderr for replays.
+    let mut content_warnings = Vec::new();
+    let mut content = Content::load(&options.save_dir).unwrap_or_else(|errors| {
+        content_warnings.push(format!("Could not use {}, using the built-in content instead", content::FILE_NAME));
+        content_warnings.extend(errors.into_iter().map(|error| format!("{}: {}", content::FILE_NAME, error)));
+        Content::default()
+    });
+    content_warnings.extend(content.add_packs(&options.save_dir.join(content::PACK_DIR)));
+    for warning in &content_warnings {
+        log::warn!("{}", warning);
+    }
+    content::install(content);
+    
+    if let Some(Command::ExportStats { format, out }) = &options.command {
+        return export_stats(&options, *format, out.as_deref());
+    }
+    if let Some(Command::Dump) = &options.command {
+        if let Err(err) = dump_state(&options, &mut io::stdout()) {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
-        
-        Ok(())
+        return Ok(());
     }
     
-    fn load_game(&mut self) -> IoResult<()> {
-        let path = Path::new("saves/game.save");
-        if !path.exists() {
-            return Ok(());
+    if headless {
+        for warning in &content_warnings {
+            eprintln!("{}", warning);
         }
-        
-        let mut file = File::open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        // Reset production remainder when loading a game
-        self.production_remainder = 0.0;
-        
-        for line in contents.lines() {
-            let parts: Vec<&str> = line.split(':').collect();
-            if parts.len() < 2 {
-                continue;
-            }
-            
-            match parts[0] {
-                "points" => {
-                    if let Ok(val) = parts[1].parse::<u64>() {
-                        self.points = val;
-                    }
-                },
-                "lifetime" => {
-                    if let Ok(val) = parts[1].parse::<u64>() {
-                        self.lifetime_points = val;
-                    }
-                },
-                "click_power" => {
-                    if let Ok(val) = parts[1].parse::<u64>() {
-                        self.click_power = val;
-                    }
-                },
-                "building" => {
-                    if parts.len() >= 4 {
-                        let key = parts[1];
-                        if let (Ok(count), Ok(_)) = (parts[2].parse::<u64>(), parts[3].parse::<f64>()) {
-                            if let Some(building) = self.buildings.get_mut(key) {
-                                building.count = count;
-                            }
-                        }
-                    }
-                },
-                "upgrade" => {
-                    if parts.len() >= 3 {
-                        if let (Ok(index), Ok(purchased)) = (parts[1].parse::<usize>(), parts[2].parse::<bool>()) {
-                            if index < self.upgrades.len() {
-                                self.upgrades[index].purchased = purchased;
-                            }
-                        }
-                    }
-                },
-                _ => {}
+        let mut state = GameState::new();
+        let (mut seconds, mut strategy) = (0, None);
+        if let Some(Command::Simulate { seconds: extra, strategy: chosen, .. }) = &options.command {
+            if let Some(slot) = &options.slot {
+                if let Err(err) = state.load_game(&options.save_dir, slot) {
+                    eprintln!("Could not load the save: {}", err);
+                    std::process::exit(1);
+                }
             }
+            (seconds, strategy) = (extra.unwrap_or(0), *chosen);
         }
-        
-        // Check if click power should be upgraded based on lifetime points
-        self.check_click_power_upgrade();
-        
-        Ok(())
+        state.rounding = options.rounding;
+        state.rng = options.rng();
+        run_headless(state, script.unwrap_or_default(), seconds, strategy);
+        return Ok(());
     }
-}
-
-fn main() -> IoResult<()> {
+    
+    // Bind before taking over the terminal so errors are readable
+    let listener = match &options.serve {
+        Some(addr) => Some(std::net::TcpListener::bind(addr)?),
+        None => None,
+    };
+    
     let mut stdout = stdout();
 
+    if !detect_color_support() {
+        COLORS_ENABLED.store(false, Ordering::Relaxed);
+        style::Colored::set_ansi_color_disabled(true);
+    }
+
     // Setup terminal
     terminal::enable_raw_mode()?;
-    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+    execute!(
+        stdout,
+        terminal::EnterAlternateScreen,
+        cursor::Hide,
+        event::EnableFocusChange,
+        event::EnableBracketedPaste
+    )?;
+
+    let mut screen = Screen::new();
+    
+    // Replays run unattended, so they don't stop at the slot menu
+    let slot = match (&options.slot, &options.replay) {
+        (Some(slot), _) => Some(slot.clone()),
+        (None, Some(_)) => Some(slots::DEFAULT_SLOT.to_string()),
+        (None, None) => picker::pick_slot(&mut screen, &mut stdout, &options.save_dir)?,
+    };
+    let Some(slot) = slot else {
+        return restore_terminal(&mut stdout);
+    };
+    screen.invalidate();
 
     // Initialize game state
     let game_state = Arc::new(Mutex::new(GameState::new()));
     
-    // Try to load saved game
+    // Try to load saved game, and the key bindings kept next to it
+    let bindings;
     {
         let mut state = game_state.lock().unwrap();
-        let _ = state.load_game();
+        state.slot = slot.clone();
+        state.save_dir = options.save_dir.clone();
+        if let Err(err) = state.load_game(&options.save_dir, &slot) {
+            state.notify(format!("Could not load the save: {}", err));
+        }
+        bindings = KeyBindings::load(&options.save_dir).unwrap_or_else(|err| {
+            log::warn!("Could not read {}: {}", keys::FILE_NAME, err);
+            state.notify(format!("Could not read {}, using the default keys: {}", keys::FILE_NAME, err));
+            KeyBindings::default()
+        });
+        for warning in content_warnings {
+            state.notify(warning);
+        }
+        let cap_hours = options.offline_cap_hours.unwrap_or(DEFAULT_OFFLINE_CAP_HOURS);
+        state.apply_offline_progress(Duration::from_secs_f64(cap_hours * 3600.0));
+        state.paused = options.start_paused;
+        if options.muted {
+            state.sound = false;
+        }
+        state.debug_mode = options.debug;
+        state.rounding = options.rounding;
+        state.rng = options.rng();
+        state.autosave_secs = options.autosave_secs;
+        if let Some(rate) = options.unfocused_rate {
+            state.unfocused_rate = rate;
+        }
     }
     
     let running = Arc::new(Mutex::new(true));
@@ -415,145 +569,165 @@ fn main() -> IoResult<()> {
             let mut last_time = std::time::Instant::now();
             
             while *running.lock().unwrap() {
-                thread::sleep(Duration::from_millis(100));
+                let tick_ms = game_state.lock().unwrap().tick_ms;
+                thread::sleep(Duration::from_millis(tick_ms));
                 
                 let now = std::time::Instant::now();
                 let elapsed = now.duration_since(last_time).as_secs_f64();
                 last_time = now;
                 
                 let mut state = game_state.lock().unwrap();
-                let production = state.calculate_production_per_second() * elapsed;
-                
-                // Add the current production to any remainder from previous ticks
-                state.production_remainder += production;
-                
-                // Extract the whole number part
-                let points_to_add = state.production_remainder.floor() as u64;
+                state.add_playtime(elapsed);
+                let scale = state.production_scale();
+                advance(&mut state, elapsed, scale);
+            }
+        });
+    }
+
+    // Auto-save thread; the game is still saved on exit when it's off
+    if options.autosave_secs > 0 {
+        let game_state = Arc::clone(&game_state);
+        let running = Arc::clone(&running);
+        let interval = Duration::from_secs(options.autosave_secs);
+        thread::spawn(move || {
+            while *running.lock().unwrap() {
+                thread::sleep(interval);
                 
-                if points_to_add > 0 {
-                    // Update the remainder to keep only the fractional part
-                    state.production_remainder -= points_to_add as f64;
-                    
-                    // Add the points
-                    state.points += points_to_add;
-                    state.lifetime_points += points_to_add;
+                if game_state.lock().unwrap().autosave {
+                    let _ = save_shared(&game_state);
                 }
             }
         });
     }
 
-    // Auto-save thread
-    {
+    // Snapshot server thread
+    if let Some(listener) = listener {
+        let game_state = Arc::clone(&game_state);
+        thread::spawn(move || serve::serve(listener, game_state));
+    }
+
+    // Replay thread, feeding scripted actions through the same dispatch as keys
+    if let Some(script) = script {
         let game_state = Arc::clone(&game_state);
         let running = Arc::clone(&running);
         thread::spawn(move || {
-            while *running.lock().unwrap() {
-                thread::sleep(Duration::from_secs(30));
+            let start = Instant::now();
+            
+            for step in script {
+                let due = Duration::from_secs_f64(step.at);
+                if let Some(wait) = due.checked_sub(start.elapsed()) {
+                    thread::sleep(wait);
+                }
                 
-                let state = game_state.lock().unwrap();
-                let _ = state.save_game();
+                if !*running.lock().unwrap() {
+                    return;
+                }
+                if !dispatch(&game_state, step.action) {
+                    *running.lock().unwrap() = false;
+                    return;
+                }
             }
         });
     }
 
+    let ticker = Ticker::new();
+    let mut last_frame = Instant::now();
+    let mut mouse_captured = false;
+    let mut click_limiter = ClickLimiter::default();
+
     // Input + draw loop
     loop {
-        // Get current state
-        let state = game_state.lock().unwrap();
+        // A replay script can end the session too
+        if !*running.lock().unwrap() {
+            break;
+        }
+        
+        // Copy out a consistent view of the state, holding the lock only for that.
+        // Nothing is drawn while nothing changed, except for the periodic redraw
+        // that keeps countdowns, the ticker and the save spinner moving.
+        let (width, height) = terminal::size()?;
+        let (view, mouse, bell) = {
+            let mut state = game_state.lock().unwrap();
+            state.terminal_size = (width, height);
+            state.advance_notifications();
+            state.forget_old_clicks();
+            let due = state.dirty || state.io_in_progress || last_frame.elapsed() >= FORCED_REDRAW;
+            state.dirty = false;
+            (due.then(|| ViewModel::capture(&state)), state.mouse, std::mem::take(&mut state.bell))
+        };
+        if bell {
+            execute!(stdout, style::Print('\u{7}'))?;
+        }
         
-        // Draw UI based on current menu
-        match state.current_menu {
-            Menu::Main => draw_main_menu(&mut stdout, &state)?,
-            Menu::Buildings => draw_buildings_menu(&mut stdout, &state)?,
-            Menu::Upgrades => draw_upgrades_menu(&mut stdout, &state)?,
+        // Capturing the mouse takes over the terminal's own text selection, so
+        // it is only turned on while the setting asks for it
+        if mouse != mouse_captured {
+            if mouse {
+                execute!(stdout, event::EnableMouseCapture)?;
+            } else {
+                execute!(stdout, event::DisableMouseCapture)?;
+            }
+            mouse_captured = mouse;
         }
         
-        // Release lock while waiting for input
-        drop(state);
+        if let Some(view) = view {
+            draw_frame(&mut screen, &mut stdout, &view, &ticker, width, height)?;
+            last_frame = Instant::now();
+        }
         
         // Poll for input with 100ms timeout
         if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key_event) = event::read()? {
-                let mut state = game_state.lock().unwrap();
-                
-                match key_event.code {
-                    // Global keys
-                    KeyCode::Char('.') => {
-                        state.click();
-                    },
-                    KeyCode::Char('s') => {
-                        let _ = state.save_game();
-                    },
-                    KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
-                        drop(state);
-                        break;
-                    },
-                    
-                    // Menu navigation
-                    KeyCode::Char('1') => {
-                        state.current_menu = Menu::Main;
-                        state.selected_index = 0;
-                    },
-                    KeyCode::Char('2') => {
-                        state.current_menu = Menu::Buildings;
-                        state.selected_index = 0;
-                    },
-                    KeyCode::Char('3') => {
-                        state.current_menu = Menu::Upgrades;
-                        state.selected_index = 0;
-                    },
-                    
-                    // Selection navigation
-                    KeyCode::Up => {
-                        if state.selected_index > 0 {
-                            state.selected_index -= 1;
-                        }
-                    },
-                    KeyCode::Down => {
-                        match state.current_menu {
-                            Menu::Buildings => {
-                                if state.selected_index < state.buildings.len() - 1 {
-                                    state.selected_index += 1;
-                                }
-                            },
-                            Menu::Upgrades => {
-                                if state.selected_index < state.upgrades.len() - 1 {
-                                    state.selected_index += 1;
-                                }
-                            },
-                            _ => {}
-                        }
-                    },
-                    
-                    // Selection action
-                    KeyCode::Enter => {
-                        match state.current_menu {
-                            Menu::Buildings => {
-                                // First collect all building keys in sorted order by cost
-                                let mut building_entries: Vec<(String, u64)> = Vec::new();
-                                for (key, building) in &state.buildings {
-                                    building_entries.push((key.clone(), building.current_cost()));
-                                }
-                                
-                                // Sort by cost
-                                building_entries.sort_by(|a, b| a.1.cmp(&b.1));
-                                
-                                // Now we can safely use the sorted keys
-                                if state.selected_index < building_entries.len() {
-                                    let key = &building_entries[state.selected_index].0;
-                                    state.buy_building(key);
-                                }
-                            },
-                            Menu::Upgrades => {
-                                let index = state.selected_index;
-                                state.buy_upgrade(index);
-                            },
-                            _ => {}
+            match event::read()? {
+                // Some terminals report releases too, which aren't presses of their own
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Release => {},
+                Event::Key(key_event) => {
+                    let (confirming, prompting, menu) = {
+                        let state = game_state.lock().unwrap();
+                        (state.confirmation.is_some(), state.prompt.is_some(), state.current_menu.clone())
+                    };
+                    let action = if confirming {
+                        confirm_action_for_key(&key_event)
+                    } else if prompting {
+                        prompt_action_for_key(&key_event)
+                    } else if menu == Menu::Buildings {
+                        buildings_action_for_key(&key_event, &bindings)
+                    } else {
+                        action_for_key(&key_event, &bindings)
+                    };
+                    let repeat = key_event.kind == KeyEventKind::Repeat;
+                    if action == Some(Action::Click) && !click_limiter.allow(Instant::now(), repeat) {
+                        continue;
+                    }
+                    if let Some(action) = action {
+                        if !dispatch(&game_state, action) {
+                            break;
                         }
-                    },
-                    
-                    _ => {}
-                }
+                    }
+                },
+                Event::Mouse(mouse_event) if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) => {
+                    // Like a key press, nothing but an answer gets through a confirmation
+                    let blocked = {
+                        let state = game_state.lock().unwrap();
+                        state.confirmation.is_some() || state.prompt.is_some()
+                    };
+                    if !blocked {
+                        dispatch(&game_state, Action::MouseClick(mouse_event.row));
+                    }
+                },
+                Event::Paste(text) => {
+                    let prompting = game_state.lock().unwrap().prompt.is_some();
+                    if prompting {
+                        dispatch(&game_state, Action::PromptPaste(text));
+                    }
+                },
+                // Whatever was on screen may have been reflowed
+                Event::Resize(_, _) => {
+                    screen.invalidate();
+                    game_state.lock().unwrap().dirty = true;
+                },
+                Event::FocusGained => game_state.lock().unwrap().focused = true,
+                Event::FocusLost => game_state.lock().unwrap().focused = false,
+                _ => {}
             }
         }
     }
@@ -564,11 +738,39 @@ fn main() -> IoResult<()> {
     // Save game before exit
     {
         let state = game_state.lock().unwrap();
-        let _ = state.save_game();
+        let _ = state.save_game(&state.save_dir, &state.slot);
     }
     
+    restore_terminal(&mut stdout)
+}
+
+// Lets every separate press of the click key through, but only
+// HELD_CLICKS_PER_SEC of the repeats while it is held down
+#[derive(Default)]
+struct ClickLimiter {
+    last_press: Option<Instant>,
+    last_click: Option<Instant>,
+}
+
+impl ClickLimiter {
+    fn allow(&mut self, now: Instant, repeat: bool) -> bool {
+        let repeat = repeat || self.last_press.is_some_and(|at| now.duration_since(at) < REPEAT_GAP);
+        self.last_press = Some(now);
+        let due = !repeat || self.last_click
+            .is_none_or(|at| now.duration_since(at).as_secs_f64() >= 1.0 / HELD_CLICKS_PER_SEC);
+        if due {
+            self.last_click = Some(now);
+        }
+        due
+    }
+}
+
+fn restore_terminal(stdout: &mut std::io::Stdout) -> IoResult<()> {
     execute!(
         stdout,
+        event::DisableMouseCapture,
+        event::DisableFocusChange,
+        event::DisableBracketedPaste,
         terminal::LeaveAlternateScreen,
         cursor::Show
     )?;
@@ -577,12 +779,41 @@ fn main() -> IoResult<()> {
     Ok(())
 }
 
-fn draw_main_menu(stdout: &mut std::io::Stdout, state: &GameState) -> IoResult<()> {
-    let (_width, height) = terminal::size()?;
-    let production_per_second = state.calculate_production_per_second();
+// Draws one frame of the current menu and its overlays
+fn draw_frame(screen: &mut Screen, out: &mut impl Write, view: &ViewModel, ticker: &Ticker, width: u16, height: u16) -> IoResult<()> {
+    let mut frame = Frame::new(width, height);
+    match view.menu {
+        Menu::Main => draw_main_menu(&mut frame, view, ticker),
+        Menu::Buildings => draw_buildings_menu(&mut frame, view),
+        Menu::Upgrades => draw_upgrades_menu(&mut frame, view),
+        Menu::Stats => draw_stats_menu(&mut frame, view),
+        Menu::History => draw_history_menu(&mut frame, view),
+        Menu::Deeds => draw_deeds_menu(&mut frame, view),
+        Menu::Ascension => draw_ascension_screen(&mut frame, view),
+        Menu::Relics => draw_prestige_menu(&mut frame, view),
+        Menu::Rebirth => draw_rebirth_menu(&mut frame, view),
+        Menu::Achievements => draw_achievements_menu(&mut frame, view),
+        Menu::Settings => draw_settings_menu(&mut frame, view),
+        Menu::Help => draw_help_screen(&mut frame, view),
+        Menu::Export => draw_export_menu(&mut frame, view),
+        Menu::Victory => draw_victory_screen(&mut frame, view),
+    }
+    draw_io_indicator(&mut frame, view);
+    draw_sandbox_marker(&mut frame, view);
+    draw_paused_marker(&mut frame, view);
+    draw_seed(&mut frame, view);
+    draw_golden(&mut frame, view);
+    draw_notification(&mut frame, view);
+    draw_confirmation(&mut frame, view);
+    screen.present(out, frame)
+}
+
+fn draw_main_menu(frame: &mut Frame, view: &ViewModel, ticker: &Ticker) {
+    let (width, height) = frame.size();
+    let production_per_second = view.production_per_second;
     
     // Determine next influence power milestone
-    let next_milestone = match state.lifetime_points {
+    let next_milestone = match view.lifetime_points.to_u64() {
         0..=999 => "1,000",
         1000..=9999 => "10,000",
         10000..=99999 => "100,000",
@@ -592,177 +823,1318 @@ fn draw_main_menu(stdout: &mut std::io::Stdout, state: &GameState) -> IoResult<(
         _ => "Maximum",
     };
     
-    let next_power = match state.lifetime_points {
+    let next_power = match view.lifetime_points.to_u64() {
         0..=999 => 2,
         1000..=9999 => 5,
         10000..=99999 => 10,
         100000..=999999 => 25,
         1000000..=9999999 => 50,
         10000000..=99999999 => 100,
-        _ => state.click_power,
+        _ => view.click_power,
     };
     
-    execute!(
-        stdout,
-        terminal::Clear(ClearType::All),
-        cursor::MoveTo(0, 0),
-        style::PrintStyledContent("Cthulhu's Dominion".blue().bold()),
-        cursor::MoveTo(0, 2),
-        style::PrintStyledContent(format!("Followers: {}", state.points).green()),
-        cursor::MoveTo(0, 3),
-        style::Print(format!("Total Converts: {}", state.lifetime_points)),
-        cursor::MoveTo(0, 4),
-        style::Print(format!("Conversion Rate: {:.1} followers/sec", production_per_second)),
-        cursor::MoveTo(0, 5),
-        style::Print(format!("Influence Power: {}", state.click_power)),
-        cursor::MoveTo(0, 6),
-        style::Print(format!("Next Power ({}) at {} total converts",
-            if next_power > state.click_power { next_power.to_string() } else { "Max".to_string() },
-            next_milestone)),
-        cursor::MoveTo(0, 7),
-        style::Print(format!("Domination Progress: {}", get_domination_status(state.lifetime_points))),
-        
-        cursor::MoveTo(0, 9),
-        style::PrintStyledContent("Rituals:".yellow()),
-        cursor::MoveTo(0, 10),
-        style::Print("Press '.' to spread influence and gain followers"),
-        cursor::MoveTo(0, 11),
-        style::Print("Press '1' for Sanctum, '2' for Minions, '3' for Artifacts"),
-        cursor::MoveTo(0, 12),
-        style::Print("Press 's' to record in the Necronomicon"),
-        cursor::MoveTo(0, 13),
-        style::Print("Press Ctrl+C to return to mortal realm"),
-        
-        cursor::MoveTo(0, height - 1),
-        style::PrintStyledContent("The Sanctum".cyan())
-    )?;
+    frame.print_styled(0, 0, paint(colored("Cthulhu's Dominion", view.theme.title).bold()));
+    match &view.prompt {
+        Some(Prompt { kind: PromptKind::Rename, text }) => frame.print_styled(0, 1, paint(colored(format!(
+            "Name this run: {}_  (Enter to keep, Esc to cancel)", text), view.theme.heading))),
+        Some(Prompt { kind: PromptKind::Import, text }) => frame.print_styled(0, 1, paint(colored(format!(
+            "Paste a save string: {} characters  (Enter to import, Esc to cancel)", text.chars().count()), view.theme.heading))),
+        // Typed in the Settings menu, where it is drawn
+        Some(Prompt { kind: PromptKind::Reserve, .. }) => {},
+        None if !view.profile_name.is_empty() => {
+            frame.print_styled(0, 1, paint(colored(format!("Run: {}", display_profile_name(&view.profile_name)), view.theme.accent)));
+        },
+        None => {},
+    }
+    frame.print_styled(0, 2, paint(colored(format!("Followers: {}", format_number(view.points, view.number_format)), view.theme.points)));
+    frame.print(0, 3, format!("Total Converts: {}", format_number(view.lifetime_points, view.number_format)));
+    let mut bonuses = Vec::new();
+    if view.shards > 0 {
+        bonuses.push(format!("+{:.0}% from {} eldritch shards", view.shards as f64 * SHARD_BONUS * 100.0, view.shards));
+    }
+    if view.achievement_multiplier > 1.0 {
+        bonuses.push(format!("{} from achievements", format_multiplier(view.achievement_multiplier, view.number_format)));
+    }
+    let rate_line = if bonuses.is_empty() {
+        format!("Conversion Rate: {} followers/sec", format_rate(production_per_second, view.number_format))
+    } else {
+        format!("Conversion Rate: {} followers/sec  ({})", format_rate(production_per_second, view.number_format), bonuses.join(", "))
+    };
+    frame.print(0, 4, &rate_line);
+    if let Some(buff) = &view.production_buff {
+        frame.print_styled(rate_line.len() as u16 + 2, 4, paint(colored(format!(
+            "Frenzy x{} ({} left)", buff.multiplier, format_duration(buff.remaining.ceil() as u64)), view.theme.heading).bold()));
+    }
+    let power_line = format!("Influence Power: {}", view.click_power);
+    frame.print(0, 5, &power_line);
+    if let Some(buff) = &view.click_buff {
+        frame.print_styled(power_line.len() as u16 + 2, 5, paint(colored(format!(
+            "Clicking Frenzy x{} ({} left)", buff.multiplier, format_duration(buff.remaining.ceil() as u64)), view.theme.heading).bold()));
+    }
+    frame.print(0, 6, format!("Next Power ({}) at {} total converts",
+        if next_power > view.click_power { next_power.to_string() } else { "Max".to_string() },
+        next_milestone));
+    let eta = match next_tier_eta(view.lifetime_points.to_u64(), production_per_second + view.click_rate) {
+        Some(secs) => format_duration(secs),
+        None => "\u{2014}".to_string(),
+    };
+    frame.print(0, 7, format!("Domination Progress: {}  (next tier in {})", get_domination_status(view.lifetime_points.to_u64()), eta));
+    let playtime = format!("Time in Service: {}", format_duration(view.playtime_secs));
+    frame.print(0, 8, &playtime);
+    let mut x = playtime.len() as u16 + 2;
+    if view.paused {
+        let text = "Time stands still (paused)";
+        frame.print_styled(x, 8, paint(colored(text, view.theme.warning).bold()));
+        x += text.len() as u16 + 2;
+    }
+    if !view.sound {
+        frame.print_styled(x, 8, paint(colored("The bells are silent (muted)", view.theme.disabled)));
+    }
+    let gained = GameState::shards_from_lifetime(view.lifetime_points);
+    let next_shard = GameState::lifetime_for_shards(gained + 1);
+    if gained > 0 {
+        frame.print_styled(0, 9, paint(colored(format!(
+            "Ascend now for {} eldritch shards  (next shard at {} total converts)", gained, format_number(next_shard, view.number_format)), view.theme.accent)));
+    } else {
+        frame.print_styled(0, 9, paint(colored(format!(
+            "Not worth ascending yet  (first shard at {} total converts)", format_number(next_shard, view.number_format)), view.theme.disabled)));
+    }
     
-    Ok(())
+    frame.print_styled(0, 10, paint(colored("Rituals:", view.theme.heading)));
+    frame.print(0, 11, "Press '.' to spread influence and gain followers");
+    frame.print(0, 12, "Press '1'-'9' for Sanctum, Minions, Artifacts, Chronicle, Annals, Deeds, Relics, Achievements, Settings");
+    frame.print(0, 13, "Press 's' to record in the Necronomicon");
+    frame.print(0, 14, "Press 'p' to halt or resume the flow of time");
+    frame.print(0, 15, "Press 'n' to name this run, 'e' to export it as a string, 'i' to import one");
+    frame.print(0, 16, "Press Shift+A to ascend, '0' for rebirth, Shift+R to banish this dominion and start over");
+    frame.print(0, 17, "Press Ctrl+C to return to mortal realm, '?' for every key and how the cult grows");
+    if view.sandbox_unlocked {
+        frame.print(0, 18, "Press 'x' to toggle the sandbox (free purchases, marks this save)");
+    }
+    if let Some(summary) = &view.offline_summary {
+        frame.print_styled(0, 19, paint(colored(summary.clone(), view.theme.points)));
+    }
+    
+    if let Some(line) = ticker.line(view, width) {
+        frame.print_styled(0, height.saturating_sub(2), paint(colored(line, view.theme.ticker).italic()));
+    }
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("The Sanctum", view.theme.footer)));
 }
 
-fn draw_buildings_menu(stdout: &mut std::io::Stdout, state: &GameState) -> IoResult<()> {
-    let (_width, height) = terminal::size()?;
+fn draw_buildings_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
     
-    execute!(
-        stdout,
-        terminal::Clear(ClearType::All),
-        cursor::MoveTo(0, 0),
-        style::PrintStyledContent("Minions of Cthulhu".blue().bold()),
-        cursor::MoveTo(0, 1),
-        style::PrintStyledContent(format!("Followers: {}", state.points).green()),
-        cursor::MoveTo(0, 2),
-        style::Print(format!("Conversion Rate: {:.1} followers/sec", state.calculate_production_per_second()))
-    )?;
+    frame.print_styled(0, 0, paint(colored("Minions of Cthulhu", view.theme.title).bold()));
+    frame.print_styled(0, 1, paint(colored(format!("Followers: {}", format_number(view.points, view.number_format)), view.theme.points)));
+    let rate_line = format!("Conversion Rate: {} followers/sec", format_rate(view.production_per_second, view.number_format));
+    frame.print(0, 2, &rate_line);
+    let buying = format!("Buying x{}", view.buy_amount);
+    frame.print_styled(rate_line.len() as u16 + 2, 2, paint(colored(&buying, view.theme.heading)));
+    frame.print_styled((rate_line.len() + buying.len()) as u16 + 4, 2,
+        paint(colored(format!("Sorted by {}", view.building_sort.name()), view.theme.heading)));
+    if !view.reserve.is_zero() {
+        frame.print_styled(0, 3, paint(colored(format!("Reserve: {} followers are held back", format_number(view.reserve, view.number_format)), view.theme.disabled)));
+    }
     
-    // Sort buildings by cost
-    let mut buildings: Vec<(&String, &Building)> = state.buildings.iter().collect();
-    buildings.sort_by(|a, b| a.1.current_cost().cmp(&b.1.current_cost()));
+    let rows: Vec<[String; 4]> = view.buildings.iter()
+        .map(|building| [
+            format!("x{}", format_number(building.count, view.number_format)),
+            if view.buy_amount > 1 {
+                format!("Souls Required: {}  (x{}: {})", format_number(building.cost, view.number_format), view.buy_amount, format_number(building.bulk_cost, view.number_format))
+            } else {
+                format!("Souls Required: {}", format_number(building.cost, view.number_format))
+            },
+            match building.clicks_per_second {
+                Some(clicks) => format!("Clicks: {}/sec", format_rate(clicks, view.number_format)),
+                None => format!("Converts: {}/sec  {}", format_rate(building.production, view.number_format),
+                    format_multiplier(building.multiplier, view.number_format)),
+            },
+            match building.efficiency {
+                Some(efficiency) => format!("Efficiency: {}", format_rate(efficiency, view.number_format)),
+                None => "Efficiency: \u{2014}".to_string(),
+            },
+        ])
+        .collect();
     
-    for (i, (_key, building)) in buildings.iter().enumerate() {
-        let y_pos = i as u16 + 4;
-        let can_afford = state.points >= building.current_cost();
-        let is_selected = i == state.selected_index;
+    // The name column includes the two-character selection prefix and room
+    // for the advisor's marker after the name
+    let widths = [
+        layout::column_width(view.buildings.iter().map(|building| building.name.as_str())) + 4,
+        layout::column_width(rows.iter().map(|row| row[0].as_str())),
+        layout::column_width(rows.iter().map(|row| row[1].as_str())),
+        layout::column_width(rows.iter().map(|row| row[2].as_str())),
+        layout::column_width(rows.iter().map(|row| row[3].as_str())),
+    ];
+    let columns = layout::columns(&widths, width);
+    let row_height = if columns.is_some() { 1 } else { 2 };
+    let visible = buildings_visible(height, row_height).max(1);
+    let first = layout::scroll_offset(view.scroll_offset, view.selected_index, visible);
+    
+    for (i, (building, cells)) in view.buildings.iter().zip(&rows).enumerate().skip(first).take(visible) {
+        // Rows below the selected one make room for its progress bar
+        let y_pos = (i - first) as u16 * row_height + 4 + (i > view.selected_index) as u16;
+        let can_afford = building.affordable;
+        let is_selected = i == view.selected_index;
         
-        let prefix = if is_selected { "> " } else { "  " };
+        let prefix = row_prefix(is_selected, can_afford);
         let name_style = if is_selected {
-            building.name.clone().yellow().bold()
+            colored(building.name.clone(), view.theme.selected).bold()
         } else if can_afford {
-            building.name.clone().white()
+            colored(building.name.clone(), view.theme.tier(building.tier, view.buildings.len()))
         } else {
-            building.name.clone().dark_grey()
+            colored(building.name.clone(), view.theme.tier(building.tier, view.buildings.len())).dim()
         };
         
-        execute!(
-            stdout,
-            cursor::MoveTo(0, y_pos),
-            style::Print(prefix),
-            style::PrintStyledContent(name_style),
-            cursor::MoveTo(20, y_pos),
-            style::Print(format!("x{}", building.count)),
-            cursor::MoveTo(30, y_pos),
-            style::Print(format!("Souls Required: {}", building.current_cost())),
-            cursor::MoveTo(50, y_pos),
-            style::Print(format!("Converts: {:.1}/sec", building.total_production()))
-        )?;
+        frame.print(0, y_pos, prefix);
+        frame.print_styled(2, y_pos, paint(name_style));
+        if building.advised {
+            // Right after the name, in the gap before the next column
+            let marker = colored('\u{2605}', view.theme.heading);
+            let marker = if can_afford { marker.bold() } else { marker.dim() };
+            frame.print_styled(2 + building.name.chars().count() as u16 + 1, y_pos, paint(marker));
+        }
+        
+        match &columns {
+            Some(offsets) => {
+                for (cell, &x_pos) in cells.iter().zip(&offsets[1..]) {
+                    frame.print(x_pos, y_pos, cell);
+                }
+            },
+            None => frame.print(layout::STACK_INDENT, y_pos + 1, cells.join("  ")),
+        }
+        
+        if is_selected && building.max_affordable > 0 {
+            let note = format!("(max {})", building.max_affordable);
+            match &columns {
+                Some(offsets) => frame.print_styled(offsets[4] + widths[4] as u16 + 2, y_pos, paint(colored(note, view.theme.disabled))),
+                None => frame.print_styled(layout::STACK_INDENT + cells.join("  ").chars().count() as u16 + 2, y_pos + 1,
+                    paint(colored(note, view.theme.disabled))),
+            }
+        }
+        
+        if is_selected {
+            let bar_width = (width as usize).saturating_sub(layout::STACK_INDENT as usize + 2).min(BUILDING_BAR_WIDTH);
+            let bar_y = y_pos + row_height;
+            let bar_end = layout::STACK_INDENT + bar_width as u16 + 4;
+            if can_afford {
+                frame.print_styled(layout::STACK_INDENT, bar_y, paint(colored(layout::render_bar(1.0, bar_width), view.theme.affordable)));
+                frame.print(bar_end, bar_y, "Affordable now");
+            } else {
+                // Ratio of logarithms, so it holds for costs past f64
+                let budget = view.points - view.reserve;
+                let fraction = 10f64.powf(budget.log10() - building.cost.log10());
+                frame.print(layout::STACK_INDENT, bar_y, layout::render_bar(fraction, bar_width));
+                let eta = match eta_seconds(building.cost - budget, view.production_per_second) {
+                    Some(secs) => format!("Affordable in ~{}", format_duration(secs.ceil() as u64)),
+                    None => "Affordable in \u{2014}".to_string(),
+                };
+                frame.print(bar_end, bar_y, eta);
+            }
+        }
     }
     
-    execute!(
-        stdout,
-        cursor::MoveTo(0, height - 2),
-        style::Print("Use Up/Down to select, Enter to summon"),
-        cursor::MoveTo(0, height - 1),
-        style::PrintStyledContent("Minions Menu".cyan())
-    )?;
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to select, Enter or '1'-'9' to summon, Esc to leave, 'b' to summon 1/10/100 at once, 'm' to summon all you can afford, '-' to release one, 'o' to change the order, 'r' to hold back your current followers");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Minions Menu", view.theme.footer)));
+}
+
+fn draw_io_indicator(frame: &mut Frame, view: &ViewModel) {
+    if !view.io_in_progress {
+        return;
+    }
     
-    Ok(())
+    let (width, _height) = frame.size();
+    let spinner = ['|', '/', '-', '\\'];
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let glyph = spinner[(millis / 100 % spinner.len() as u128) as usize];
+    
+    frame.print_styled(width.saturating_sub(2), 0, paint(colored(glyph, view.theme.disabled)));
 }
 
-fn get_domination_status(lifetime_points: u64) -> String {
-    match lifetime_points {
-        0..=999 => "Local Cult (Town)".to_string(),
-        1000..=9999 => "Regional Influence (County)".to_string(),
-        10000..=99999 => "National Presence (Country)".to_string(),
-        100000..=999999 => "Continental Power (Continent)".to_string(),
-        1000000..=9999999 => "Global Reach (Earth)".to_string(),
-        10000000..=99999999 => "Cosmic Influence (Solar System)".to_string(),
-        100000000..=999999999 => "Galactic Dominion (Galaxy)".to_string(),
-        _ => "Universal Awakening (Cthulhu Rises!)".to_string(),
+fn draw_sandbox_marker(frame: &mut Frame, view: &ViewModel) {
+    if !view.sandbox {
+        return;
     }
+    
+    let (width, _height) = frame.size();
+    let marker = "[SANDBOX]";
+    
+    frame.print_styled(width.saturating_sub(marker.len() as u16 + 4), 0, paint(colored(marker, view.theme.accent).bold()));
 }
 
-fn draw_upgrades_menu(stdout: &mut std::io::Stdout, state: &GameState) -> IoResult<()> {
-    let (_width, height) = terminal::size()?;
+// Left of the sandbox marker, so both fit when both are on
+fn draw_paused_marker(frame: &mut Frame, view: &ViewModel) {
+    if !view.paused {
+        return;
+    }
     
-    execute!(
-        stdout,
-        terminal::Clear(ClearType::All),
-        cursor::MoveTo(0, 0),
-        style::PrintStyledContent("Eldritch Artifacts".blue().bold()),
-        cursor::MoveTo(0, 1),
-        style::PrintStyledContent(format!("Followers: {}", state.points).green())
-    )?;
+    let (width, _height) = frame.size();
+    let marker = "[PAUSED]";
+    let right = if view.sandbox { "[SANDBOX]".len() as u16 + 5 } else { 4 };
     
-    for (i, upgrade) in state.upgrades.iter().enumerate() {
-        // Use 3 lines per upgrade instead of 2 for better spacing
-        let y_pos = i as u16 * 3 + 3;
-        let can_afford = state.points >= upgrade.cost && !upgrade.purchased;
-        let is_selected = i == state.selected_index;
+    frame.print_styled(width.saturating_sub(marker.len() as u16 + right), 0, paint(colored(marker, view.theme.warning).bold()));
+}
+
+// Debug overlay, so a bug report can name the seed to replay with
+fn draw_seed(frame: &mut Frame, view: &ViewModel) {
+    if let Some(seed) = view.seed {
+        let (width, height) = frame.size();
+        let text = format!("seed {}", seed);
+        frame.print_styled(width.saturating_sub(text.len() as u16 + 1), height.saturating_sub(2), paint(colored(text, view.theme.disabled)));
+    }
+}
+
+// Kept off the header and footer rows
+fn draw_golden(frame: &mut Frame, view: &ViewModel) {
+    if let Some(golden) = view.golden {
+        let (width, height) = frame.size();
+        let glyph = "(@)";
+        let x_pos = (golden.x * width.saturating_sub(glyph.len() as u16) as f64) as u16;
+        let y_pos = 1 + (golden.y * height.saturating_sub(3) as f64) as u16;
+        frame.print_styled(x_pos, y_pos, paint(glyph.black().on_yellow().bold()));
+    }
+}
+
+fn draw_notification(frame: &mut Frame, view: &ViewModel) {
+    if let Some(text) = &view.notification {
+        // Right-aligned on the bottom row, clear of the menu name on the left
+        let (width, height) = frame.size();
+        let x_pos = width.saturating_sub(text.chars().count() as u16 + 1);
+        frame.print_styled(x_pos, height.saturating_sub(1), paint(view.theme.notice(text.clone())));
+    }
+}
+
+fn draw_confirmation(frame: &mut Frame, view: &ViewModel) {
+    if let Some(question) = view.confirmation {
+        // Across the middle of the screen, blanking whatever is drawn under it
+        let (width, height) = frame.size();
+        let text = format!(" {}  (y/n) ", question);
+        let y_pos = height / 2;
+        frame.print(0, y_pos, " ".repeat(width as usize));
+        let x_pos = width.saturating_sub(text.chars().count() as u16) / 2;
+        frame.print_styled(x_pos, y_pos, paint(colored(text, view.theme.warning).bold()));
+    }
+}
+
+// Long names are cut short so they don't run into the rest of the header
+fn display_profile_name(name: &str) -> String {
+    const SHOWN: usize = 24;
+    if name.chars().count() > SHOWN {
+        format!("{}...", name.chars().take(SHOWN - 3).collect::<String>())
+    } else {
+        name.to_string()
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn draw_upgrades_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
+    
+    frame.print_styled(0, 0, paint(colored("Eldritch Artifacts", view.theme.title).bold()));
+    frame.print_styled(0, 1, paint(colored(format!("Followers: {}", format_number(view.points, view.number_format)), view.theme.points)));
+    
+    let costs: Vec<String> = view.upgrades.iter()
+        .map(|upgrade| format!("Souls Required: {}", format_number(upgrade.cost, view.number_format)))
+        .collect();
+    let widths = [
+        layout::column_width(view.upgrades.iter().map(|upgrade| upgrade.name.as_str())) + 2,
+        layout::column_width(costs.iter().map(String::as_str)),
+        "[PURCHASED]".len(),
+    ];
+    let columns = layout::columns(&widths, width);
+    
+    // Scroll just far enough to keep the selected upgrade on screen
+    let lines = view.upgrade_lines;
+    let visible = upgrades_visible(height, lines).max(1);
+    let first = layout::scroll_offset(view.scroll_offset, view.selected_index, visible);
+    
+    for (i, (upgrade, cost)) in view.upgrades.iter().zip(&costs).enumerate().skip(first).take(visible) {
+        let y_pos = (i - first) as u16 * lines + 3;
+        let can_afford = upgrade.affordable;
+        let is_selected = i == view.selected_index;
         
-        let prefix = if is_selected { "> " } else { "  " };
+        let prefix = row_prefix(is_selected, can_afford);
         let name_style = if upgrade.purchased {
-            upgrade.name.clone().green()
+            colored(upgrade.name.clone(), view.theme.owned)
         } else if is_selected {
-            upgrade.name.clone().yellow().bold()
+            colored(upgrade.name.clone(), view.theme.selected).bold()
         } else if can_afford {
-            upgrade.name.clone().white()
+            colored(upgrade.name.clone(), view.theme.affordable)
         } else {
-            upgrade.name.clone().dark_grey()
+            colored(upgrade.name.clone(), view.theme.disabled)
         };
         
         let status = if upgrade.purchased { "[PURCHASED]" } else { "" };
         
-        execute!(
-            stdout,
-            cursor::MoveTo(0, y_pos),
-            style::Print(prefix),
-            style::PrintStyledContent(name_style),
-            cursor::MoveTo(40, y_pos),
-            style::Print(format!("Souls Required: {}", upgrade.cost)),
-            cursor::MoveTo(65, y_pos),
-            style::Print(status),
-            cursor::MoveTo(4, y_pos + 1),
-            style::Print(format!("{}", upgrade.description))
-        )?;
+        frame.print(0, y_pos, prefix);
+        frame.print_styled(2, y_pos, paint(name_style));
+        
+        match &columns {
+            Some(offsets) => {
+                frame.print(offsets[1], y_pos, cost);
+                frame.print(offsets[2], y_pos, status);
+                frame.print(layout::STACK_INDENT, y_pos + 1, &upgrade.description);
+            },
+            None => {
+                frame.print(layout::STACK_INDENT, y_pos + 1, format!("{}  {}  {}", cost, status, upgrade.description));
+            },
+        }
+        
+        if lines == 3 && !upgrade.purchased {
+            frame.print_styled(layout::STACK_INDENT, y_pos + 2, paint(colored(upgrade_outlook(upgrade, view), view.theme.disabled)));
+        }
     }
     
-    execute!(
-        stdout,
-        cursor::MoveTo(0, height - 2),
-        style::Print("Use Up/Down to select, Enter to acquire"),
-        cursor::MoveTo(0, height - 1),
-        style::PrintStyledContent("Artifacts Menu".cyan())
-    )?;
+    if let Some(index) = view.pending_upgrade {
+        frame.print_styled(0, height.saturating_sub(3), paint(colored(format!(
+            "{} dips below your reserve of {}. Press Enter again to acquire it anyway",
+            view.upgrades[index].name, format_number(view.reserve, view.number_format)), view.theme.warning)));
+    }
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to select, Enter to acquire, 'a' to acquire all you can afford, 'l' for more or fewer details");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Artifacts Menu", view.theme.footer)));
+}
+
+fn draw_prestige_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
     
-    Ok(())
+    frame.print_styled(0, 0, paint(colored("Eldritch Relics", view.theme.title).bold()));
+    frame.print_styled(0, 1, paint(colored(format!("Eldritch shards: {}", view.shards), view.theme.accent)));
+    
+    let costs: Vec<String> = view.prestige_upgrades.iter()
+        .map(|relic| format!("Shards Required: {}", relic.cost))
+        .collect();
+    let widths = [
+        layout::column_width(view.prestige_upgrades.iter().map(|relic| relic.name.as_str())) + 2,
+        layout::column_width(costs.iter().map(String::as_str)),
+        "[PURCHASED]".len(),
+    ];
+    let columns = layout::columns(&widths, width);
+    
+    // Scroll just far enough to keep the selected relic on screen
+    let visible = (height.saturating_sub(6) / 2).max(1) as usize;
+    let first = (view.selected_index + 1).saturating_sub(visible);
+    
+    for (i, (relic, cost)) in view.prestige_upgrades.iter().zip(&costs).enumerate().skip(first).take(visible) {
+        let y_pos = (i - first) as u16 * 2 + 3;
+        let is_selected = i == view.selected_index;
+        
+        let name_style = if relic.purchased {
+            colored(relic.name.clone(), view.theme.owned)
+        } else if is_selected {
+            colored(relic.name.clone(), view.theme.selected).bold()
+        } else if relic.affordable {
+            colored(relic.name.clone(), view.theme.affordable)
+        } else {
+            colored(relic.name.clone(), view.theme.disabled)
+        };
+        let status = if relic.purchased { "[PURCHASED]" } else { "" };
+        
+        frame.print(0, y_pos, row_prefix(is_selected, relic.affordable));
+        frame.print_styled(2, y_pos, paint(name_style));
+        match &columns {
+            Some(offsets) => {
+                frame.print(offsets[1], y_pos, cost);
+                frame.print(offsets[2], y_pos, status);
+                frame.print(layout::STACK_INDENT, y_pos + 1, &relic.description);
+            },
+            None => {
+                frame.print(layout::STACK_INDENT, y_pos + 1, format!("{}  {}  {}", cost, status, relic.description));
+            },
+        }
+    }
+    
+    frame.print(0, height.saturating_sub(3), "Relics are kept when you ascend, but spent shards no longer add to production");
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to select, Enter to acquire");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Relics Menu", view.theme.footer)));
+}
+
+fn draw_rebirth_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
+    let gained = rebirth::tokens_from_shards(view.shards);
+    
+    frame.print_styled(0, 0, paint(colored("Rebirth", view.theme.title).bold()));
+    frame.print_styled(0, 1, paint(colored(format!("Rebirth tokens: {}", view.rebirth_tokens), view.theme.accent)));
+    if gained > 0 {
+        frame.print_styled(20, 1, paint(colored(format!("Tokens gained by rebirth now: {}", gained), view.theme.points)));
+    } else {
+        frame.print_styled(20, 1, paint(colored(format!("Rebirth earns nothing until you hold {} eldritch shards", rebirth::SHARDS_PER_TOKEN), view.theme.disabled)));
+    }
+    
+    let costs: Vec<String> = view.skills.iter()
+        .map(|skill| format!("Tokens Required: {}", skill.cost))
+        .collect();
+    let widths = [
+        layout::column_width(view.skills.iter().map(|skill| skill.name.as_str())) + 2,
+        layout::column_width(costs.iter().map(String::as_str)),
+        "[PURCHASED]".len(),
+    ];
+    let columns = layout::columns(&widths, width);
+    
+    // Scroll just far enough to keep the selected node on screen
+    let visible = (height.saturating_sub(7) / 2).max(1) as usize;
+    let first = (view.selected_index + 1).saturating_sub(visible);
+    
+    for (i, (skill, cost)) in view.skills.iter().zip(&costs).enumerate().skip(first).take(visible) {
+        let y_pos = (i - first) as u16 * 2 + 3;
+        let is_selected = i == view.selected_index;
+        
+        let name_style = if skill.owned {
+            colored(skill.name.clone(), view.theme.owned)
+        } else if is_selected {
+            colored(skill.name.clone(), view.theme.selected).bold()
+        } else if skill.affordable {
+            colored(skill.name.clone(), view.theme.affordable)
+        } else {
+            colored(skill.name.clone(), view.theme.disabled)
+        };
+        let status = if skill.owned { "[PURCHASED]" } else { "" };
+        let detail = if skill.unlocked {
+            skill.description.clone()
+        } else {
+            format!("{}  (needs {})", skill.description, skill.requires.join(", "))
+        };
+        
+        frame.print(0, y_pos, row_prefix(is_selected, skill.affordable));
+        frame.print_styled(2, y_pos, paint(name_style));
+        match &columns {
+            Some(offsets) => {
+                frame.print(offsets[1], y_pos, cost);
+                frame.print(offsets[2], y_pos, status);
+                frame.print(layout::STACK_INDENT, y_pos + 1, &detail);
+            },
+            None => {
+                frame.print(layout::STACK_INDENT, y_pos + 1, format!("{}  {}  {}", cost, status, detail));
+            },
+        }
+    }
+    
+    frame.print(0, height.saturating_sub(4), "Rebirth trades your shards, relics and this run for tokens; skills and achievements are kept");
+    frame.print(0, height.saturating_sub(3), format!("Each token takes {} eldritch shards", rebirth::SHARDS_PER_TOKEN));
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to select, Enter to learn a skill, Shift+B to be reborn");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Rebirth Menu", view.theme.footer)));
 }
 
+fn draw_achievements_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
+    
+    let unlocked = view.achievements.iter().filter(|achievement| achievement.unlocked).count();
+    frame.print_styled(0, 0, paint(colored("Achievements", view.theme.title).bold()));
+    frame.print(0, 1, format!("{} of {} unlocked, +{:.0}% global production",
+        unlocked, view.achievements.len(), (view.achievement_multiplier - 1.0) * 100.0));
+    
+    // Scroll just far enough to keep the selected achievement on screen
+    let visible = achievements_visible(height).max(1);
+    let first = layout::scroll_offset(view.scroll_offset, view.selected_index, visible);
+    
+    for (i, achievement) in view.achievements.iter().enumerate().skip(first).take(visible) {
+        let y_pos = (i - first) as u16 * 2 + 3;
+        let is_selected = i == view.selected_index;
+        let (current, target) = achievement.progress;
+        
+        let name_style = if achievement.unlocked {
+            colored(achievement.name, view.theme.owned)
+        } else if is_selected {
+            colored(achievement.name, view.theme.selected).bold()
+        } else {
+            colored(achievement.name, view.theme.disabled)
+        };
+        frame.print(0, y_pos, row_prefix(is_selected, false));
+        frame.print_styled(2, y_pos, paint(name_style));
+        let mut status = if achievement.unlocked { "[UNLOCKED]".to_string() } else { format!("{}/{}", format_number(current, view.number_format), format_number(target, view.number_format)) };
+        if let Some(multiplier) = achievement.reward_multiplier {
+            status.push_str(&format!("  +{:.0}% global production", (multiplier - 1.0) * 100.0));
+        }
+        frame.print(achievement.name.chars().count() as u16 + 4, y_pos, status);
+        
+        // Goals with more than one step get a bar, scaled to the terminal
+        if !achievement.unlocked && target > 1 {
+            let bar_width = (width as usize).saturating_sub(layout::STACK_INDENT as usize + 2).min(30);
+            let bar = layout::render_bar(current as f64 / target as f64, bar_width);
+            frame.print(layout::STACK_INDENT, y_pos + 1, &bar);
+            frame.print(layout::STACK_INDENT + bar.chars().count() as u16 + 2, y_pos + 1, achievement.description);
+        } else {
+            frame.print(layout::STACK_INDENT, y_pos + 1, achievement.description);
+        }
+    }
+    
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to scroll");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Achievements Menu", view.theme.footer)));
+}
+
+// Return on investment and time until affordable, for the detail line
+fn upgrade_outlook(upgrade: &UpgradeView, view: &ViewModel) -> String {
+    let payoff = if upgrade.gain > 0.0 {
+        format!("+{:.1}/sec, pays for itself in {}", upgrade.gain, format_duration((upgrade.cost as f64 / upgrade.gain).ceil() as u64))
+    } else if upgrade.empowers_minions {
+        "No minions to empower yet".to_string()
+    } else {
+        "Strengthens your influence".to_string()
+    };
+    
+    let deficit = BigNum::from(upgrade.cost) - view.points;
+    let eta = match eta_seconds(deficit, view.production_per_second) {
+        _ if upgrade.affordable || deficit.is_zero() => "affordable now".to_string(),
+        Some(secs) => format!("affordable in {}", format_duration(secs.ceil() as u64)),
+        None => "affordable in \u{2014}".to_string(),
+    };
+    
+    format!("{}; {}", payoff, eta)
+}
+
+
+fn draw_stats_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
+    
+    frame.print_styled(0, 0, paint(colored("Chronicle of the Dominion", view.theme.title).bold()));
+    frame.print_styled(0, 1, paint(colored(format!("Followers: {}", format_number(view.points, view.number_format)), view.theme.points)));
+    frame.print(0, 3, format!("Total Converts: {}", format_number(view.lifetime_points, view.number_format)));
+    frame.print(2, 4, format!("Gathered by minions: {}", format_number(view.produced_points, view.number_format)));
+    frame.print(2, 5, format!("Gathered by influence: {}", format_number(view.lifetime_points - BigNum::from(view.produced_points), view.number_format)));
+    if view.sandbox_tainted {
+        frame.print_styled(0, 6, paint(colored("Sandbox was used; this record cannot be trusted", view.theme.accent)));
+    }
+    frame.print_styled(0, 7, paint(colored("Eras of Conversion:", view.theme.heading)));
+    
+    // Order purchased upgrades by when they were acquired
+    let mut eras: Vec<(&str, u64)> = view.upgrades.iter()
+        .filter_map(|upgrade| upgrade.produced_at_purchase.map(|snapshot| (upgrade.name.as_str(), snapshot)))
+        .collect();
+    eras.sort_by_key(|era| era.1);
+    
+    let mut rows: Vec<(String, String)> = Vec::new();
+    let mut previous: Option<(&str, u64)> = None;
+    for &(name, snapshot) in &eras {
+        let label = match previous {
+            Some((prev_name, _)) => format!("{} -> {}", prev_name, name),
+            None => format!("Before {}", name),
+        };
+        let delta = snapshot.saturating_sub(previous.map_or(0, |era| era.1));
+        rows.push((label, format_number(delta, view.number_format)));
+        previous = Some((name, snapshot));
+    }
+    
+    let (label, since) = match previous {
+        Some((name, snapshot)) => (format!("Since {}", name), snapshot),
+        None => ("No artifacts acquired yet".to_string(), 0),
+    };
+    rows.push((label, format_number(view.produced_points.saturating_sub(since), view.number_format)));
+    
+    let widths = [
+        layout::column_width(rows.iter().map(|row| row.0.as_str())) + 2,
+        layout::column_width(rows.iter().map(|row| row.1.as_str())),
+    ];
+    let columns = layout::columns(&widths, width);
+    
+    let mut y_pos = 8;
+    for (label, value) in &rows {
+        frame.print(2, y_pos, label);
+        match &columns {
+            Some(offsets) => frame.print(offsets[1], y_pos, value),
+            None => {
+                y_pos += 1;
+                frame.print(layout::STACK_INDENT, y_pos, value);
+            },
+        }
+        y_pos += 1;
+    }
+    
+    // Share of production per minion type, biggest first
+    y_pos += 1;
+    frame.print_styled(0, y_pos, paint(colored("Conversions by Minion:", view.theme.heading)));
+    let mut shares: Vec<(&str, f64)> = view.buildings.iter()
+        .filter(|building| building.effective_production > 0.0)
+        .map(|building| (building.name.as_str(), building.effective_production))
+        .collect();
+    shares.sort_by(|a, b| b.1.total_cmp(&a.1));
+    if shares.is_empty() {
+        y_pos += 1;
+        frame.print_styled(2, y_pos, paint(colored("No minions yet; summon some in the Minions menu", view.theme.disabled)));
+    }
+    let name_width = layout::column_width(shares.iter().map(|share| share.0));
+    // Leave room for the indent, the names and the percentage
+    let bar_width = (width as usize).saturating_sub(name_width + 12);
+    for (name, production) in &shares {
+        y_pos += 1;
+        let fraction = production / view.production_per_second;
+        let bar = "#".repeat((fraction * bar_width as f64).round() as usize);
+        frame.print(2, y_pos, format!("{:<width$}  ", name, width = name_width));
+        frame.print_styled(name_width as u16 + 4, y_pos, paint(colored(bar.clone(), view.theme.accent)));
+        frame.print(name_width as u16 + 5 + bar.len() as u16, y_pos, format!("{:.0}%", fraction * 100.0));
+    }
+    
+    // Timeline of tiers, with the playtime each one took to reach
+    y_pos += 1;
+    frame.print_styled(0, y_pos, paint(colored("Milestones:", view.theme.heading)));
+    if view.milestones.is_empty() {
+        frame.print_styled(2, y_pos + 1, paint(colored("No new tier reached yet", view.theme.disabled)));
+    }
+    let mut previous_playtime = 0;
+    for milestone in &view.milestones {
+        y_pos += 1;
+        frame.print(2, y_pos, format!(
+            "{}  reached at {} (+{})",
+            milestone.tier,
+            format_duration(milestone.playtime_secs),
+            format_duration(milestone.playtime_secs.saturating_sub(previous_playtime))));
+        previous_playtime = milestone.playtime_secs;
+    }
+    
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Chronicle Menu", view.theme.footer)));
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn draw_settings_menu(frame: &mut Frame, view: &ViewModel) {
+    let (_width, height) = frame.size();
+    
+    frame.print_styled(0, 0, paint(colored("Rites of Perception", view.theme.title).bold()));
+    
+    let example = format_number(1_234_567_890, view.number_format);
+    let autosave = match (view.autosave, view.autosave_secs) {
+        (_, 0) => "Off (disabled by --autosave-secs 0)".to_string(),
+        (true, secs) => format!("On (every {})", format_duration(secs)),
+        (false, _) => "Off".to_string(),
+    };
+    let rows = [
+        format!("Number format: {}  (e.g. {})", view.number_format.name(), example),
+        format!("Color theme: {}", view.theme_preset.name()),
+        format!("Autosave: {}", autosave),
+        format!("Tick rate: {} ms  ({} per second)", view.tick_ms, 1000 / view.tick_ms),
+        format!("Mouse: {}", if view.mouse { "On (click to spread influence or buy a row)" } else { "Off" }),
+        format!("Clicks while paused: {}", if view.paused_clicks { "Allowed" } else { "Blocked" }),
+        format!("Sound: {}", if view.sound { "On (a bell for golden followers, tier-ups and achievements)" } else { "Off" }),
+        match &view.prompt {
+            Some(Prompt { kind: PromptKind::Reserve, text }) => format!("Reserve: {}_  (Enter to set, Esc to cancel)", text),
+            _ if view.reserve.is_zero() => "Reserve: None  (Enter to type an amount)".to_string(),
+            _ => format!("Reserve: {} followers  (Enter to type an amount)", format_number(view.reserve, view.number_format)),
+        },
+        format!("Achievement bonus: {}", if view.achievement_bonus {
+            format!("On (+{:.0}% production per achievement)", ACHIEVEMENT_BONUS * 100.0)
+        } else {
+            "Off".to_string()
+        }),
+    ];
+    for (i, row) in rows.iter().enumerate() {
+        let y_pos = i as u16 + 2;
+        let is_selected = i == view.selected_index;
+        frame.print(0, y_pos, row_prefix(is_selected, false));
+        if is_selected {
+            frame.print_styled(2, y_pos, paint(colored(row.clone(), view.theme.selected).bold()));
+        } else {
+            frame.print(2, y_pos, row);
+        }
+    }
+    
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to select, Enter or Left/Right to change");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Settings Menu", view.theme.footer)));
+}
+
+fn draw_help_screen(frame: &mut Frame, view: &ViewModel) {
+    let (_width, height) = frame.size();
+    
+    frame.print_styled(0, 0, paint(colored("Forbidden Knowledge", view.theme.title).bold()));
+    
+    let key_width = layout::column_width(help::SECTIONS.iter()
+        .flat_map(|section| section.entries.iter().map(|(key, _)| *key)));
+    let mut lines = Vec::with_capacity(help_line_count());
+    for section in help::SECTIONS {
+        lines.push(colored(section.title.to_string(), view.theme.heading));
+        for (key, text) in section.entries {
+            lines.push(colored(format!("  {:<width$}  {}", key, text, width = key_width), None));
+        }
+        lines.push(colored(String::new(), None));
+    }
+    
+    // The selection is the first line shown, moved by Up/Down and PgUp/PgDn
+    let visible = help_visible(height).max(1);
+    let first = view.selected_index.min(lines.len().saturating_sub(visible));
+    for (i, line) in lines.into_iter().skip(first).take(visible).enumerate() {
+        frame.print_styled(0, i as u16 + 2, paint(line));
+    }
+    
+    frame.print(0, height.saturating_sub(2), "Use Up/Down or PgUp/PgDn to scroll, '1'-'9' to leave");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Help", view.theme.footer)));
+}
+
+fn draw_export_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
+    
+    frame.print_styled(0, 0, paint(colored("Sealed Scroll", view.theme.title).bold()));
+    frame.print(0, 1, "Copy this string and press 'i' in the Sanctum of another game to import it:");
+    
+    // Wrapped to the terminal width; whitespace is ignored when importing
+    let text: Vec<char> = view.export_text.as_deref().unwrap_or_default().chars().collect();
+    let available = height.saturating_sub(6) as usize;
+    for (i, line) in text.chunks(width.max(1) as usize).take(available).enumerate() {
+        frame.print(0, i as u16 + 3, line.iter().collect::<String>());
+    }
+    if text.len() > available * width as usize {
+        frame.print_styled(0, height.saturating_sub(3), paint(colored("The terminal is too small to show the whole string", view.theme.warning)));
+    }
+    
+    frame.print(0, height.saturating_sub(2), "Press Enter or '1' to return to the Sanctum");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Export Menu", view.theme.footer)));
+}
+
+fn draw_history_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
+    
+    frame.print_styled(0, 0, paint(colored("Annals of Acquisition", view.theme.title).bold()));
+    frame.print(0, 1, format!("{} purchases recorded", view.purchase_history.len()));
+    
+    if view.purchase_history.is_empty() {
+        frame.print_styled(0, 3, paint(colored("Nothing has been acquired yet", view.theme.disabled)));
+    }
+    
+    // Newest first, scrolled so the selected entry is at the top
+    let now = unix_now();
+    let rows: Vec<[String; 4]> = view.purchase_history.iter().rev()
+        .map(|record| [
+            format_age(now.saturating_sub(record.timestamp)),
+            record.item.clone(),
+            format!("x{}", record.count_after),
+            format!("Souls Spent: {}", format_number(record.cost, view.number_format)),
+        ])
+        .collect();
+    
+    let widths = [
+        layout::column_width(rows.iter().map(|row| row[0].as_str())),
+        layout::column_width(rows.iter().map(|row| row[1].as_str())),
+        layout::column_width(rows.iter().map(|row| row[2].as_str())),
+        layout::column_width(rows.iter().map(|row| row[3].as_str())),
+    ];
+    let columns = layout::columns(&widths, width);
+    let row_height = if columns.is_some() { 1 } else { 2 };
+    let visible = (height.saturating_sub(6) / row_height) as usize;
+    
+    for (i, cells) in rows.iter().skip(view.selected_index).take(visible).enumerate() {
+        let y_pos = i as u16 * row_height + 3;
+        match &columns {
+            Some(offsets) => {
+                for (cell, &x_pos) in cells.iter().zip(offsets) {
+                    frame.print(x_pos, y_pos, cell);
+                }
+            },
+            None => {
+                frame.print(0, y_pos, &cells[1]);
+                frame.print(layout::STACK_INDENT, y_pos + 1, format!("{}  {}  {}", cells[0], cells[2], cells[3]));
+            },
+        }
+    }
+    
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to scroll, 'c' to clear the annals");
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Annals Menu", view.theme.footer)));
+}
+
+fn draw_deeds_menu(frame: &mut Frame, view: &ViewModel) {
+    let (width, height) = frame.size();
+    
+    frame.print_styled(0, 0, paint(colored("Deeds of the Cult", view.theme.title).bold()));
+    frame.print_styled(0, 1, paint(colored(format!("Followers: {}", format_number(view.points, view.number_format)), view.theme.points)));
+    
+    let rows = [
+        ("Times you spread influence", view.stats.total_clicks.to_string()),
+        ("Minions summoned", view.stats.buildings_bought.to_string()),
+        ("Artifacts acquired", view.stats.upgrades_bought.to_string()),
+        ("Souls spent", format_number(view.stats.souls_spent, view.number_format)),
+    ];
+    let widths = [
+        layout::column_width(rows.iter().map(|row| row.0)) + 2,
+        layout::column_width(rows.iter().map(|row| row.1.as_str())),
+    ];
+    let columns = layout::columns(&widths, width);
+    
+    let mut y_pos = 3;
+    for (label, value) in rows {
+        frame.print(0, y_pos, label);
+        match &columns {
+            Some(offsets) => frame.print(offsets[1], y_pos, value),
+            None => {
+                y_pos += 1;
+                frame.print(layout::STACK_INDENT, y_pos, value);
+            },
+        }
+        y_pos += 1;
+    }
+    
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Deeds Menu", view.theme.footer)));
+}
+
+fn draw_ascension_screen(frame: &mut Frame, view: &ViewModel) {
+    let (_width, height) = frame.size();
+    let gained = GameState::shards_from_lifetime(view.lifetime_points);
+    
+    frame.print_styled(0, 0, paint(colored("Ascension", view.theme.title).bold()));
+    frame.print(0, 2, "Ascending gives up your followers, converts, minions and artifacts.");
+    frame.print(0, 3, "Your eldritch shards, relics, playtime, deeds and annals are kept.");
+    frame.print(0, 5, format!("Eldritch shards: {} (+{:.0}% production)", view.shards, view.shards as f64 * SHARD_BONUS * 100.0));
+    if gained > 0 {
+        frame.print_styled(0, 6, paint(colored(format!("Shards gained by ascending now: {}", gained), view.theme.points)));
+        frame.print(0, height.saturating_sub(2), "Press Enter to ascend, '1' to return to the Sanctum");
+    } else {
+        frame.print_styled(0, 6, paint(colored(format!("Ascending earns nothing until you reach {} total converts", format_number(SHARD_DIVISOR as u64, view.number_format)), view.theme.disabled)));
+        frame.print(0, height.saturating_sub(2), "Press '1' to return to the Sanctum");
+    }
+    
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Ascension Menu", view.theme.footer)));
+}
+
+fn draw_victory_screen(frame: &mut Frame, view: &ViewModel) {
+    let (_width, height) = frame.size();
+    
+    // Cycle the banner through ominous colors
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let color = view.theme.banner((millis / 250) as usize);
+    
+    let minions: u64 = view.buildings.iter().map(|building| building.count).sum();
+    let artifacts = view.upgrades.iter().filter(|upgrade| upgrade.purchased).count();
+    
+    frame.print_styled(0, 1, paint(colored("~~~ Ph'nglui mglw'nafh Cthulhu R'lyeh wgah'nagl fhtagn ~~~", color).bold()));
+    frame.print_styled(8, 3, paint(colored("THE STARS ARE RIGHT. CTHULHU RISES!", color).bold()));
+    
+    frame.print_styled(0, 6, paint(colored("Your dominion is complete:", view.theme.heading)));
+    frame.print(2, 7, format!("Total Converts: {}", format_number(view.lifetime_points, view.number_format)));
+    frame.print(2, 8, format!("Gathered by minions: {}", format_number(view.produced_points, view.number_format)));
+    frame.print(2, 9, format!("Minions summoned: {}", format_number(minions, view.number_format)));
+    frame.print(2, 10, format!("Artifacts acquired: {} of {}", artifacts, view.upgrades.len()));
+    frame.print(2, 11, format!("Influence Power: {}", view.click_power));
+    
+    let choices = ["Keep playing among the ruins", "Ascend, trading this run for eldritch shards", "Start over from nothing"];
+    for (i, choice) in choices.iter().enumerate() {
+        let y_pos = i as u16 + 13;
+        let is_selected = i == view.selected_index;
+        frame.print(0, y_pos, row_prefix(is_selected, false));
+        if is_selected {
+            frame.print_styled(2, y_pos, paint(colored(*choice, view.theme.selected).bold()));
+        } else {
+            frame.print(2, y_pos, choice);
+        }
+    }
+    frame.print(0, height.saturating_sub(2), "Use Up/Down to choose, Enter to confirm");
+    
+    frame.print_styled(0, height.saturating_sub(1), paint(colored("Victory", view.theme.footer)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn parse(args: &[&str]) -> Result<Options, clap::Error> {
+        Options::try_parse_from(std::iter::once("clickercurse-game").chain(args.iter().copied()))
+    }
+
+    // Draws one menu of `state` on an 80x24 screen
+    fn draw(state: &GameState, draw_menu: fn(&mut Frame, &ViewModel)) -> Frame {
+        let mut frame = Frame::new(80, 24);
+        draw_menu(&mut frame, &ViewModel::capture(state));
+        frame
+    }
+
+    // The only test reading CLICKERCURSE_SAVE_DIR, which is process-wide
+    #[test]
+    fn save_dir_comes_from_the_flag_then_the_environment() {
+        std::env::remove_var("CLICKERCURSE_SAVE_DIR");
+        assert_eq!(parse(&[]).unwrap().save_dir, PathBuf::from(slots::DEFAULT_SAVE_DIR));
+
+        std::env::set_var("CLICKERCURSE_SAVE_DIR", "/var/lib/cult");
+        assert_eq!(parse(&[]).unwrap().save_dir, PathBuf::from("/var/lib/cult"));
+        assert_eq!(parse(&["--save-dir", "elsewhere"]).unwrap().save_dir, PathBuf::from("elsewhere"));
+        std::env::remove_var("CLICKERCURSE_SAVE_DIR");
+    }
+
+    #[test]
+    fn durations_roll_over_at_minutes_and_hours() {
+        assert_eq!(format_duration(0), "00:00:00");
+        assert_eq!(format_duration(59), "00:00:59");
+        assert_eq!(format_duration(60), "00:01:00");
+        assert_eq!(format_duration(3_599), "00:59:59");
+        assert_eq!(format_duration(3_600), "01:00:00");
+        assert_eq!(format_duration(100 * 3_600 + 61), "100:01:01");
+    }
+
+    #[test]
+    fn autosave_interval_defaults_and_can_be_turned_off() {
+        assert_eq!(parse(&[]).unwrap().autosave_secs, DEFAULT_AUTOSAVE_SECS);
+        assert_eq!(parse(&["--autosave-secs", "0"]).unwrap().autosave_secs, 0);
+        assert_eq!(parse(&["--autosave-secs", "120"]).unwrap().autosave_secs, 120);
+        assert!(parse(&["--autosave-secs", "-1"]).is_err());
+        assert!(parse(&["--autosave-secs", "soon"]).is_err());
+    }
+
+    #[test]
+    fn the_sanctum_shows_what_ascending_would_bring() {
+        let draw_sanctum = |frame: &mut Frame, view: &ViewModel| draw_main_menu(frame, view, &Ticker::new());
+        let mut state = GameState::new();
+        assert_eq!(draw(&state, draw_sanctum).line(9), "Not worth ascending yet  (first shard at 1.00T total converts)");
+
+        state.lifetime_points = BigNum::from(8_000_000_000_000u64);
+        assert_eq!(draw(&state, draw_sanctum).line(9), "Ascend now for 2 eldritch shards  (next shard at 27.00T total converts)");
+    }
+
+    #[test]
+    fn the_sanctum_shows_the_achievement_multiplier() {
+        let sanctum = |frame: &mut Frame, view: &ViewModel| draw_main_menu(frame, view, &Ticker::new());
+        let mut state = GameState::new();
+        assert_eq!(draw(&state, sanctum).line(4), "Conversion Rate: 0.0 followers/sec");
+
+        state.buildings[0].count = 100;
+        state.apply_action(&Action::Click);
+        assert_eq!(draw(&state, sanctum).line(4), "Conversion Rate: 10.2 followers/sec  (x1.02 from achievements)");
+
+        // The achievement bonus is the last setting
+        state.apply_action(&Action::OpenMenu(Menu::Settings));
+        state.apply_action(&Action::SelectLast);
+        state.apply_action(&Action::ConfirmSelection);
+        assert_eq!(draw(&state, sanctum).line(4), "Conversion Rate: 10.4 followers/sec  (x1.04 from achievements)");
+    }
+
+    #[test]
+    fn achievement_rewards_are_labelled() {
+        let mut state = GameState::new();
+        state.current_menu = Menu::Achievements;
+        let frame = draw(&state, draw_achievements_menu);
+        assert_eq!(frame.line(1), "0 of 9 unlocked, +0% global production");
+        assert_eq!(frame.line(5), "  Tireless Preacher  0/1000  +2% global production");
+
+        state.buildings[0].count = 100;
+        state.apply_action(&Action::Click);
+        let frame = draw(&state, draw_achievements_menu);
+        assert_eq!(frame.line(1), "2 of 9 unlocked, +2% global production");
+        assert_eq!(frame.line(7), "  A Hundred Whisperers  [UNLOCKED]  +2% global production");
+    }
+
+    #[test]
+    fn the_sanctum_shows_active_frenzies() {
+        let mut state = GameState::new();
+        // Rolls the spawn delay, the position, then 0.073 for a clicking frenzy
+        state.rng = Rng::new(3);
+        let sanctum = |frame: &mut Frame, view: &ViewModel| draw_main_menu(frame, view, &Ticker::new());
+        assert_eq!(draw(&state, sanctum).line(5), "Influence Power: 1");
+
+        state.advance_golden(0.0);
+        state.advance_golden(200.0);
+        state.apply_action(&Action::CatchGolden);
+        state.advance_buffs(0.5);
+        assert_eq!(draw(&state, sanctum).line(5), "Influence Power: 1  Clicking Frenzy x777 (00:00:13 left)");
+    }
+
+    #[test]
+    fn the_selected_minion_shows_progress_towards_its_price() {
+        let mut state = GameState::new();
+        state.current_menu = Menu::Buildings;
+        state.points = BigNum::from(5u64);
+        assert_eq!(draw(&state, draw_buildings_menu).line(6), "    [######--------------]  Affordable in \u{2014}");
+
+        state.points = BigNum::from(20u64);
+        assert_eq!(draw(&state, draw_buildings_menu).line(6), "    [####################]  Affordable now");
+    }
+
+    #[test]
+    fn the_selected_minion_shows_when_it_is_affordable() {
+        let mut state = GameState::new();
+        state.current_menu = Menu::Buildings;
+        state.points = BigNum::from(5u64);
+        // One Elder One converts a follower a second, so ten more take 10s
+        state.buildings[1].count = 1;
+        assert_eq!(draw(&state, draw_buildings_menu).line(6), "    [######--------------]  Affordable in ~00:00:10");
+    }
+
+    #[test]
+    fn every_menu_draws_on_tiny_terminals() {
+        let mut state = GameState::new();
+        state.lifetime_points = BigNum::pow(10.0, 14.0);
+        state.apply_action(&Action::Click);
+        let menus = [
+            Menu::Main, Menu::Buildings, Menu::Upgrades, Menu::Stats, Menu::History, Menu::Deeds, Menu::Ascension,
+            Menu::Relics, Menu::Rebirth, Menu::Achievements, Menu::Settings, Menu::Help, Menu::Export, Menu::Victory,
+        ];
+        for menu in menus {
+            state.current_menu = menu.clone();
+            let view = ViewModel::capture(&state);
+            let mut screen = Screen::new();
+            for (width, height) in [(0, 0), (1, 1), (10, 2), (3, 10), (59, 5), (60, 6), (80, 24)] {
+                draw_frame(&mut screen, &mut Vec::new(), &view, &Ticker::new(), width, height).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn confirmations_take_only_an_answer() {
+        let key = |code| confirm_action_for_key(&KeyEvent::new(code, KeyModifiers::NONE));
+        assert!(matches!(key(KeyCode::Char('y')), Some(Action::Answer(true))));
+        assert!(matches!(key(KeyCode::Char('N')), Some(Action::Answer(false))));
+        assert!(matches!(key(KeyCode::Esc), Some(Action::Answer(false))));
+        assert!(key(KeyCode::Enter).is_none());
+        assert!(key(KeyCode::Char(' ')).is_none());
+
+        let mut state = GameState::new();
+        state.apply_action(&Action::StartReset);
+        let line = draw(&state, draw_confirmation).line(12);
+        assert_eq!(line.trim_start(), "Banish this dominion? Its save and backups are deleted.  (y/n)");
+    }
+
+    #[test]
+    fn notifications_sit_at_the_bottom_right() {
+        let mut state = GameState::new();
+        state.notify("Your dominion is saved".to_string());
+        state.advance_notifications();
+        assert_eq!(draw(&state, draw_notification).line(23), format!("{}Your dominion is saved", " ".repeat(57)));
+    }
+
+    #[test]
+    fn minions_show_their_multiplier() {
+        let mut state = GameState::new();
+        state.buildings[0].count = 10;
+        state.points = BigNum::from(100u64);
+        state.apply_action(&Action::OpenMenu(Menu::Upgrades));
+        state.apply_action(&Action::ConfirmSelection);
+        // The base rate, with the multiplier beside it
+        assert_eq!(draw(&state, draw_buildings_menu).line(5), "    x10  Souls Required: 60  Converts: 1.0/sec  x2  Efficiency: 300.0");
+    }
+
+    #[test]
+    fn the_best_buy_is_starred() {
+        let mut state = GameState::new();
+        state.points = BigNum::from(5u64);
+        let frame = draw(&state, draw_buildings_menu);
+        assert_eq!((frame.line(4).as_str(), frame.line(7).as_str()), ("> Cultist", "  Elder One \u{2605}"));
+
+        state.points = BigNum::from(20u64);
+        let frame = draw(&state, draw_buildings_menu);
+        assert_eq!((frame.line(4).as_str(), frame.line(7).as_str()), ("> Cultist \u{2605}", "  Elder One"));
+    }
+
+    #[test]
+    fn a_remapped_click_key_clicks() {
+        let bindings = KeyBindings::parse("click = \"c\"").unwrap();
+        let mut state = GameState::new();
+        let action = action_for_key(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE), &bindings).unwrap();
+        state.apply_action(&action);
+        assert_eq!(state.points, BigNum::from(1));
+        assert!(action_for_key(&KeyEvent::new(KeyCode::Char('.'), KeyModifiers::NONE), &bindings).is_none());
+    }
+
+    #[test]
+    fn vim_keys_move_within_the_list() {
+        let bindings = KeyBindings::default();
+        let mut state = GameState::new();
+        let press = |state: &mut GameState, keys: &str| {
+            for ch in keys.chars() {
+                let event = KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE);
+                let action = match state.current_menu {
+                    Menu::Buildings => buildings_action_for_key(&event, &bindings),
+                    _ => action_for_key(&event, &bindings),
+                };
+                state.apply_action(&action.unwrap());
+            }
+            ViewModel::capture(state).selected_index
+        };
+
+        state.apply_action(&Action::OpenMenu(Menu::Buildings));
+        assert_eq!(press(&mut state, "k"), 0);
+        assert_eq!(press(&mut state, "jjj"), 3);
+        assert_eq!(press(&mut state, "k"), 2);
+        assert_eq!(press(&mut state, "G"), 6);
+        assert_eq!(press(&mut state, "j"), 6);
+        assert_eq!(press(&mut state, "g"), 0);
+
+        // The Rites of Perception list is two rows longer
+        state.apply_action(&Action::OpenMenu(Menu::Settings));
+        assert_eq!(press(&mut state, "G"), 8);
+        assert_eq!(press(&mut state, "kkgj"), 1);
+    }
+
+    #[test]
+    fn separate_taps_always_click() {
+        let mut limiter = ClickLimiter::default();
+        let start = Instant::now();
+        let allowed = (0..10)
+            .filter(|i| limiter.allow(start + Duration::from_millis(i * 60), false))
+            .count();
+        assert_eq!(allowed, 10);
+    }
+
+    #[test]
+    fn held_keys_click_at_the_capped_rate() {
+        let mut limiter = ClickLimiter::default();
+        let start = Instant::now();
+        // Key repeats every 10ms for two seconds
+        let allowed = (0..200)
+            .filter(|i| limiter.allow(start + Duration::from_millis(i * 10), *i > 0))
+            .count();
+        assert_eq!(allowed, 2 * HELD_CLICKS_PER_SEC as usize);
+
+        // Without the repeat flag, presses closer than REPEAT_GAP count as held
+        let mut limiter = ClickLimiter::default();
+        let gap = REPEAT_GAP / 2;
+        let allowed = (0..200).filter(|i| limiter.allow(start + gap * *i, false)).count();
+        let secs = (gap * 200).as_secs_f64();
+        assert!(allowed as f64 <= secs * HELD_CLICKS_PER_SEC + 1.0, "{} clicks in {}s", allowed, secs);
+        assert!(allowed > 1);
+
+        // Letting go and tapping again clicks straight away
+        assert!(limiter.allow(start + gap * 200 + REPEAT_GAP, false));
+    }
+
+    #[test]
+    fn nothing_is_credited_while_paused() {
+        let mut state = GameState::new();
+        state.buildings[1].count = 10;
+        state.buildings[2].count = 10;
+        state.apply_action(&Action::TogglePause);
+        for _ in 0..100 {
+            advance(&mut state, 0.1, 1.0);
+        }
+        assert!(state.points.is_zero());
+        assert!(draw(&state, draw_paused_marker).line(0).ends_with("[PAUSED]"));
+
+        // Unpausing starts from now, with no backlog for the paused time
+        state.apply_action(&Action::TogglePause);
+        advance(&mut state, 1.0, 1.0);
+        assert_eq!(state.points, BigNum::from(12u64));
+        assert_eq!(draw(&state, draw_paused_marker).line(0), "");
+    }
+
+    #[test]
+    fn subcommands_and_their_flags() {
+        assert!(parse(&[]).unwrap().command.is_none());
+        assert!(matches!(parse(&["run"]).unwrap().command, Some(Command::Run)));
+        assert!(matches!(parse(&["reset", "--yes"]).unwrap().command, Some(Command::Reset { yes: true })));
+        assert!(matches!(parse(&["reset"]).unwrap().command, Some(Command::Reset { yes: false })));
+
+        let options = parse(&["simulate", "--seconds", "600", "--strategy", "buy-best-efficiency", "--seed", "42"]).unwrap();
+        assert_eq!(options.seed, Some(42));
+        match options.command {
+            Some(Command::Simulate { script, seconds, strategy }) => {
+                assert_eq!((script, seconds, strategy), (None, Some(600), Some(Strategy::BuyBestEfficiency)));
+            },
+            other => panic!("expected simulate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn global_flags_go_before_or_after_the_subcommand() {
+        let options = parse(&["--slot", "second", "simulate", "opening.txt", "--offline-cap", "2.5", "--unfocused-rate", "0.25"]).unwrap();
+        assert_eq!(options.slot.as_deref(), Some("second"));
+        assert_eq!(options.offline_cap_hours, Some(2.5));
+        assert_eq!(options.unfocused_rate, Some(0.25));
+        let options = parse(&["run", "--start-paused", "--muted"]).unwrap();
+        assert!(options.start_paused && options.muted);
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        use clap::error::ErrorKind;
+        let kind = |args: &[&str]| parse(args).unwrap_err().kind();
+        assert_eq!(kind(&["--frobnicate"]), ErrorKind::UnknownArgument);
+        assert_eq!(kind(&["summon"]), ErrorKind::InvalidSubcommand);
+        assert_eq!(kind(&["--seed", "dagon"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--slot", "../etc"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--unfocused-rate", "1.5"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--offline-cap=-1"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["simulate"]), ErrorKind::MissingRequiredArgument);
+        assert_eq!(kind(&["simulate", "--seconds", "5", "--strategy", "buy-everything"]), ErrorKind::InvalidValue);
+        assert_eq!(kind(&["--headless"]), ErrorKind::MissingRequiredArgument);
+    }
+
+    // What a headless run ends with, for comparing two of them
+    fn simulated(seed: u64, strategy: Strategy) -> (String, String, Vec<u64>, usize) {
+        let mut state = GameState::new();
+        state.rng = Rng::new(seed);
+        let script = replay::parse_script(&"0 click\n".repeat(30)).unwrap();
+        let (clock, purchases) = fast_forward(&mut state, script, 900, Some(strategy));
+        assert_eq!(clock, 900.0);
+        let counts = state.buildings.iter().map(|building| building.count).collect();
+        (state.points.to_string(), state.lifetime_points.to_string(), counts, purchases)
+    }
+
+    #[test]
+    fn seeded_simulations_are_repeatable() {
+        for strategy in [Strategy::BuyCheapest, Strategy::BuyBestEfficiency] {
+            let first = simulated(42, strategy);
+            assert_eq!(simulated(42, strategy), first);
+            assert!(first.3 > 0, "{:?} bought nothing", strategy);
+        }
+        assert_ne!(simulated(42, Strategy::BuyCheapest).2, simulated(42, Strategy::BuyBestEfficiency).2);
+    }
+
+    #[test]
+    fn a_quitting_script_ends_the_simulation() {
+        let mut state = GameState::new();
+        let script = replay::parse_script("0 click\n5 quit\n6 click").unwrap();
+        assert_eq!(fast_forward(&mut state, script, 100, Some(Strategy::BuyCheapest)), (5.0, 0));
+        assert_eq!(state.points, BigNum::from(1));
+    }
+
+    #[test]
+    fn export_stats_writes_the_slot_save() {
+        let dir = TempDir::new("export");
+        let mut state = GameState::new();
+        state.points = BigNum::from(1500);
+        state.buildings[0].count = 10;
+        state.save_game(&dir, "ritual").unwrap();
+
+        let out = dir.join("run.csv");
+        let options = parse(&["--save-dir", dir.to_str().unwrap(), "--slot", "ritual"]).unwrap();
+        export_stats(&options, ExportFormat::Csv, Some(&out)).unwrap();
+        let csv = fs::read_to_string(&out).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], clickercurse_game::export::CSV_HEADER);
+        assert_eq!(rows[1], "run,points,Followers,1500,");
+        assert!(rows.contains(&"building,cursor,Cultist,10,1"), "{}", csv);
+    }
+
+    #[test]
+    fn dump_prints_the_slot_save_as_json() {
+        let dir = TempDir::new("dump");
+        let options = parse(&["--save-dir", dir.to_str().unwrap(), "--slot", "ritual", "dump"]).unwrap();
+        let dump = |options: &Options| {
+            let mut out = Vec::new();
+            dump_state(options, &mut out).unwrap();
+            serde_json::from_slice::<GameState>(&out).unwrap()
+        };
+
+        // Nothing saved yet gives a new game
+        let dumped = dump(&options);
+        assert!(dumped.points.is_zero());
+        assert!(dumped.buildings.iter().all(|building| building.count == 0));
+
+        let mut state = GameState::new();
+        state.points = BigNum::from(1500);
+        state.lifetime_points = BigNum::from(2000);
+        state.buildings[1].count = 3;
+        state.save_game(&dir, "ritual").unwrap();
+        let dumped = dump(&options);
+        assert_eq!((dumped.points, dumped.lifetime_points), (state.points, state.lifetime_points));
+        let counts = |state: &GameState| state.buildings.iter().map(|building| building.count).collect::<Vec<_>>();
+        assert_eq!(counts(&dumped), counts(&state));
+
+        // A save that can't be read is an error, not a new game
+        fs::write(slots::slot_path(&dir, "broken"), "nonsense").unwrap();
+        let options = parse(&["--save-dir", dir.to_str().unwrap(), "--slot", "broken", "dump"]).unwrap();
+        let mut out = Vec::new();
+        let err = dump_state(&options, &mut out).unwrap_err();
+        assert!(err.to_string().starts_with("Could not load the save: "), "{}", err);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn idols_click_faster_with_the_production_scale() {
+        let mut state = GameState::new();
+        state.buildings[2].count = 5;
+        advance(&mut state, 10.0, 1.0);
+        assert_eq!(state.points, BigNum::from(10));
+        advance(&mut state, 10.0, 2.0);
+        assert_eq!(state.points, BigNum::from(30));
+    }
+}
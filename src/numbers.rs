@@ -0,0 +1,216 @@
+// How the large numbers of a late game are shown
+//
+// In the abbreviated format, numbers below EXACT_BELOW are shown in full and
+// larger ones are scaled down by thousands to the biggest suffix that keeps
+// them under 1000, so 2,300,000 reads as 2.30M. The scientific format shows
+// the same number as 2.30e6, and the raw format keeps every digit, grouped
+// in threes as 2,300,000. Past the named suffixes, and past RAW_MAX_DIGITS
+// digits in the raw format, numbers fall back to scientific.
+
+use crate::bignum::BigNum;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    #[default]
+    Abbreviated,
+    Scientific,
+    Raw,
+}
+
+impl NumberFormat {
+    pub fn next(self) -> Self {
+        match self {
+            NumberFormat::Abbreviated => NumberFormat::Scientific,
+            NumberFormat::Scientific => NumberFormat::Raw,
+            NumberFormat::Raw => NumberFormat::Abbreviated,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        match self {
+            NumberFormat::Abbreviated => NumberFormat::Raw,
+            NumberFormat::Scientific => NumberFormat::Abbreviated,
+            NumberFormat::Raw => NumberFormat::Scientific,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NumberFormat::Abbreviated => "Abbreviated",
+            NumberFormat::Scientific => "Scientific",
+            NumberFormat::Raw => "Raw",
+        }
+    }
+}
+
+// Each suffix is a thousand times the one before, starting at a thousand
+const SUFFIXES: [&str; 11] = ["K", "M", "B", "T", "Qa", "Qi", "Sx", "Sp", "Oc", "No", "Dc"];
+
+// Smaller numbers are short enough to show every digit
+const EXACT_BELOW: u64 = 10_000;
+
+// Decimals shown after scaling
+const PRECISION: usize = 2;
+
+// Past this many digits the raw format gives up and shows scientific
+const RAW_MAX_DIGITS: usize = 40;
+
+pub fn format_number(n: impl Into<BigNum>, format: NumberFormat) -> String {
+    let n = n.into();
+    match format {
+        NumberFormat::Abbreviated => abbreviated(n, PRECISION),
+        NumberFormat::Scientific => scientific(n, PRECISION),
+        NumberFormat::Raw => raw(n),
+    }
+}
+
+// Rates per second, with one decimal while they are small enough to show in full
+pub fn format_rate(rate: f64, format: NumberFormat) -> String {
+    if rate < EXACT_BELOW as f64 {
+        return format!("{:.1}", rate);
+    }
+    format_number(BigNum::from_f64(rate), format)
+}
+
+// Production multipliers like x8 or x1.5, with at most two decimals
+pub fn format_multiplier(multiplier: f64, format: NumberFormat) -> String {
+    if multiplier < EXACT_BELOW as f64 {
+        let text = format!("{:.2}", multiplier);
+        return format!("x{}", text.trim_end_matches('0').trim_end_matches('.'));
+    }
+    format!("x{}", format_number(BigNum::from_f64(multiplier), format))
+}
+
+// Splits a positive number into a leading value and a power of ten that is a
+// multiple of `step`, rounding the value to `precision` decimals and carrying
+// into the next power when it reaches 10^step
+fn scaled(n: BigNum, step: i32, precision: usize) -> (f64, i32) {
+    let log = n.log10();
+    let mut power = (log / step as f64).floor() as i32 * step;
+    let mut value = 10f64.powf(log - power as f64);
+    let scale = 10f64.powi(precision as i32);
+    value = (value * scale).round() / scale;
+    if value >= 10f64.powi(step) {
+        value /= 10f64.powi(step);
+        power += step;
+    }
+    (value, power)
+}
+
+fn abbreviated(n: BigNum, precision: usize) -> String {
+    if n < BigNum::from(EXACT_BELOW) {
+        return n.to_string();
+    }
+
+    // Rounding can carry into the next suffix, 999,999 is 1.00M and not 1000.00K
+    let (value, power) = scaled(n, 3, precision);
+    match SUFFIXES.get(power as usize / 3 - 1) {
+        Some(suffix) => format!("{:.*}{}", precision, value, suffix),
+        None => scientific(n, precision),
+    }
+}
+
+fn scientific(n: BigNum, precision: usize) -> String {
+    if n < BigNum::from(EXACT_BELOW) {
+        return n.to_string();
+    }
+
+    let (value, power) = scaled(n, 1, precision);
+    format!("{:.*}e{}", precision, value, power)
+}
+
+fn raw(n: BigNum) -> String {
+    let digits = n.log10().floor() as usize + 1;
+    if digits > RAW_MAX_DIGITS {
+        return scientific(n, PRECISION);
+    }
+    // Past the exact range the trailing digits are unknown, so they show as zeros
+    let (mantissa, exponent) = n.parts();
+    group_digits(&format!("{}{}", mantissa, "0".repeat(exponent as usize)))
+}
+
+// Puts a comma between each group of three digits, counted from the right
+fn group_digits(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abbreviation_boundaries() {
+        let abbreviated = |n: u64| format_number(n, NumberFormat::Abbreviated);
+        assert_eq!(abbreviated(999), "999");
+        assert_eq!(abbreviated(1_000), "1000");
+        assert_eq!(abbreviated(9_999), "9999");
+        assert_eq!(abbreviated(10_000), "10.00K");
+        assert_eq!(abbreviated(999_994), "999.99K");
+        assert_eq!(abbreviated(999_999), "1.00M");
+        assert_eq!(abbreviated(1_000_000), "1.00M");
+        assert_eq!(abbreviated(1_500_000_000), "1.50B");
+        assert_eq!(abbreviated(u64::MAX), "18.45Qi");
+    }
+
+    #[test]
+    fn each_format_on_representative_values() {
+        let cases: [(BigNum, [&str; 3]); 5] = [
+            (BigNum::from(12_345u64), ["12.35K", "1.23e4", "12,345"]),
+            // Past the exact range the raw digits end in zeros
+            (BigNum::from(1_234_567_890_123_456u64), ["1.23Qa", "1.23e15", "1,234,567,890,123,450"]),
+            (BigNum::from(u64::MAX), ["18.45Qi", "1.84e19", "18,446,744,073,709,500,000"]),
+            ("456e22".parse().unwrap(), ["4.56Sp", "4.56e24", "4,560,000,000,000,000,000,000,000"]),
+            (BigNum::pow(10.0, 100.0), ["1.00e100", "1.00e100", "1.00e100"]),
+        ];
+        let formats = [NumberFormat::Abbreviated, NumberFormat::Scientific, NumberFormat::Raw];
+        for (n, expected) in cases {
+            for (format, expected) in formats.into_iter().zip(expected) {
+                assert_eq!(format_number(n, format), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn formats_cycle_both_ways() {
+        let mut format = NumberFormat::default();
+        for _ in 0..3 {
+            assert_eq!(format.next().previous(), format);
+            format = format.next();
+        }
+        assert_eq!(format, NumberFormat::Abbreviated);
+        assert_eq!(NumberFormat::Scientific.name(), "Scientific");
+    }
+
+    #[test]
+    fn digits_group_in_threes_from_the_right() {
+        assert_eq!(group_digits("0"), "0");
+        assert_eq!(group_digits("7"), "7");
+        assert_eq!(group_digits("42"), "42");
+        assert_eq!(group_digits("999"), "999");
+        assert_eq!(group_digits("1400000"), "1,400,000");
+        let powers: Vec<String> = (0..8).map(|power| group_digits(&10u64.pow(power).to_string())).collect();
+        assert_eq!(powers, ["1", "10", "100", "1,000", "10,000", "100,000", "1,000,000", "10,000,000"]);
+        assert_eq!(format_number(1_000u64, NumberFormat::Raw), "1,000");
+    }
+
+    #[test]
+    fn rates_keep_a_decimal_until_they_abbreviate() {
+        assert_eq!(format_rate(0.0, NumberFormat::Abbreviated), "0.0");
+        assert_eq!(format_rate(0.25, NumberFormat::Abbreviated), "0.2");
+        assert_eq!(format_rate(9_999.9, NumberFormat::Abbreviated), "9999.9");
+        assert_eq!(format_rate(10_000.0, NumberFormat::Abbreviated), "10.00K");
+        assert_eq!(format_rate(4_520_000.0, NumberFormat::Abbreviated), "4.52M");
+        assert_eq!(format_rate(4_520_000.0, NumberFormat::Scientific), "4.52e6");
+        assert_eq!(format_rate(4_520_000.0, NumberFormat::Raw), "4,520,000");
+        assert_eq!(format_rate(1e40, NumberFormat::Abbreviated), "1.00e40");
+    }
+}
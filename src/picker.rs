@@ -0,0 +1,109 @@
+// The startup menu for picking a save slot, shown when --slot isn't given
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    style::Stylize,
+    terminal,
+};
+use std::{io, path::Path};
+
+use clickercurse_game::slots::{list_saves, profile_name, valid_slot_name, DEFAULT_SLOT};
+use clickercurse_game::theme::{colored, ThemePreset};
+
+use crate::paint;
+use crate::screen::{Frame, Screen};
+
+// Lets the player pick a slot or start a new one before the game begins.
+// Returns None if they quit instead.
+pub fn pick_slot(screen: &mut Screen, out: &mut impl io::Write, dir: &Path) -> io::Result<Option<String>> {
+    let slots: Vec<(String, Option<String>)> = list_saves(dir)?
+        .into_iter()
+        .map(|slot| {
+            let name = profile_name(dir, &slot);
+            (slot, name)
+        })
+        .collect();
+
+    // Nothing to choose from on a first run
+    if slots.is_empty() {
+        return Ok(Some(DEFAULT_SLOT.to_string()));
+    }
+
+    // No save is loaded yet, so there is no chosen theme to use
+    let theme = ThemePreset::default().theme();
+    let mut selected = 0;
+    let mut new_name: Option<String> = None; // Set while naming a new slot
+    let mut error: Option<String> = None;
+
+    loop {
+        let (width, height) = terminal::size()?;
+        let mut frame = Frame::new(width, height);
+        frame.print_styled(0, 0, paint(colored("Choose a Save", theme.title).bold()));
+
+        for (i, (slot, name)) in slots.iter().enumerate() {
+            let prefix = if i == selected { "> " } else { "  " };
+            let label = match name {
+                Some(name) => format!("{}{}  ({})", prefix, slot, name),
+                None => format!("{}{}", prefix, slot),
+            };
+            frame.print(0, i as u16 + 2, label);
+        }
+        let prefix = if selected == slots.len() { "> " } else { "  " };
+        frame.print_styled(0, slots.len() as u16 + 2, paint(colored(format!("{}New game", prefix), theme.points)));
+
+        match &new_name {
+            Some(name) => {
+                frame.print_styled(0, height.saturating_sub(3), paint(colored(format!("Name the new save: {}_", name), theme.heading)));
+            },
+            None => frame.print(0, height.saturating_sub(3), "Use Up/Down to select, Enter to begin, Ctrl+C to leave"),
+        }
+        if let Some(error) = &error {
+            frame.print_styled(0, height.saturating_sub(2), paint(colored(error.clone(), theme.warning)));
+        }
+        screen.present(out, frame)?;
+
+        let key_event = match event::read()? {
+            Event::Key(key_event) => key_event,
+            Event::Resize(_, _) => {
+                screen.invalidate();
+                continue;
+            },
+            _ => continue,
+        };
+        if key_event.code == KeyCode::Char('c') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(None);
+        }
+
+        if let Some(name) = &mut new_name {
+            match key_event.code {
+                KeyCode::Char(ch) => name.push(ch),
+                KeyCode::Backspace => {
+                    name.pop();
+                },
+                KeyCode::Esc => new_name = None,
+                KeyCode::Enter => {
+                    if !valid_slot_name(name) {
+                        error = Some("Use letters, digits, '-' and '_' only".to_string());
+                    } else if slots.iter().any(|(slot, _)| slot == name) {
+                        error = Some(format!("A save named '{}' already exists", name));
+                    } else {
+                        return Ok(new_name);
+                    }
+                },
+                _ => {},
+            }
+            continue;
+        }
+
+        match key_event.code {
+            KeyCode::Up => selected = selected.saturating_sub(1),
+            KeyCode::Down => selected = (selected + 1).min(slots.len()),
+            KeyCode::Enter if selected == slots.len() => {
+                new_name = Some(String::new());
+                error = None;
+            },
+            KeyCode::Enter => return Ok(Some(slots[selected].0.clone())),
+            _ => {},
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// Rebirth, the prestige layer above ascension, and its skill tree
+//
+// Being reborn trades every eldritch shard (and with them the relics) for
+// rebirth tokens, one per SHARDS_PER_TOKEN shards. Tokens buy the nodes of a
+// small skill tree, each needing the nodes listed in `requires` first. The
+// nodes are kept for good, by key, and change how the game works rather than
+// adding production: minions cost less, influence power grows faster, and
+// more time away counts as offline progress.
+
+// Shards traded for each rebirth token
+pub const SHARDS_PER_TOKEN: u64 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkillEffect {
+    MinionDiscount(f64), // Fraction taken off every minion's price
+    ClickScaling(f64), // Multiplies the influence power each milestone grants
+    OfflineHours(f64), // Added to the offline progress cap
+}
+
+pub struct SkillNode {
+    pub key: &'static str, // Names the node in saves
+    pub name: &'static str,
+    pub description: &'static str,
+    pub cost: u64, // In rebirth tokens
+    pub requires: &'static [&'static str],
+    pub effect: SkillEffect,
+}
+
+// Roots first, so every node comes after the nodes it requires
+pub const NODES: &[SkillNode] = &[
+    SkillNode {
+        key: "bargain",
+        name: "Whispered Bargains",
+        description: "Minions cost 10% less",
+        cost: 1,
+        requires: &[],
+        effect: SkillEffect::MinionDiscount(0.1),
+    },
+    SkillNode {
+        key: "fervor",
+        name: "Fervent Voices",
+        description: "Influence power milestones grant twice as much",
+        cost: 1,
+        requires: &[],
+        effect: SkillEffect::ClickScaling(2.0),
+    },
+    SkillNode {
+        key: "dreams",
+        name: "Long Dreams",
+        description: "4 more hours of offline progress",
+        cost: 1,
+        requires: &[],
+        effect: SkillEffect::OfflineHours(4.0),
+    },
+    SkillNode {
+        key: "tithe",
+        name: "Tithe of Ages",
+        description: "Minions cost a further 15% less",
+        cost: 3,
+        requires: &["bargain"],
+        effect: SkillEffect::MinionDiscount(0.15),
+    },
+    SkillNode {
+        key: "zeal",
+        name: "Unending Zeal",
+        description: "Influence power milestones grant a further 50% more",
+        cost: 3,
+        requires: &["fervor"],
+        effect: SkillEffect::ClickScaling(1.5),
+    },
+    SkillNode {
+        key: "slumber",
+        name: "Eternal Slumber",
+        description: "16 more hours of offline progress",
+        cost: 3,
+        requires: &["dreams"],
+        effect: SkillEffect::OfflineHours(16.0),
+    },
+    SkillNode {
+        key: "rlyeh",
+        name: "Keys of R'lyeh",
+        description: "Minions cost a further 20% less",
+        cost: 10,
+        requires: &["tithe", "zeal", "slumber"],
+        effect: SkillEffect::MinionDiscount(0.2),
+    },
+];
+
+// Tokens earned by being reborn with this many shards
+pub fn tokens_from_shards(shards: u64) -> u64 {
+    shards / SHARDS_PER_TOKEN
+}
+
+pub fn node(key: &str) -> Option<&'static SkillNode> {
+    NODES.iter().find(|node| node.key == key)
+}
+
+// Whether every node `node` requires is among the `owned` keys
+pub fn unlocked(node: &SkillNode, owned: &[String]) -> bool {
+    node.requires.iter().all(|required| owned.iter().any(|key| key == required))
+}
+
+// Effects of the owned nodes, skipping keys no node has any more
+pub fn effects(owned: &[String]) -> impl Iterator<Item = SkillEffect> + '_ {
+    owned.iter().filter_map(|key| node(key)).map(|node| node.effect)
+}
+
+// Fraction of the full price minions cost with these nodes
+pub fn price_factor(owned: &[String]) -> f64 {
+    effects(owned)
+        .filter_map(|effect| match effect {
+            SkillEffect::MinionDiscount(discount) => Some(1.0 - discount),
+            _ => None,
+        })
+        .product()
+}
+
+// Multiplier on the influence power of each milestone
+pub fn click_scaling(owned: &[String]) -> f64 {
+    effects(owned)
+        .filter_map(|effect| match effect {
+            SkillEffect::ClickScaling(scaling) => Some(scaling),
+            _ => None,
+        })
+        .product()
+}
+
+pub fn offline_hours(owned: &[String]) -> f64 {
+    effects(owned)
+        .filter_map(|effect| match effect {
+            SkillEffect::OfflineHours(hours) => Some(hours),
+            _ => None,
+        })
+        .sum()
+}
@@ -0,0 +1,149 @@
+// Replay scripts: one timed action per line, for example
+//
+//     0.0 click
+//     1.5 buy cursor
+//     2.0 menu buildings
+//
+// Times are seconds since the replay started and must not go backwards.
+// Blank lines and lines starting with '#' are ignored.
+
+use crate::{Action, Menu};
+
+#[derive(Clone, Debug)]
+pub struct TimedAction {
+    pub at: f64,
+    pub action: Action,
+}
+
+pub fn parse_script(text: &str) -> Result<Vec<TimedAction>, String> {
+    let mut steps = Vec::new();
+    let mut last_at = 0.0;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let at = words.next()
+            .and_then(|word| word.parse::<f64>().ok())
+            .filter(|at| at.is_finite() && *at >= 0.0)
+            .ok_or_else(|| format!("line {}: expected a time in seconds", number + 1))?;
+        if at < last_at {
+            return Err(format!("line {}: actions must be in time order", number + 1));
+        }
+
+        let words: Vec<&str> = words.collect();
+        let action = parse_action(&words).map_err(|err| format!("line {}: {}", number + 1, err))?;
+
+        last_at = at;
+        steps.push(TimedAction { at, action });
+    }
+
+    Ok(steps)
+}
+
+fn parse_action(words: &[&str]) -> Result<Action, String> {
+    match words {
+        ["click"] => Ok(Action::Click),
+        ["pause"] => Ok(Action::TogglePause),
+        ["sandbox"] => Ok(Action::ToggleSandbox),
+        ["reserve"] => Ok(Action::ToggleReserve),
+        ["reserve", amount] => crate::parse_reserve(amount)
+            .map(Action::SetReserve)
+            .ok_or_else(|| format!("invalid reserve '{}'", amount)),
+        ["spendall"] => Ok(Action::SpendAll),
+        ["golden"] => Ok(Action::CatchGolden),
+        ["ascend"] => Ok(Action::StartAscension),
+        ["rebirth"] => Ok(Action::StartRebirth),
+        ["lines"] => Ok(Action::ToggleUpgradeLines),
+        ["bulk"] => Ok(Action::CycleBuyAmount),
+        ["sort"] => Ok(Action::CycleBuildingSort),
+        ["max"] => Ok(Action::BuyMax),
+        ["sell"] => Ok(Action::SellSelection),
+        ["name", name @ ..] => Ok(Action::SetProfileName(name.join(" "))),
+        ["save"] => Ok(Action::Save),
+        ["quit"] => Ok(Action::Quit),
+        ["up"] => Ok(Action::SelectPrevious),
+        ["down"] => Ok(Action::SelectNext),
+        ["pageup"] => Ok(Action::PagePrevious),
+        ["pagedown"] => Ok(Action::PageNext),
+        ["first"] => Ok(Action::SelectFirst),
+        ["last"] => Ok(Action::SelectLast),
+        ["select"] => Ok(Action::ConfirmSelection),
+        ["yes"] => Ok(Action::Answer(true)),
+        ["no"] => Ok(Action::Answer(false)),
+        ["left"] => Ok(Action::SettingPrevious),
+        ["right"] => Ok(Action::SettingNext),
+        ["buy", key] => Ok(Action::BuyBuilding(key.to_string())),
+        ["upgrade", index] => index.parse::<usize>()
+            .map(Action::BuyUpgrade)
+            .map_err(|_| format!("invalid upgrade index '{}'", index)),
+        ["row", row] => row.parse::<usize>().ok()
+            .filter(|row| (1..=9).contains(row))
+            .map(|row| Action::QuickBuy(row - 1))
+            .ok_or_else(|| format!("invalid minion row '{}'", row)),
+        ["relic", index] => index.parse::<usize>()
+            .map(Action::BuyPrestigeUpgrade)
+            .map_err(|_| format!("invalid relic index '{}'", index)),
+        ["skill", index] => index.parse::<usize>()
+            .map(Action::BuySkill)
+            .map_err(|_| format!("invalid skill index '{}'", index)),
+        ["menu", name] => menu_by_name(name)
+            .map(Action::OpenMenu)
+            .ok_or_else(|| format!("unknown menu '{}'", name)),
+        _ => Err(format!("unknown action '{}'", words.join(" "))),
+    }
+}
+
+fn menu_by_name(name: &str) -> Option<Menu> {
+    match name {
+        "main" => Some(Menu::Main),
+        "buildings" => Some(Menu::Buildings),
+        "upgrades" => Some(Menu::Upgrades),
+        "stats" => Some(Menu::Stats),
+        "history" => Some(Menu::History),
+        "deeds" => Some(Menu::Deeds),
+        "relics" => Some(Menu::Relics),
+        "rebirth" => Some(Menu::Rebirth),
+        "achievements" => Some(Menu::Achievements),
+        "settings" => Some(Menu::Settings),
+        "help" => Some(Menu::Help),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timed_actions() {
+        let steps = parse_script("# warm up\n0 click\n\n1.5 buy cursor\n  2 menu buildings \n2 upgrade 3\n").unwrap();
+        let parsed: Vec<(f64, Action)> = steps.into_iter().map(|step| (step.at, step.action)).collect();
+        assert_eq!(parsed, vec![
+            (0.0, Action::Click),
+            (1.5, Action::BuyBuilding("cursor".to_string())),
+            (2.0, Action::OpenMenu(Menu::Buildings)),
+            (2.0, Action::BuyUpgrade(3)),
+        ]);
+    }
+
+    #[test]
+    fn rejects_bad_lines_with_their_number() {
+        assert_eq!(parse_script("2 click\n1 click").unwrap_err(), "line 2: actions must be in time order");
+        assert_eq!(parse_script("soon click").unwrap_err(), "line 1: expected a time in seconds");
+        assert_eq!(parse_script("-1 click").unwrap_err(), "line 1: expected a time in seconds");
+        assert_eq!(parse_script("0 click\n1 dance").unwrap_err(), "line 2: unknown action 'dance'");
+        assert_eq!(parse_script("0 row 10").unwrap_err(), "line 1: invalid minion row '10'");
+        assert_eq!(parse_script("0 menu attic").unwrap_err(), "line 1: unknown menu 'attic'");
+    }
+
+    #[test]
+    fn rows_count_from_one() {
+        let steps = parse_script("0 row 1\n0 row 9").unwrap();
+        assert_eq!(steps[0].action, Action::QuickBuy(0));
+        assert_eq!(steps[1].action, Action::QuickBuy(8));
+    }
+}
@@ -0,0 +1,86 @@
+// Small seedable random number generator (SplitMix64)
+//
+// Good enough for gameplay randomness, and a fixed seed makes headless
+// replays reproducible.
+//
+// This is used rather than rand's SmallRng because SmallRng doesn't promise
+// the same sequence across platforms or rand releases, so a --seed replay
+// could change under a dependency update. SplitMix64 is fixed, a few lines
+// long and needs no extra crate.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { seed, state: seed }
+    }
+
+    // Seeded from the clock, for when no seed was asked for
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        Rng::new(nanos as u64)
+    }
+
+    // The seed this generator started from, to reproduce a run with --seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+        let first: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..8).map(|_| c.next_u64()).collect::<Vec<_>>());
+        assert_eq!(a.seed(), 42);
+    }
+
+    #[test]
+    fn floats_stay_in_the_unit_interval() {
+        let mut rng = Rng::new(7);
+        assert!((0..10_000).map(|_| rng.next_f64()).all(|roll| (0.0..1.0).contains(&roll)));
+    }
+
+    #[test]
+    fn floats_are_spread_evenly() {
+        let mut rng = Rng::new(1);
+        let rolls: Vec<f64> = (0..100_000).map(|_| rng.next_f64()).collect();
+        let mean = rolls.iter().sum::<f64>() / rolls.len() as f64;
+        assert!((mean - 0.5).abs() < 0.01, "mean {}", mean);
+
+        // Each tenth of the interval gets close to a tenth of the rolls
+        let mut buckets = [0usize; 10];
+        for roll in &rolls {
+            buckets[(roll * 10.0) as usize] += 1;
+        }
+        for count in buckets {
+            assert!((9_500..=10_500).contains(&count), "buckets {:?}", buckets);
+        }
+    }
+}
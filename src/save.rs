@@ -0,0 +1,470 @@
+// Save file versions and migrations
+//
+// Saves are JSON with a `version` field. Older saves are upgraded one version
+// at a time by `migrate` before they are deserialized into a GameState.
+//
+//   1 - the first JSON saves, with no version field and full building and
+//       upgrade definitions
+//   2 - only building counts and production and upgrade progress are saved;
+//       the rest of the definitions come from the game itself
+//   3 - followers, converts and the reserve are saved as a mantissa and an
+//       exponent so they can grow past u64::MAX
+//   4 - playtime is saved as whole seconds, with the part of a second apart
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{Achievement, BigNum, Building, GameState, PrestigeUpgrade, Upgrade, MAX_BASE_PRODUCTION};
+
+pub const SAVE_VERSION: u32 = 4;
+
+// Saves written before versioning was added
+const UNVERSIONED: u32 = 1;
+
+pub fn save_version(value: &Value) -> u32 {
+    value.get("version")
+        .and_then(Value::as_u64)
+        .map_or(UNVERSIONED, |version| version as u32)
+}
+
+// Upgrades a save from `raw_version` to SAVE_VERSION
+pub fn migrate(raw_version: u32, mut value: Value) -> Result<Value, String> {
+    if raw_version > SAVE_VERSION {
+        return Err(format!(
+            "save is from a newer version of the game (version {}, this build supports up to {})",
+            raw_version, SAVE_VERSION));
+    }
+
+    let mut version = raw_version;
+    while version < SAVE_VERSION {
+        value = match version {
+            1 => v1_to_v2(value),
+            2 => v2_to_v3(value),
+            3 => v3_to_v4(value),
+            _ => return Err(format!("no migration from save version {}", version)),
+        };
+        version += 1;
+    }
+
+    value["version"] = json!(SAVE_VERSION);
+    Ok(value)
+}
+
+// Drop the saved definitions, keeping only what the player changed
+fn v1_to_v2(mut value: Value) -> Value {
+    if let Some(buildings) = value.get_mut("buildings").and_then(Value::as_object_mut) {
+        for building in buildings.values_mut() {
+            *building = json!({
+                "count": building.get("count").cloned().unwrap_or(json!(0)),
+                "base_production": building.get("base_production").cloned().unwrap_or(Value::Null),
+            });
+        }
+    }
+    if let Some(upgrades) = value.get_mut("upgrades").and_then(Value::as_array_mut) {
+        for upgrade in upgrades.iter_mut() {
+            *upgrade = json!({
+                "purchased": upgrade.get("purchased").cloned().unwrap_or(json!(false)),
+                "produced_at_purchase": upgrade.get("produced_at_purchase").cloned().unwrap_or(Value::Null),
+            });
+        }
+    }
+    value
+}
+
+// Turn the u64 follower counts into big numbers
+fn v2_to_v3(mut value: Value) -> Value {
+    for field in ["points", "lifetime_points", "reserve"] {
+        if let Some(count) = value.get(field).and_then(Value::as_u64) {
+            value[field] = json!(BigNum::from(count));
+        }
+    }
+    value
+}
+
+// Split the playtime into whole seconds and the fraction left over
+fn v3_to_v4(mut value: Value) -> Value {
+    if let Some(secs) = value.get("playtime_secs").and_then(Value::as_f64) {
+        let secs = secs.max(0.0);
+        value["playtime_secs"] = json!(secs.floor() as u64);
+        value["playtime_fraction"] = json!(secs.fract());
+    }
+    value
+}
+
+const CHECKSUM_PREFIX: &str = "checksum:";
+
+// 64-bit FNV-1a, enough to catch corruption and casual edits
+fn checksum(body: &str) -> u64 {
+    body.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+// Appends a checksum line covering the body
+pub fn seal(body: &str) -> String {
+    format!("{}\n{}{:016x}\n", body, CHECKSUM_PREFIX, checksum(body))
+}
+
+// Returns the body of a sealed save after checking it against its checksum.
+// Saves from before checksums were added are returned whole.
+pub fn unseal(contents: &str) -> Result<&str, String> {
+    let trimmed = contents.trim_end();
+    let (body, last_line) = match trimmed.rsplit_once('\n') {
+        Some(split) => split,
+        None => return Ok(contents),
+    };
+    let Some(expected) = last_line.strip_prefix(CHECKSUM_PREFIX) else {
+        return Ok(contents);
+    };
+
+    match u64::from_str_radix(expected, 16) {
+        Ok(expected) if expected == checksum(body) => Ok(body),
+        _ => Err("save is corrupt or was edited (checksum mismatch)".to_string()),
+    }
+}
+
+pub fn is_sealed(contents: &str) -> bool {
+    contents.trim_end()
+        .rsplit_once('\n')
+        .is_some_and(|(_, last_line)| last_line.starts_with(CHECKSUM_PREFIX))
+}
+
+// Gzips and base64-encodes save contents into a string that survives copy
+// and paste
+pub fn encode_transfer(contents: &str) -> io::Result<String> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(contents.as_bytes())?;
+    Ok(STANDARD.encode(encoder.finish()?))
+}
+
+// Reverses encode_transfer. Whitespace is ignored, since terminals tend to
+// add line breaks when copying wrapped text.
+pub fn decode_transfer(text: &str) -> Result<String, String> {
+    let compact: String = text.chars().filter(|ch| !ch.is_whitespace()).collect();
+    let bytes = STANDARD.decode(compact).map_err(|_| "that is not a save string".to_string())?;
+
+    let mut contents = String::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_string(&mut contents)
+        .map_err(|_| "the save string is damaged".to_string())?;
+    Ok(contents)
+}
+
+// Older copies of each slot kept as `{slot}.save.1` (newest) to `.3`
+pub const BACKUPS: usize = 3;
+
+pub fn backup_path(path: &Path, generation: usize) -> PathBuf {
+    path.with_extension(format!("save.{}", generation))
+}
+
+// Shifts each backup one generation older, dropping the oldest, and moves
+// the current save in as the newest
+pub fn rotate_backups(path: &Path) -> io::Result<()> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for generation in (1..BACKUPS).rev() {
+        let older = backup_path(path, generation);
+        if older.exists() {
+            fs::rename(&older, backup_path(path, generation + 1))?;
+        }
+    }
+    fs::rename(path, backup_path(path, 1))
+}
+
+// Deletes a save and all of its backups, so nothing is left to fall back to
+pub fn remove_with_backups(path: &Path) -> io::Result<()> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for generation in 0..=BACKUPS {
+        let candidate = if generation == 0 { path.to_path_buf() } else { backup_path(path, generation) };
+        match fs::remove_file(&candidate) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {},
+        }
+    }
+    Ok(())
+}
+
+// Manual saves and autosaves run on their own threads and share a temp file
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// Writes through a temporary file renamed over `path`, so a crash mid-write
+// leaves the previous save intact rather than a truncated one
+pub fn write_atomic(path: &Path, write: impl FnOnce(&mut File) -> io::Result<()>) -> io::Result<()> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let tmp_path = path.with_extension("save.tmp");
+    let result = File::create(&tmp_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.flush()?;
+        file.sync_all()
+    });
+
+    match result {
+        Ok(()) => fs::rename(&tmp_path, path),
+        Err(err) => {
+            let _ = fs::remove_file(&tmp_path);
+            Err(err)
+        },
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BuildingProgress {
+    count: u64,
+    #[serde(default)]
+    base_production: Option<f64>, // Missing keeps the game's default
+}
+
+#[derive(Serialize, Deserialize)]
+struct UpgradeProgress {
+    purchased: bool,
+    produced_at_purchase: Option<u64>,
+}
+
+// Serializes buildings as their counts, keyed like GameState::buildings
+pub mod buildings {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(buildings: &[Building], serializer: S) -> Result<S::Ok, S::Error> {
+        // Sorted by key, as saves have always been
+        let progress: BTreeMap<&String, BuildingProgress> = buildings.iter()
+            .map(|building| (&building.key, BuildingProgress {
+                count: building.count,
+                base_production: Some(building.base_production),
+            }))
+            .collect();
+        progress.serialize(serializer)
+    }
+
+    // Unknown keys are ignored, buildings missing from the save start at zero
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Building>, D::Error> {
+        let progress = HashMap::<String, BuildingProgress>::deserialize(deserializer)?;
+        let mut buildings = GameState::new().buildings;
+        for (key, saved) in progress {
+            if let Some(building) = buildings.iter_mut().find(|building| building.key == key) {
+                building.count = saved.count;
+                if let Some(production) = saved.base_production.filter(|production| production.is_finite()) {
+                    building.base_production = production.clamp(0.0, MAX_BASE_PRODUCTION);
+                }
+            }
+        }
+        Ok(buildings)
+    }
+}
+
+// Serializes upgrades as their progress, in GameState::upgrades order
+pub mod upgrades {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(upgrades: &[Upgrade], serializer: S) -> Result<S::Ok, S::Error> {
+        let progress: Vec<UpgradeProgress> = upgrades.iter()
+            .map(|upgrade| UpgradeProgress {
+                purchased: upgrade.purchased,
+                produced_at_purchase: upgrade.produced_at_purchase,
+            })
+            .collect();
+        progress.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Upgrade>, D::Error> {
+        let progress = Vec::<UpgradeProgress>::deserialize(deserializer)?;
+        let mut upgrades = GameState::new().upgrades;
+        for (upgrade, saved) in upgrades.iter_mut().zip(progress) {
+            upgrade.purchased = saved.purchased;
+            upgrade.produced_at_purchase = saved.produced_at_purchase;
+        }
+        Ok(upgrades)
+    }
+}
+
+// Serializes relics as whether each was bought, in GameState::prestige_upgrades
+// order. They are saved apart from the upgrades since ascending keeps them.
+pub mod prestige_upgrades {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(relics: &[PrestigeUpgrade], serializer: S) -> Result<S::Ok, S::Error> {
+        let purchased: Vec<bool> = relics.iter().map(|relic| relic.purchased).collect();
+        purchased.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PrestigeUpgrade>, D::Error> {
+        let purchased = Vec::<bool>::deserialize(deserializer)?;
+        let mut relics = GameState::new().prestige_upgrades;
+        for (relic, saved) in relics.iter_mut().zip(purchased) {
+            relic.purchased = saved;
+        }
+        Ok(relics)
+    }
+}
+
+// Serializes achievements as the keys of those unlocked. Keys that are no
+// longer defined are dropped on load.
+pub mod achievements {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(achievements: &[Achievement], serializer: S) -> Result<S::Ok, S::Error> {
+        let unlocked: Vec<&str> = achievements.iter()
+            .filter(|achievement| achievement.unlocked)
+            .map(|achievement| achievement.key)
+            .collect();
+        unlocked.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Achievement>, D::Error> {
+        let unlocked = Vec::<String>::deserialize(deserializer)?;
+        let mut achievements = GameState::new().achievements;
+        for achievement in &mut achievements {
+            achievement.unlocked = unlocked.iter().any(|key| key == achievement.key);
+        }
+        Ok(achievements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn failed_writes_keep_the_previous_save() {
+        let dir = TempDir::new("atomic-write");
+        let path = dir.join("game.save");
+        write_atomic(&path, |file| file.write_all(b"good")).unwrap();
+
+        let err = write_atomic(&path, |file| {
+            file.write_all(b"half")?;
+            Err(io::Error::other("disk full"))
+        }).unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "good");
+        assert!(!path.with_extension("save.tmp").exists());
+    }
+
+    #[test]
+    fn checksum_is_fnv1a() {
+        assert_eq!(checksum(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(checksum("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(checksum("foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn seal_round_trips_and_catches_edits() {
+        let sealed = seal("{\"points\": 5}");
+        assert!(is_sealed(&sealed));
+        assert_eq!(unseal(&sealed), Ok("{\"points\": 5}"));
+
+        let edited = sealed.replace('5', "6");
+        assert_eq!(unseal(&edited), Err("save is corrupt or was edited (checksum mismatch)".to_string()));
+    }
+
+    #[test]
+    fn unsealed_saves_are_read_whole() {
+        assert!(!is_sealed("{\"points\": 5}\n"));
+        assert_eq!(unseal("{\"points\": 5}\n"), Ok("{\"points\": 5}\n"));
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_backups() {
+        let dir = TempDir::new("rotate");
+        let path = dir.join("game.save");
+        for generation in 1..=5 {
+            fs::write(&path, generation.to_string()).unwrap();
+            rotate_backups(&path).unwrap();
+        }
+        assert_eq!(backup_path(&path, 1), dir.join("game.save.1"));
+        let kept: Vec<String> = (1..=BACKUPS).map(|generation| fs::read_to_string(backup_path(&path, generation)).unwrap()).collect();
+        assert_eq!(kept, ["5", "4", "3"]);
+        assert!(!backup_path(&path, BACKUPS + 1).exists());
+        // The current save is moved, not copied
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn transfer_strings_round_trip() {
+        let contents = seal("{\"points\": 5}");
+        let encoded = encode_transfer(&contents).unwrap();
+        assert_eq!(decode_transfer(&encoded).unwrap(), contents);
+
+        // Line breaks from copying wrapped text are ignored
+        let (head, tail) = encoded.split_at(encoded.len() / 2);
+        assert_eq!(decode_transfer(&format!("  {}\n{}\r\n", head, tail)).unwrap(), contents);
+    }
+
+    #[test]
+    fn garbage_transfer_strings_are_rejected() {
+        assert_eq!(decode_transfer("not base64!"), Err("that is not a save string".to_string()));
+        assert_eq!(decode_transfer(&STANDARD.encode("plain text")), Err("the save string is damaged".to_string()));
+    }
+
+    #[test]
+    fn state_round_trips_through_a_sealed_save() {
+        let mut state = GameState::new();
+        state.points = BigNum::pow(10.0, 25.0);
+        state.lifetime_points = BigNum::from(123_456u64);
+        state.buildings[1].count = 7;
+        state.upgrades[0].purchased = true;
+
+        let loaded = GameState::from_json_or_legacy(&state.save_contents().unwrap()).unwrap();
+        assert_eq!(loaded.points, state.points);
+        assert_eq!(loaded.lifetime_points, state.lifetime_points);
+        assert_eq!(loaded.buildings[1].count, 7);
+        assert!(loaded.upgrades[0].purchased);
+        assert_eq!(loaded.version, SAVE_VERSION);
+    }
+
+    #[test]
+    fn migrates_unversioned_saves() {
+        let v1 = json!({
+            "points": 500,
+            "lifetime_points": 800,
+            "buildings": {"cursor": {"name": "Cultist", "count": 3, "cost": 15, "base_production": 0.5}},
+            "upgrades": [{"name": "Old upgrade", "purchased": true, "produced_at_purchase": 40}],
+        });
+        assert_eq!(save_version(&v1), 1);
+
+        let migrated = migrate(save_version(&v1), v1).unwrap();
+        assert_eq!(migrated["version"], json!(SAVE_VERSION));
+        assert_eq!(migrated["buildings"]["cursor"], json!({"count": 3, "base_production": 0.5}));
+        assert_eq!(migrated["upgrades"][0], json!({"purchased": true, "produced_at_purchase": 40}));
+
+        let state: GameState = serde_json::from_value(migrated).unwrap();
+        assert_eq!(state.points, BigNum::from(500u64));
+        assert_eq!(state.lifetime_points, BigNum::from(800u64));
+        assert_eq!(state.buildings[0].count, 3);
+        assert_eq!(state.buildings[0].base_production, 0.5);
+        assert_eq!(state.upgrades[0].produced_at_purchase, Some(40));
+    }
+
+    #[test]
+    fn migrates_u64_counts_to_big_numbers() {
+        let v2 = json!({"version": 2, "points": 42, "reserve": 7});
+        let migrated = migrate(2, v2).unwrap();
+        assert_eq!(migrated["points"], json!(BigNum::from(42u64)));
+        assert_eq!(migrated["reserve"], json!(BigNum::from(7u64)));
+        assert!(migrated.get("lifetime_points").is_none());
+    }
+
+    #[test]
+    fn migrates_playtime_to_whole_seconds() {
+        let v3 = json!({"version": 3, "playtime_secs": 4_321.5});
+        let migrated = migrate(3, v3).unwrap();
+        assert_eq!(migrated["playtime_secs"], json!(4_321));
+        assert_eq!(migrated["playtime_fraction"], json!(0.5));
+
+        let state: GameState = serde_json::from_value(migrated).unwrap();
+        assert_eq!(state.playtime_secs, 4_321);
+    }
+
+    #[test]
+    fn refuses_saves_from_newer_versions() {
+        let err = migrate(SAVE_VERSION + 1, json!({})).unwrap_err();
+        assert!(err.starts_with("save is from a newer version of the game"));
+    }
+}
@@ -0,0 +1,208 @@
+// Double-buffered terminal output
+//
+// The draw functions paint into an in-memory Frame. Screen keeps the frame
+// that is currently on the terminal and only writes the cells that changed,
+// instead of clearing and reprinting everything every 100ms.
+
+use crossterm::{
+    cursor, queue,
+    style::{self, ContentStyle, StyledContent},
+    terminal::{self, ClearType},
+};
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+// Longest stretch of unchanged cells folded into a run of changed ones
+const MAX_GAP: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cell {
+    ch: char,
+    style: ContentStyle,
+}
+
+impl Cell {
+    fn blank() -> Self {
+        Cell { ch: ' ', style: ContentStyle::new() }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    pub fn new(width: u16, height: u16) -> Self {
+        Frame {
+            width,
+            height,
+            cells: vec![Cell::blank(); width as usize * height as usize],
+        }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    // The characters of one row, for tests to read back what was drawn
+    #[cfg(test)]
+    pub fn line(&self, y: u16) -> String {
+        let row = y as usize * self.width as usize;
+        self.cells[row..row + self.width as usize].iter().map(|cell| cell.ch).collect::<String>().trim_end().to_string()
+    }
+
+    pub fn print(&mut self, x: u16, y: u16, text: impl Display) {
+        self.put(x, y, &text.to_string(), ContentStyle::new());
+    }
+
+    pub fn print_styled<D: Display>(&mut self, x: u16, y: u16, content: StyledContent<D>) {
+        self.put(x, y, &content.content().to_string(), *content.style());
+    }
+
+    // Anything past the right or bottom edge is clipped
+    fn put(&mut self, x: u16, y: u16, text: &str, style: ContentStyle) {
+        if y >= self.height {
+            return;
+        }
+
+        let row = y as usize * self.width as usize;
+        for (offset, ch) in text.chars().enumerate() {
+            let column = x as usize + offset;
+            if column >= self.width as usize {
+                break;
+            }
+            self.cells[row + column] = Cell { ch, style };
+        }
+    }
+}
+
+pub struct Screen {
+    shown: Option<Frame>,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Screen { shown: None }
+    }
+
+    // Forget what is on the terminal so the next frame is drawn in full
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+    }
+
+    // Writes the changes since the last frame in a single write, so the
+    // terminal never shows half of a frame. Any Write works as `out`,
+    // including a Vec<u8> to see what would be sent to the terminal.
+    pub fn present(&mut self, out: &mut impl Write, frame: Frame) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        let shown = match self.shown.take() {
+            Some(shown) if shown.size() == frame.size() => shown,
+            // First frame or a resize: start over from a cleared terminal
+            _ => {
+                queue!(buffer, terminal::Clear(ClearType::All))?;
+                Frame::new(frame.width, frame.height)
+            }
+        };
+
+        let width = frame.width as usize;
+        for y in 0..frame.height as usize {
+            let row = y * width;
+            let mut x = 0;
+            while x < width {
+                if frame.cells[row + x] == shown.cells[row + x] {
+                    x += 1;
+                    continue;
+                }
+
+                // Gather the run of changed cells sharing this style. Short
+                // stretches of unchanged cells are reprinted rather than
+                // skipped, since a cursor move costs more than a few chars.
+                let style = frame.cells[row + x].style;
+                let start = x;
+                let mut end = x;
+                while x < width && frame.cells[row + x].style == style && x - end <= MAX_GAP {
+                    if frame.cells[row + x] != shown.cells[row + x] {
+                        end = x;
+                    }
+                    x += 1;
+                }
+                x = end + 1;
+                let run: String = frame.cells[row + start..row + x].iter().map(|cell| cell.ch).collect();
+
+                queue!(
+                    buffer,
+                    cursor::MoveTo(start as u16, y as u16),
+                    style::PrintStyledContent(StyledContent::new(style, run))
+                )?;
+            }
+        }
+
+        out.write_all(&buffer)?;
+        out.flush()?;
+        self.shown = Some(frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLEAR: &str = "\x1b[2J";
+
+    fn presented(screen: &mut Screen, frame: Frame) -> String {
+        let mut out = Vec::new();
+        screen.present(&mut out, frame).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn frame_with(width: u16, height: u16, text: &str) -> Frame {
+        let mut frame = Frame::new(width, height);
+        frame.print(0, 0, text);
+        frame
+    }
+
+    #[test]
+    fn only_the_first_frame_and_resizes_clear() {
+        let mut screen = Screen::new();
+        assert!(presented(&mut screen, frame_with(20, 5, "Followers: 1")).contains(CLEAR));
+
+        let same_size = presented(&mut screen, frame_with(20, 5, "Followers: 2"));
+        assert!(!same_size.contains(CLEAR));
+        assert!(same_size.contains('2'));
+        assert!(!same_size.contains("Followers"));
+
+        let resized = presented(&mut screen, frame_with(30, 5, "Followers: 2"));
+        assert!(resized.contains(CLEAR));
+        assert!(resized.contains("Followers: 2"));
+
+        screen.invalidate();
+        assert!(presented(&mut screen, frame_with(30, 5, "Followers: 2")).contains(CLEAR));
+    }
+
+    #[test]
+    fn unchanged_frames_write_nothing() {
+        let mut screen = Screen::new();
+        presented(&mut screen, frame_with(20, 5, "Followers: 1"));
+        assert_eq!(presented(&mut screen, frame_with(20, 5, "Followers: 1")), "");
+    }
+
+    #[test]
+    fn text_past_the_edges_is_clipped() {
+        let mut frame = Frame::new(5, 2);
+        frame.print(2, 0, "Cthulhu");
+        frame.print(0, 2, "below");
+        frame.print(9, 1, "right");
+        assert_eq!(frame.line(0), "  Cth");
+        assert_eq!(frame.line(1), "");
+
+        let mut empty = Frame::new(0, 0);
+        empty.print(0, 0, "nothing fits");
+        assert_eq!(empty.size(), (0, 0));
+    }
+}
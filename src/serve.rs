@@ -0,0 +1,85 @@
+// Read-only JSON view of the game for external dashboards and overlays
+//
+// Each connection gets a fresh snapshot as a minimal HTTP response and is then
+// closed, so `curl http://127.0.0.1:7878/` or a browser source both work.
+
+use clickercurse_game::{get_domination_status, GameState};
+use serde::Serialize;
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[derive(Serialize)]
+struct BuildingSnapshot {
+    key: String,
+    name: String,
+    count: u64,
+    cost: f64, // Very large costs lose their last digits
+    production_per_second: f64,
+}
+
+#[derive(Serialize)]
+pub struct StateSnapshot {
+    points: f64,
+    lifetime_points: f64,
+    click_power: u64,
+    production_per_second: f64,
+    tier: String,
+    paused: bool,
+    buildings: Vec<BuildingSnapshot>,
+}
+
+impl StateSnapshot {
+    pub fn of(state: &GameState) -> Self {
+        let mut buildings: Vec<BuildingSnapshot> = state.buildings.iter()
+            .map(|building| BuildingSnapshot {
+                key: building.key.clone(),
+                name: building.name.clone(),
+                count: building.count,
+                cost: building.current_cost().to_f64(),
+                production_per_second: building.total_production(),
+            })
+            .collect();
+        buildings.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+
+        StateSnapshot {
+            points: state.points.to_f64(),
+            lifetime_points: state.lifetime_points.to_f64(),
+            click_power: state.click_power,
+            production_per_second: state.production_per_second(),
+            tier: get_domination_status(state.lifetime_points.to_u64()),
+            paused: state.paused,
+            buildings,
+        }
+    }
+}
+
+pub fn serve(listener: TcpListener, game_state: Arc<Mutex<GameState>>) {
+    for stream in listener.incoming().flatten() {
+        // A misbehaving client only costs its own connection
+        let _ = respond(stream, &game_state);
+    }
+}
+
+fn respond(mut stream: TcpStream, game_state: &Mutex<GameState>) -> std::io::Result<()> {
+    // Drain (and ignore) whatever request the client sent
+    stream.set_read_timeout(Some(Duration::from_millis(200)))?;
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request);
+
+    let body = {
+        let state = game_state.lock().unwrap();
+        serde_json::to_string(&StateSnapshot::of(&state))?
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
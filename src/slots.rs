@@ -0,0 +1,90 @@
+// Named save slots in the save directory
+//
+// Each slot is a file `{dir}/{slot}.save`. The default slot is `game`, so
+// saves from before slots existed show up as a slot of their own.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::save;
+
+pub const DEFAULT_SLOT: &str = "game";
+pub const DEFAULT_SAVE_DIR: &str = "saves";
+
+pub fn slot_path(dir: &Path, slot: &str) -> PathBuf {
+    dir.join(format!("{}.save", slot))
+}
+
+// Slot names of every save in the save directory, sorted
+pub fn list_saves(dir: &Path) -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut slots = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "save") {
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                slots.push(stem.to_string());
+            }
+        }
+    }
+    slots.sort();
+    Ok(slots)
+}
+
+// Slot names end up in file names, so keep them to a safe set of characters
+pub fn valid_slot_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+// The run's profile name, read without loading the whole save
+pub fn profile_name(dir: &Path, slot: &str) -> Option<String> {
+    let contents = fs::read_to_string(slot_path(dir, slot)).ok()?;
+    let value: serde_json::Value = serde_json::from_str(save::unseal(&contents).ok()?).ok()?;
+    value.get("profile_name")?.as_str()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn lists_slots_by_name() {
+        let dir = TempDir::new("list-saves");
+        for file in ["b.save", "a.save", "a.save.1", "notes.txt", "c.save.tmp"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        assert_eq!(list_saves(&dir).unwrap(), vec!["a", "b"]);
+        assert_eq!(list_saves(&dir.join("missing")).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn slot_names_stay_file_safe() {
+        assert_eq!(slot_path(Path::new("saves"), "run-2"), Path::new("saves").join("run-2.save"));
+        assert!(valid_slot_name("run_2-b"));
+        for name in ["", "../game", "a b", "naïve", &"x".repeat(33)] {
+            assert!(!valid_slot_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn reads_the_profile_name_of_a_sealed_save() {
+        let dir = TempDir::new("profile-name");
+        fs::write(slot_path(&dir, "named"), save::seal(r#"{"profile_name": "Innsmouth"}"#)).unwrap();
+        fs::write(slot_path(&dir, "blank"), save::seal(r#"{"profile_name": ""}"#)).unwrap();
+        assert_eq!(profile_name(&dir, "named"), Some("Innsmouth".to_string()));
+        assert_eq!(profile_name(&dir, "blank"), None);
+        assert_eq!(profile_name(&dir, "missing"), None);
+    }
+}
@@ -0,0 +1,35 @@
+// Scratch directories for tests that touch the file system
+//
+// Each test gets a directory of its own under the system's temp dir, named
+// after the test and the process so tests running side by side never share
+// one. Anything left by an earlier run that crashed is cleared first, and the
+// directory is removed again when the TempDir is dropped.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("clickercurse-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
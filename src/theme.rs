@@ -0,0 +1,204 @@
+// Color themes for the terminal UI
+//
+// Draw functions never name a color themselves. They ask the active Theme for
+// the role of what they draw (a title, the follower count, a row that can be
+// afforded, ...), so one preset can restyle every screen. Bold, dim and
+// italic stay with the draw functions, so a theme without colors still keeps
+// its emphasis.
+
+use crossterm::style::{Attribute, Color, ContentStyle, StyledContent};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreset {
+    #[default]
+    Classic,
+    Eldritch,
+    Monochrome,
+}
+
+impl ThemePreset {
+    pub fn next(self) -> Self {
+        match self {
+            ThemePreset::Classic => ThemePreset::Eldritch,
+            ThemePreset::Eldritch => ThemePreset::Monochrome,
+            ThemePreset::Monochrome => ThemePreset::Classic,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        match self {
+            ThemePreset::Classic => ThemePreset::Monochrome,
+            ThemePreset::Eldritch => ThemePreset::Classic,
+            ThemePreset::Monochrome => ThemePreset::Eldritch,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemePreset::Classic => "Classic",
+            ThemePreset::Eldritch => "Eldritch",
+            ThemePreset::Monochrome => "Monochrome",
+        }
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemePreset::Classic => CLASSIC,
+            ThemePreset::Eldritch => ELDRITCH,
+            ThemePreset::Monochrome => MONOCHROME,
+        }
+    }
+}
+
+// A color for each role, None leaving the terminal's own color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub title: Option<Color>,
+    pub points: Option<Color>, // Follower counts and good news
+    pub selected: Option<Color>,
+    pub affordable: Option<Color>,
+    pub owned: Option<Color>, // Purchased artifacts and relics, unlocked achievements
+    pub disabled: Option<Color>, // Out of reach, or only a hint
+    pub heading: Option<Color>,
+    pub warning: Option<Color>,
+    pub accent: Option<Color>, // Shards, the sandbox and the run's name
+    pub footer: Option<Color>,
+    pub ticker: Option<Color>,
+    pub notice: Option<Color>, // Background of banners, drawn in black on it
+    pub tiers: &'static [Color], // Minion names, cheapest first
+    pub banner: &'static [Color], // Cycled by the victory banner
+}
+
+const CLASSIC: Theme = Theme {
+    title: Some(Color::Blue),
+    points: Some(Color::Green),
+    selected: Some(Color::Yellow),
+    affordable: Some(Color::White),
+    owned: Some(Color::Green),
+    disabled: Some(Color::DarkGrey),
+    heading: Some(Color::Yellow),
+    warning: Some(Color::Red),
+    accent: Some(Color::Magenta),
+    footer: Some(Color::Cyan),
+    ticker: Some(Color::DarkCyan),
+    notice: Some(Color::Yellow),
+    // Cheap minions get cool colors, the most expensive ones ominous reds
+    tiers: &[Color::Cyan, Color::Blue, Color::Green, Color::Magenta, Color::Red, Color::DarkRed],
+    banner: &[Color::DarkGreen, Color::Green, Color::DarkCyan, Color::DarkMagenta],
+};
+
+// Sea greens and bruised purples
+const ELDRITCH: Theme = Theme {
+    title: Some(Color::DarkMagenta),
+    points: Some(Color::DarkGreen),
+    selected: Some(Color::Green),
+    affordable: Some(Color::Grey),
+    owned: Some(Color::DarkCyan),
+    disabled: Some(Color::DarkGrey),
+    heading: Some(Color::DarkCyan),
+    warning: Some(Color::DarkRed),
+    accent: Some(Color::Magenta),
+    footer: Some(Color::DarkGreen),
+    ticker: Some(Color::DarkMagenta),
+    notice: Some(Color::DarkCyan),
+    tiers: &[Color::DarkCyan, Color::Cyan, Color::DarkGreen, Color::Green, Color::DarkMagenta, Color::Magenta],
+    banner: &[Color::DarkMagenta, Color::Magenta, Color::DarkGreen, Color::DarkCyan],
+};
+
+const MONOCHROME: Theme = Theme {
+    title: None,
+    points: None,
+    selected: None,
+    affordable: None,
+    owned: None,
+    disabled: None,
+    heading: None,
+    warning: None,
+    accent: None,
+    footer: None,
+    ticker: None,
+    notice: None,
+    tiers: &[],
+    banner: &[],
+};
+
+impl Theme {
+    // Color for the minion at `position` in a list of `count` sorted by cost
+    pub fn tier(&self, position: usize, count: usize) -> Option<Color> {
+        self.tiers.get(position * self.tiers.len() / count.max(1)).copied()
+    }
+
+    // A banner in black on the notice color, or in reverse video without one
+    pub fn notice<D: Display>(&self, content: D) -> StyledContent<D> {
+        let mut style = ContentStyle::new();
+        match self.notice {
+            Some(background) => {
+                style.foreground_color = Some(Color::Black);
+                style.background_color = Some(background);
+            },
+            None => style.attributes.set(Attribute::Reverse),
+        }
+        StyledContent::new(style, content)
+    }
+
+    // Color of the victory banner `step` changes after the first
+    pub fn banner(&self, step: usize) -> Option<Color> {
+        self.banner.get(step % self.banner.len().max(1)).copied()
+    }
+}
+
+// `content` in `color`, or unstyled for None
+pub fn colored<D: Display>(content: D, color: Option<Color>) -> StyledContent<D> {
+    let style = ContentStyle { foreground_color: color, ..ContentStyle::new() };
+    StyledContent::new(style, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monochrome_has_no_colors() {
+        let theme = ThemePreset::Monochrome.theme();
+        let roles = [
+            theme.title, theme.points, theme.selected, theme.affordable, theme.owned, theme.disabled,
+            theme.heading, theme.warning, theme.accent, theme.footer, theme.ticker, theme.notice,
+        ];
+        assert!(roles.iter().all(Option::is_none));
+        assert_eq!(theme.tier(0, 7), None);
+        assert_eq!(theme.banner(3), None);
+
+        let styled = colored("followers", theme.points);
+        assert_eq!(*styled.style(), ContentStyle::new());
+        // Banners still stand out, in reverse video
+        assert!(theme.notice("saved").style().attributes.has(Attribute::Reverse));
+    }
+
+    #[test]
+    fn tiers_spread_over_the_sorted_minions() {
+        let theme = ThemePreset::Classic.theme();
+        let tiers: Vec<_> = (0..7).map(|position| theme.tier(position, 7).unwrap()).collect();
+        assert_eq!(tiers.first(), Some(&Color::Cyan));
+        assert_eq!(tiers.last(), Some(&Color::DarkRed));
+        assert_eq!(theme.tier(0, 0), Some(Color::Cyan));
+        assert_eq!(theme.banner(4), theme.banner(0));
+
+        let notice = theme.notice("saved");
+        assert_eq!(notice.style().background_color, Some(Color::Yellow));
+        assert_eq!(notice.style().foreground_color, Some(Color::Black));
+    }
+
+    #[test]
+    fn presets_cycle_both_ways() {
+        let mut preset = ThemePreset::default();
+        for _ in 0..3 {
+            assert_eq!(preset.next().previous(), preset);
+            preset = preset.next();
+        }
+        assert_eq!(preset, ThemePreset::Classic);
+        assert_eq!(ThemePreset::Eldritch.name(), "Eldritch");
+    }
+}
@@ -0,0 +1,94 @@
+// News ticker: a rotating line of flavor at the bottom of the Sanctum
+//
+// Each line is a template whose {placeholders} are filled from the view when
+// it is shown. A line whose placeholder has no value right now (a minion the
+// player doesn't own, no recent golden follower) is skipped until it does.
+// To extend the ticker, add a line to templates().
+
+use std::time::{Duration, Instant};
+
+use clickercurse_game::numbers::{format_number, format_rate};
+use clickercurse_game::view::ViewModel;
+
+// How long each line stays before the next one
+const ROTATE_EVERY: Duration = Duration::from_secs(6);
+
+// A golden follower counts as recently sighted for this many seconds
+const RECENT_SIGHTING_SECS: f64 = 300.0;
+
+fn templates() -> Vec<String> {
+    [
+        "Strange lights are reported over the Pacific",
+        "Dreams of a sunken city trouble the artists of the world",
+        "Scholars at Miskatonic University deny any connection to recent disappearances",
+        "Fishermen of Innsmouth report record catches, decline to elaborate",
+        "The stars are slowly coming right",
+        "You now command {followers} followers",
+        "{converts} souls have heard the call so far",
+        "You now command {cultists} Cultists",
+        "Your {top_minion} minions now number {top_count}",
+        "The faithful gain {rate} followers each second",
+        "A golden follower was recently sighted, {golden_ago} seconds ago",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+pub struct Ticker {
+    templates: Vec<String>,
+    started: Instant,
+}
+
+impl Ticker {
+    pub fn new() -> Self {
+        Ticker { templates: templates(), started: Instant::now() }
+    }
+
+    // The line to show now, cut to the terminal width so it never wraps
+    pub fn line(&self, view: &ViewModel, width: u16) -> Option<String> {
+        let lines: Vec<String> = self.templates.iter()
+            .filter_map(|template| fill(template, view))
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+
+        let step = (self.started.elapsed().as_secs() / ROTATE_EVERY.as_secs()) as usize;
+        Some(lines[step % lines.len()].chars().take(width as usize).collect())
+    }
+}
+
+// Replaces each {placeholder} with its live value, or None when one has no value
+fn fill(template: &str, view: &ViewModel) -> Option<String> {
+    let mut line = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let close = open + rest[open..].find('}')?;
+        line.push_str(&rest[..open]);
+        line.push_str(&placeholder(&rest[open + 1..close], view)?);
+        rest = &rest[close + 1..];
+    }
+    line.push_str(rest);
+    Some(line)
+}
+
+fn placeholder(name: &str, view: &ViewModel) -> Option<String> {
+    let top_minion = view.buildings.iter()
+        .filter(|building| building.count > 0)
+        .max_by_key(|building| building.count);
+    match name {
+        "followers" => Some(format_number(view.points, view.number_format)),
+        "converts" if !view.lifetime_points.is_zero() => Some(format_number(view.lifetime_points, view.number_format)),
+        "cultists" => view.buildings.iter()
+            .find(|building| building.name == "Cultist" && building.count > 0)
+            .map(|building| building.count.to_string()),
+        "top_minion" => top_minion.map(|building| building.name.clone()),
+        "top_count" => top_minion.map(|building| building.count.to_string()),
+        "rate" if view.production_per_second > 0.0 => Some(format_rate(view.production_per_second, view.number_format)),
+        "golden_ago" => view.golden_sighted_ago
+            .filter(|ago| *ago < RECENT_SIGHTING_SECS)
+            .map(|ago| format!("{:.0}", ago)),
+        _ => None,
+    }
+}
@@ -0,0 +1,266 @@
+// Everything the draw functions need, copied out of GameState in one go
+//
+// The main loop captures a ViewModel while holding the lock and renders from
+// it afterwards, so a frame never mixes values from before and after a tick.
+
+use crate::bignum::BigNum;
+use crate::numbers::NumberFormat;
+use crate::rebirth;
+use crate::theme::{Theme, ThemePreset};
+use crate::{BuffKind, BuildingSort, GameState, GoldenEvent, Menu, Milestone, Prompt, PurchaseRecord, Stats};
+
+// Combined effect of the active buffs of one kind
+#[derive(Clone, Debug)]
+pub struct BuffView {
+    pub multiplier: f64,
+    pub remaining: f64, // Seconds until the last one wears off
+}
+
+#[derive(Clone, Debug)]
+pub struct BuildingView {
+    pub name: String,
+    pub count: u64,
+    pub cost: BigNum,
+    pub bulk_cost: BigNum, // For the next buy_amount purchases
+    pub max_affordable: u64, // How many one buy-max would summon
+    pub production: f64,
+    pub effective_production: f64, // With upgrades applied
+    pub clicks_per_second: Option<f64>, // Made by all of them, for auto-clickers
+    pub multiplier: f64, // From artifacts, shards, relics, achievements and buffs
+    pub efficiency: Option<f64>, // Price per follower per second, None without production
+    pub affordable: bool,
+    pub tier: usize, // Position in GameState::buildings, which picks the name's color
+    pub advised: bool, // The best value to summon next, see GameState::best_buy
+}
+
+#[derive(Clone, Debug)]
+pub struct UpgradeView {
+    pub name: String,
+    pub description: String,
+    pub cost: u64,
+    pub purchased: bool,
+    pub affordable: bool,
+    pub produced_at_purchase: Option<u64>,
+    pub gain: f64, // Production per second it would add
+    pub empowers_minions: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct PrestigeUpgradeView {
+    pub name: String,
+    pub description: String,
+    pub cost: u64,
+    pub purchased: bool,
+    pub affordable: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct SkillView {
+    pub name: String,
+    pub description: String,
+    pub cost: u64,
+    pub requires: Vec<&'static str>, // Names of the nodes needed first
+    pub owned: bool,
+    pub unlocked: bool, // Every node it requires is owned
+    pub affordable: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct AchievementView {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub unlocked: bool,
+    pub progress: (u64, u64), // Current and target
+    pub reward_multiplier: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ViewModel {
+    pub menu: Menu,
+    pub selected_index: usize,
+    pub scroll_offset: usize,
+    pub points: BigNum,
+    pub lifetime_points: BigNum,
+    pub produced_points: u64,
+    pub click_power: u64,
+    pub production_per_second: f64,
+    pub click_rate: f64, // Recent points per second from clicking
+    pub paused: bool,
+    pub playtime_secs: u64,
+    pub io_in_progress: bool,
+    pub sandbox: bool,
+    pub sandbox_unlocked: bool,
+    pub sandbox_tainted: bool,
+    pub reserve: BigNum,
+    pub pending_upgrade: Option<usize>,
+    pub upgrade_lines: u16,
+    pub buy_amount: u8,
+    pub number_format: NumberFormat,
+    pub theme_preset: ThemePreset,
+    pub theme: Theme,
+    pub autosave: bool,
+    pub autosave_secs: u64,
+    pub tick_ms: u64,
+    pub mouse: bool,
+    pub paused_clicks: bool,
+    pub sound: bool,
+    pub achievement_bonus: bool,
+    pub profile_name: String,
+    pub prompt: Option<Prompt>,
+    pub confirmation: Option<&'static str>, // The question awaiting y or n
+    pub export_text: Option<String>, // Only filled for the Export menu
+    pub offline_summary: Option<String>,
+    pub building_sort: BuildingSort,
+    pub buildings: Vec<BuildingView>, // In display order, see GameState::sorted_building_keys
+    pub upgrades: Vec<UpgradeView>,
+    pub prestige_upgrades: Vec<PrestigeUpgradeView>,
+    pub skills: Vec<SkillView>, // Every node of the rebirth skill tree
+    pub rebirth_tokens: u64,
+    pub achievements: Vec<AchievementView>,
+    pub achievement_multiplier: f64,
+    pub purchase_history: Vec<PurchaseRecord>, // Only filled for the Annals menu
+    pub milestones: Vec<Milestone>,
+    pub stats: Stats,
+    pub shards: u64,
+    pub notification: Option<String>,
+    pub golden: Option<GoldenEvent>,
+    pub golden_sighted_ago: Option<f64>,
+    pub production_buff: Option<BuffView>,
+    pub click_buff: Option<BuffView>,
+    pub seed: Option<u64>, // Only filled in debug mode
+}
+
+impl ViewModel {
+    pub fn capture(state: &GameState) -> Self {
+        let best_buy = state.best_buy();
+
+        ViewModel {
+            menu: state.current_menu.clone(),
+            selected_index: state.selected_index,
+            scroll_offset: state.scroll_offset,
+            points: state.points,
+            lifetime_points: state.lifetime_points,
+            produced_points: state.produced_points,
+            click_power: state.click_power,
+            production_per_second: state.production_per_second(),
+            click_rate: state.click_rate(),
+            paused: state.paused,
+            playtime_secs: state.playtime_secs,
+            io_in_progress: state.io_in_progress,
+            sandbox: state.sandbox,
+            sandbox_unlocked: state.sandbox_unlocked(),
+            sandbox_tainted: state.sandbox_tainted,
+            reserve: state.reserve,
+            pending_upgrade: state.pending_upgrade,
+            upgrade_lines: state.upgrade_lines,
+            buy_amount: state.buy_amount,
+            number_format: state.number_format,
+            theme_preset: state.theme,
+            theme: state.theme.theme(),
+            autosave: state.autosave,
+            autosave_secs: state.autosave_secs,
+            tick_ms: state.tick_ms,
+            mouse: state.mouse,
+            paused_clicks: state.paused_clicks,
+            sound: state.sound,
+            achievement_bonus: state.achievement_bonus,
+            profile_name: state.profile_name.clone(),
+            prompt: state.prompt.clone(),
+            confirmation: state.confirmation.as_ref().map(|confirmation| confirmation.question()),
+            export_text: if state.current_menu == Menu::Export {
+                state.export_text.clone()
+            } else {
+                None
+            },
+            offline_summary: state.offline_summary.clone(),
+            building_sort: state.building_sort,
+            buildings: state.sorted_building_keys().iter()
+                .filter_map(|key| Some((key, state.buildings.iter().position(|building| building.key == *key)?)))
+                .map(|(key, tier)| (key, tier, &state.buildings[tier]))
+                .map(|(key, tier, building)| BuildingView {
+                    name: building.name.clone(),
+                    count: building.count,
+                    cost: building.current_cost(),
+                    bulk_cost: building.cost_for_n(state.buy_amount as u64),
+                    max_affordable: building.max_affordable(state.points - state.reserve),
+                    production: building.total_production(),
+                    effective_production: state.effective_production(key),
+                    clicks_per_second: building.is_auto_clicker().then(|| building.total_clicks_per_second()),
+                    multiplier: state.building_multiplier(key),
+                    efficiency: state.building_efficiency(key),
+                    affordable: state.can_spend(building.current_cost()),
+                    advised: best_buy.as_ref() == Some(key),
+                    tier,
+                })
+                .collect(),
+            upgrades: state.upgrades.iter()
+                .map(|upgrade| UpgradeView {
+                    name: upgrade.name.clone(),
+                    description: upgrade.description.clone(),
+                    cost: upgrade.cost,
+                    purchased: upgrade.purchased,
+                    affordable: !upgrade.purchased && (state.sandbox || state.points >= BigNum::from(upgrade.cost)),
+                    produced_at_purchase: upgrade.produced_at_purchase,
+                    gain: state.upgrade_gain(upgrade),
+                    empowers_minions: upgrade.building_multiplier.is_some(),
+                })
+                .collect(),
+            prestige_upgrades: state.prestige_upgrades.iter()
+                .map(|relic| PrestigeUpgradeView {
+                    name: relic.name.clone(),
+                    description: relic.description.clone(),
+                    cost: relic.cost,
+                    purchased: relic.purchased,
+                    affordable: !relic.purchased && (state.sandbox || state.shards >= relic.cost),
+                })
+                .collect(),
+            skills: rebirth::NODES.iter()
+                .map(|node| {
+                    let owned = state.skills.iter().any(|key| key == node.key);
+                    let unlocked = rebirth::unlocked(node, &state.skills);
+                    SkillView {
+                        name: node.name.to_string(),
+                        description: node.description.to_string(),
+                        cost: node.cost,
+                        requires: node.requires.iter().filter_map(|key| rebirth::node(key)).map(|node| node.name).collect(),
+                        owned,
+                        unlocked,
+                        affordable: !owned && unlocked && (state.sandbox || state.rebirth_tokens >= node.cost),
+                    }
+                })
+                .collect(),
+            achievements: state.achievements.iter()
+                .map(|achievement| AchievementView {
+                    name: achievement.name,
+                    description: achievement.description,
+                    unlocked: achievement.unlocked,
+                    progress: (achievement.progress)(state),
+                    reward_multiplier: achievement.reward_multiplier,
+                })
+                .collect(),
+            achievement_multiplier: state.achievement_multiplier(),
+            purchase_history: if state.current_menu == Menu::History {
+                state.purchase_history.iter().cloned().collect()
+            } else {
+                Vec::new()
+            },
+            milestones: state.milestones.clone(),
+            stats: state.stats,
+            shards: state.shards,
+            rebirth_tokens: state.rebirth_tokens,
+            notification: state.notifications.front().map(|notification| notification.text.clone()),
+            golden: state.golden,
+            golden_sighted_ago: state.golden_sighted_ago,
+            production_buff: buff_view(state, BuffKind::Production),
+            click_buff: buff_view(state, BuffKind::Click),
+            seed: state.debug_mode.then(|| state.rng.seed()),
+        }
+    }
+}
+
+fn buff_view(state: &GameState, kind: BuffKind) -> Option<BuffView> {
+    state.buff_remaining(kind).map(|remaining| BuffView {
+        multiplier: state.buff_multiplier(kind),
+        remaining,
+    })
+}
@@ -0,0 +1,34 @@
+// Content packs merged through the library's public API, as the game does at
+// startup
+
+use clickercurse_game::content::Content;
+use std::fs;
+
+#[path = "../src/testing.rs"]
+mod testing;
+
+use testing::TempDir;
+
+#[test]
+fn two_packs_merge_and_can_use_each_others_minions() {
+    let dir = TempDir::new("packs-merge");
+    fs::write(dir.join("a.pack.json"), r#"{"buildings": [
+        {"key": "crypt", "name": "Crypt", "base_cost": 50000, "base_production": 100.0, "cost_multiplier": 1.15},
+        {"key": "cursor", "name": "Impostor", "base_cost": 1, "base_production": 1.0, "cost_multiplier": 1.15}
+    ]}"#).unwrap();
+    // The artifact names the crypt from the other pack
+    fs::write(dir.join("b.pack.json"), r#"{
+        "upgrades": [{"name": "Bone Keys", "description": "Crypts twice as deep", "cost": 500000, "building_multiplier": ["crypt", 2.0], "click_multiplier": null}],
+        "relics": [{"name": "Grave Dust", "description": "+2 influence power", "cost": 3, "production_bonus": null, "click_bonus": 2}]
+    }"#).unwrap();
+    fs::write(dir.join("notes.json"), "not a pack").unwrap();
+
+    let mut content = Content::default();
+    let warnings = content.add_packs(&dir);
+    assert_eq!(warnings, ["Left out the minion 'cursor' from the pack a.pack.json: the key is already used"]);
+    assert_eq!((content.buildings.len(), content.upgrades.len(), content.relics.len()), (8, 8, 6));
+    // Still cheapest first, the crypt between the colony and the temple
+    let keys: Vec<&str> = content.buildings.iter().map(|building| building.key.as_str()).collect();
+    assert_eq!(keys, ["cursor", "grandma", "idol", "farm", "mine", "crypt", "temple", "portal"]);
+    assert_eq!(content.buildings[0].name, "Cultist");
+}